@@ -1,3 +1,5 @@
 fn main() {
-    tauri_build::build()
+    // Tauri's default manifest, plus opting in to long paths
+    let windows = tauri_build::WindowsAttributes::new().app_manifest(include_str!("windows-app-manifest.xml"));
+    tauri_build::try_build(tauri_build::Attributes::new().windows_attributes(windows)).expect("failed to run tauri-build");
 }
@@ -0,0 +1,126 @@
+// Completion alerts for people away from the machine, such as a producer
+// waiting on an on-set offload: a JSON webhook and an optional email, sent
+// when each import finishes
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+use crate::config;
+use crate::error::CamPorterError;
+use crate::import::ImportReport;
+
+const ALERTS_KEY: &str = "completion_alerts";
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CompletionAlerts {
+    /// URL that receives the summary as a JSON POST
+    pub webhook_url: Option<String>,
+    pub smtp: Option<SmtpSettings>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    /// Defaults to 465 for implicit TLS, 587 for STARTTLS
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub starttls: bool,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: String,
+}
+
+/// What the webhook receives
+#[derive(Serialize)]
+struct CompletionSummary {
+    /// `import_completed` or `import_failed`
+    event: &'static str,
+    target_path: String,
+    report: Option<ImportReport>,
+    error: Option<String>,
+}
+
+fn post_webhook(url: &str, summary: &CompletionSummary) -> Result<(), String> {
+    let body = serde_json::to_string(summary).map_err(|e| e.to_string())?;
+    ureq::post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&body)
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn send_email(smtp: &SmtpSettings, summary: &CompletionSummary) -> Result<(), String> {
+    let (subject, outcome) = match (&summary.report, &summary.error) {
+        (Some(report), _) => (format!("Import complete: {}", report.summary), report.summary.clone()),
+        (None, error) => ("Import failed".to_string(), error.clone().unwrap_or_default()),
+    };
+    let body = format!("Destination: {}\n\n{}\n", summary.target_path, outcome);
+
+    let email = Message::builder()
+        .from(smtp.from.parse().map_err(|e| format!("Invalid sender address: {}", e))?)
+        .to(smtp.to.parse().map_err(|e| format!("Invalid recipient address: {}", e))?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)
+        .map_err(|e| e.to_string())?;
+
+    let builder = if smtp.starttls {
+        SmtpTransport::starttls_relay(&smtp.host)
+    } else {
+        SmtpTransport::relay(&smtp.host)
+    }
+    .map_err(|e| e.to_string())?;
+    let mut builder = builder.port(smtp.port.unwrap_or(if smtp.starttls { 587 } else { 465 }));
+    if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+        builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+    builder.build().send(&email).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Send the configured alerts for a finished import, in the background
+pub fn import_finished(app: &tauri::AppHandle, target_path: &str, result: &Result<ImportReport, CamPorterError>) {
+    let alerts: CompletionAlerts = config::get(app, ALERTS_KEY).ok().flatten().unwrap_or_default();
+    let webhook_url = alerts.webhook_url.filter(|u| !u.is_empty());
+    if webhook_url.is_none() && alerts.smtp.is_none() {
+        return;
+    }
+
+    let summary = CompletionSummary {
+        event: if result.is_ok() { "import_completed" } else { "import_failed" },
+        target_path: target_path.to_string(),
+        report: result.as_ref().ok().cloned(),
+        error: result.as_ref().err().map(|e| e.to_string()),
+    };
+    thread::spawn(move || {
+        if let Some(url) = webhook_url {
+            match post_webhook(&url, &summary) {
+                Ok(()) => info!("Sent completion webhook to {}", url),
+                Err(e) => warn!("Completion webhook to {} failed: {}", url, e),
+            }
+        }
+        if let Some(smtp) = alerts.smtp {
+            match send_email(&smtp, &summary) {
+                Ok(()) => info!("Sent completion email to {}", smtp.to),
+                Err(e) => warn!("Completion email to {} failed: {}", smtp.to, e),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_completion_alerts(app: tauri::AppHandle) -> Result<CompletionAlerts, CamPorterError> {
+    Ok(config::get(&app, ALERTS_KEY)?.unwrap_or_default())
+}
+
+/// Save the alert settings. The SMTP password is kept in the settings file
+/// as given.
+#[tauri::command]
+pub fn set_completion_alerts(alerts: CompletionAlerts, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    Ok(config::set(&app, ALERTS_KEY, &alerts)?)
+}
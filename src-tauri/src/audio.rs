@@ -0,0 +1,179 @@
+// Processing field recordings once they're imported: converting WAV/MP3 from
+// audio recorders (to FLAC, say) and normalizing their loudness with ffmpeg's
+// loudnorm filter. Runs in the background after the copy, like time-lapse
+// previews, so the card can be pulled as soon as the import finishes.
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use tauri::Emitter;
+
+use crate::error::CamPorterError;
+use crate::{config, ffmpeg, is_audio_extension, watchdog};
+
+const AUDIO_KEY: &str = "audio_processing";
+/// Used when ffprobe can't tell the recording's rate
+const DEFAULT_SAMPLE_RATE: u32 = 48_000;
+/// True-peak ceiling and loudness range handed to loudnorm
+const TRUE_PEAK_DB: f64 = -1.5;
+const LOUDNESS_RANGE: f64 = 11.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioFormat {
+    Flac,
+    Wav,
+}
+
+impl AudioFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+        }
+    }
+
+    fn codec(self) -> &'static str {
+        match self {
+            AudioFormat::Flac => "flac",
+            // loudnorm hands back floats; 24-bit keeps a recorder's depth
+            AudioFormat::Wav => "pcm_s24le",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AudioProcessing {
+    /// Format to convert recordings to; None keeps each file's own
+    pub convert_to: Option<AudioFormat>,
+    /// Integrated loudness to normalize to, e.g. -16 LUFS, or -23 for broadcast
+    pub target_lufs: Option<f64>,
+    /// Drop the imported original once its processed version is written,
+    /// instead of keeping both
+    pub replace_original: bool,
+}
+
+impl AudioProcessing {
+    fn is_enabled(&self) -> bool {
+        self.convert_to.is_some() || self.target_lufs.is_some()
+    }
+}
+
+fn sample_rate(path: &Path) -> u32 {
+    let Some(ffprobe) = ffmpeg::ffprobe_path() else {
+        return DEFAULT_SAMPLE_RATE;
+    };
+    let mut command = Command::new(ffprobe);
+    command
+        .args(["-v", "error", "-select_streams", "a:0", "-show_entries", "stream=sample_rate", "-of", "csv=p=0"])
+        .arg(path);
+    watchdog::output(&mut command, watchdog::PROBE_TIMEOUT)
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8_lossy(&o.stdout).trim().parse().ok())
+        .unwrap_or(DEFAULT_SAMPLE_RATE)
+}
+
+/// Where a recording's processed version goes: next to it, with the new
+/// format's extension, or a `_normalized` suffix when that's its own name
+fn output_path(path: &Path, extension: &str, replace_original: bool) -> PathBuf {
+    let output = path.with_extension(extension);
+    if output != path || replace_original {
+        return output;
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}_normalized.{}", stem, extension))
+}
+
+/// Convert and/or normalize one recording, returning the processed file
+pub fn process(path: &Path, settings: &AudioProcessing) -> Result<PathBuf, String> {
+    let ffmpeg_cmd = ffmpeg::ffmpeg_path().ok_or("FFmpeg not found")?;
+    let extension = match settings.convert_to {
+        Some(format) => format.extension().to_string(),
+        None => path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase(),
+    };
+    let unchanged = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case(&extension));
+    if unchanged && settings.target_lufs.is_none() {
+        return Ok(path.to_path_buf());
+    }
+    let output = output_path(path, &extension, settings.replace_original);
+    // Written under a hidden name first, keeping the extension for ffmpeg
+    let partial = output.with_file_name(format!(".{}", output.file_name().unwrap_or_default().to_string_lossy()));
+
+    let mut command = Command::new(ffmpeg_cmd);
+    command.args(["-y", "-loglevel", "error", "-i"]).arg(path).args(["-map", "0:a", "-map_metadata", "0"]);
+    if let Some(lufs) = settings.target_lufs {
+        // loudnorm works at 192 kHz internally, so resample back afterwards
+        let filter = format!("loudnorm=I={}:TP={}:LRA={},aresample={}", lufs, TRUE_PEAK_DB, LOUDNESS_RANGE, sample_rate(path));
+        command.args(["-af", &filter]);
+    }
+    match settings.convert_to {
+        Some(format) => command.args(["-c:a", format.codec()]),
+        // Normalizing an MP3 means re-encoding it; keep it near transparent
+        None if extension == "mp3" => command.args(["-c:a", "libmp3lame", "-q:a", "0"]),
+        None => command.args(["-c:a", AudioFormat::Wav.codec()]),
+    };
+    command.arg(&partial);
+
+    let result = watchdog::output(&mut command, watchdog::ENCODE_TIMEOUT).map_err(|e| e.to_string());
+    match result {
+        Ok(o) if o.status.success() => {}
+        Ok(o) => {
+            let _ = fs::remove_file(&partial);
+            return Err(format!("FFmpeg failed: {}", String::from_utf8_lossy(&o.stderr).trim()));
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    }
+
+    if settings.replace_original && output != path {
+        fs::remove_file(path).map_err(|e| format!("Failed to remove original: {}", e))?;
+    }
+    fs::rename(&partial, &output).map_err(|e| e.to_string())?;
+    Ok(output)
+}
+
+/// Process the recordings among freshly imported files in the background,
+/// announcing each one as `audio-processed`
+pub fn process_imported(app: &tauri::AppHandle, imported: &[String]) {
+    let settings: AudioProcessing = config::get(app, AUDIO_KEY).ok().flatten().unwrap_or_default();
+    if !settings.is_enabled() {
+        return;
+    }
+    let recordings: Vec<PathBuf> = imported
+        .iter()
+        .map(PathBuf::from)
+        .filter(|p| p.extension().and_then(|e| e.to_str()).is_some_and(|e| is_audio_extension(&e.to_lowercase())))
+        .collect();
+    if recordings.is_empty() {
+        return;
+    }
+
+    let app = app.clone();
+    thread::spawn(move || {
+        for recording in recordings {
+            match process(&recording, &settings) {
+                Ok(output) => {
+                    info!("Processed {} into {}", recording.display(), output.display());
+                    let _ = app.emit("audio-processed", output.to_string_lossy().to_string());
+                }
+                Err(e) => warn!("Failed to process {}: {}", recording.display(), e),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_audio_processing(app: tauri::AppHandle) -> Result<AudioProcessing, CamPorterError> {
+    Ok(config::get(&app, AUDIO_KEY)?.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn set_audio_processing(settings: AudioProcessing, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    Ok(config::set(&app, AUDIO_KEY, &settings)?)
+}
@@ -0,0 +1,129 @@
+// The import log left in the destination for audit trails on client work:
+// a readable import-YYYYMMDD-HHMMSS.log listing every file copied, skipped or
+// failed with its size and checksum, and a JSON twin of it for tooling
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{humanize, longpath};
+use crate::import::ImportReport;
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Copied,
+    Skipped,
+    Failed,
+}
+
+/// One file's line in the log
+#[derive(Serialize, Clone)]
+pub struct LogEntry {
+    pub source: String,
+    pub target: Option<String>,
+    pub outcome: Outcome,
+    pub size: Option<u64>,
+    /// xxh3-64 of the verified copy, in hex
+    pub checksum: Option<String>,
+    /// Why the file was skipped or failed
+    pub reason: Option<String>,
+}
+
+impl LogEntry {
+    pub fn copied(source: &Path, target: &Path, size: u64, hash: Option<u64>) -> Self {
+        LogEntry {
+            source: source.to_string_lossy().to_string(),
+            target: Some(longpath::display(target)),
+            outcome: Outcome::Copied,
+            size: Some(size),
+            checksum: hash.map(|h| format!("{:016x}", h)),
+            reason: None,
+        }
+    }
+
+    pub fn skipped(source: &Path, size: u64, reason: &str) -> Self {
+        LogEntry {
+            source: source.to_string_lossy().to_string(),
+            target: None,
+            outcome: Outcome::Skipped,
+            size: Some(size),
+            checksum: None,
+            reason: Some(reason.to_string()),
+        }
+    }
+
+    pub fn failed(source: &Path, reason: &str) -> Self {
+        LogEntry {
+            source: source.to_string_lossy().to_string(),
+            target: None,
+            outcome: Outcome::Failed,
+            size: fs::metadata(source).map(|m| m.len()).ok(),
+            checksum: None,
+            reason: Some(reason.to_string()),
+        }
+    }
+
+    fn line(&self) -> String {
+        let outcome = match self.outcome {
+            Outcome::Copied => "COPIED",
+            Outcome::Skipped => "SKIPPED",
+            Outcome::Failed => "FAILED",
+        };
+        let size = self.size.map(humanize::bytes).unwrap_or_default();
+        let mut line = format!("{:<8} {:>10}  {:<16}  {}", outcome, size, self.checksum.as_deref().unwrap_or(""), self.source);
+        if let Some(target) = &self.target {
+            line += &format!(" -> {}", target);
+        }
+        if let Some(reason) = &self.reason {
+            line += &format!(" ({})", reason);
+        }
+        line
+    }
+}
+
+#[derive(Serialize)]
+struct LogFile<'a> {
+    started: String,
+    destination: &'a str,
+    summary: &'a str,
+    copied: usize,
+    skipped: usize,
+    failed: usize,
+    bytes: u64,
+    entries: &'a [LogEntry],
+}
+
+/// Write the log and its JSON twin into `destination`, returning the log's path
+pub fn write(destination: &str, started: DateTime<Local>, report: &ImportReport) -> Result<PathBuf, String> {
+    let stem = format!("import-{}", started.format("%Y%m%d-%H%M%S"));
+    let dir = Path::new(destination);
+
+    let mut text = format!(
+        "CamPorter import log\nStarted:     {}\nDestination: {}\nResult:      {}\n\n",
+        started.format("%Y-%m-%d %H:%M:%S"),
+        destination,
+        report.summary
+    );
+    for entry in &report.log {
+        text += &entry.line();
+        text.push('\n');
+    }
+    let log_path = dir.join(format!("{}.log", stem));
+    fs::write(&log_path, text).map_err(|e| format!("Failed to write {}: {}", log_path.display(), e))?;
+
+    let json = LogFile {
+        started: started.to_rfc3339(),
+        destination,
+        summary: &report.summary,
+        copied: report.copied,
+        skipped: report.skipped,
+        failed: report.failed,
+        bytes: report.bytes,
+        entries: &report.log,
+    };
+    let json_path = dir.join(format!("{}.json", stem));
+    let content = serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?;
+    fs::write(&json_path, content).map_err(|e| format!("Failed to write {}: {}", json_path.display(), e))?;
+    Ok(log_path)
+}
@@ -0,0 +1,133 @@
+// Opt-in per-stage timings for the copy pipeline, turned into a report of
+// where an import spends its time, for tuning buffer sizes and concurrency
+// to a particular card reader and destination
+use log::info;
+use serde::Serialize;
+use std::time::Duration;
+use ts_rs::TS;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Reading from the source; also covers kernel copies and clones, which
+    /// read and write in one call
+    Read,
+    Write,
+    Hash,
+    Fsync,
+    /// Timestamps, permissions, truncation and the final rename
+    Metadata,
+}
+
+const STAGES: [Stage; 5] = [Stage::Read, Stage::Write, Stage::Hash, Stage::Fsync, Stage::Metadata];
+
+/// Time spent in each stage, in microseconds
+#[derive(Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct StageTimes {
+    pub read_us: u64,
+    pub write_us: u64,
+    pub hash_us: u64,
+    pub fsync_us: u64,
+    pub metadata_us: u64,
+}
+
+impl StageTimes {
+    fn get(&self, stage: Stage) -> u64 {
+        match stage {
+            Stage::Read => self.read_us,
+            Stage::Write => self.write_us,
+            Stage::Hash => self.hash_us,
+            Stage::Fsync => self.fsync_us,
+            Stage::Metadata => self.metadata_us,
+        }
+    }
+
+    fn add(&mut self, stage: Stage, elapsed: Duration) {
+        let us = elapsed.as_micros() as u64;
+        match stage {
+            Stage::Read => self.read_us += us,
+            Stage::Write => self.write_us += us,
+            Stage::Hash => self.hash_us += us,
+            Stage::Fsync => self.fsync_us += us,
+            Stage::Metadata => self.metadata_us += us,
+        }
+    }
+
+    fn total(&self) -> u64 {
+        STAGES.iter().map(|&s| self.get(s)).sum()
+    }
+}
+
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct FileTimings {
+    pub path: String,
+    pub bytes: u64,
+    #[serde(flatten)]
+    pub times: StageTimes,
+}
+
+/// Where the time went across a whole import
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct BenchmarkReport {
+    pub buffer_size: usize,
+    pub files: Vec<FileTimings>,
+    pub totals: StageTimes,
+    /// Stage that took the largest share of the time
+    pub bottleneck: Stage,
+    /// Share of the measured time spent in the bottleneck, 0 to 100
+    pub bottleneck_percent: u8,
+    pub advice: String,
+}
+
+/// Timings collected while an import runs
+#[derive(Default)]
+pub struct Benchmark {
+    current: StageTimes,
+    files: Vec<FileTimings>,
+}
+
+impl Benchmark {
+    pub fn add(&mut self, stage: Stage, elapsed: Duration) {
+        self.current.add(stage, elapsed);
+    }
+
+    /// Close off the timings gathered since the last file, retries included
+    pub fn finish_file(&mut self, path: &str, bytes: u64) {
+        let times = std::mem::take(&mut self.current);
+        self.files.push(FileTimings { path: path.to_string(), bytes, times });
+    }
+
+    pub fn report(self, buffer_size: usize) -> BenchmarkReport {
+        let mut totals = StageTimes::default();
+        for file in &self.files {
+            for stage in STAGES {
+                totals.add(stage, Duration::from_micros(file.times.get(stage)));
+            }
+        }
+
+        let bottleneck = STAGES.into_iter().max_by_key(|&s| totals.get(s)).unwrap_or(Stage::Read);
+        let bottleneck_percent = (totals.get(bottleneck) * 100 / totals.total().max(1)) as u8;
+        let advice = match bottleneck {
+            Stage::Read => "The card or reader limits throughput; a faster reader or port would help more than any setting",
+            Stage::Write => "The destination limits throughput; a larger buffer may help on network shares, a faster disk elsewhere",
+            Stage::Hash => "Hashing limits throughput, so the CPU is the bottleneck",
+            Stage::Fsync => "Flushing to disk dominates; the destination may be a network share or have little write cache",
+            Stage::Metadata => "Per-file overhead dominates, as with many small files; copying several files at once would help",
+        }
+        .to_string();
+        info!("Benchmark over {} files: {:?} took {}% of the time", self.files.len(), bottleneck, bottleneck_percent);
+
+        BenchmarkReport {
+            buffer_size,
+            files: self.files,
+            totals,
+            bottleneck,
+            bottleneck_percent,
+            advice,
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// Catalog of every file imported (source, card, destination, content hash),
+// kept in an SQLite database in app data. It answers "did I already offload
+// this card?" and recognises files already imported to any destination.
+use log::warn;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use crate::error::CamPorterError;
+
+#[derive(Serialize, Clone)]
+pub struct CatalogEntry {
+    pub source_path: String,
+    pub card_id: Option<String>,
+    pub destination: String,
+    /// xxh3 as hex, since JavaScript numbers can't hold every u64
+    pub hash: String,
+    pub size: u64,
+    pub imported: u64, // Unix timestamp
+}
+
+/// Filter for `query_import_history`; every field is optional
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct HistoryFilter {
+    pub card_id: Option<String>,
+    /// Substring of the source path
+    pub source_path: Option<String>,
+    /// Substring of the destination path
+    pub destination: Option<String>,
+    pub hash: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub limit: Option<u32>,
+}
+
+/// The database is opened on first use
+#[derive(Default)]
+pub struct Catalog {
+    conn: Mutex<Option<Connection>>,
+}
+
+fn open(app: &tauri::AppHandle) -> Result<Connection, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
+
+    let conn = Connection::open(app_data_dir.join("history.sqlite3")).map_err(|e| e.to_string())?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS imports (
+            id INTEGER PRIMARY KEY,
+            source_path TEXT NOT NULL,
+            card_id TEXT,
+            destination TEXT NOT NULL,
+            hash INTEGER NOT NULL,
+            size INTEGER NOT NULL,
+            imported INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS imports_size_hash ON imports (size, hash);
+        CREATE INDEX IF NOT EXISTS imports_card ON imports (card_id);",
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+impl Catalog {
+    fn with_conn<T>(&self, app: &tauri::AppHandle, f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+        let mut conn = self.conn.lock().map_err(|e| e.to_string())?;
+        if conn.is_none() {
+            *conn = Some(open(app)?);
+        }
+        f(conn.as_ref().ok_or("Catalog unavailable")?)
+    }
+
+    /// Find an earlier import of this content that still exists on disk. The
+    /// file is only hashed when some cataloged file has the same size.
+    pub fn find(&self, app: &tauri::AppHandle, size: u64, hash: impl FnOnce() -> Option<u64>) -> Option<CatalogEntry> {
+        let result = self.with_conn(app, |conn| {
+            let same_size: bool = conn
+                .query_row("SELECT EXISTS(SELECT 1 FROM imports WHERE size = ?1)", params![size as i64], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            let Some(hash) = same_size.then(hash).flatten() else {
+                return Ok(None);
+            };
+
+            let mut statement = conn
+                .prepare("SELECT * FROM imports WHERE size = ?1 AND hash = ?2 ORDER BY imported DESC")
+                .map_err(|e| e.to_string())?;
+            let found = statement
+                .query_map(params![size as i64, hash as i64], entry_from_row)
+                .map_err(|e| e.to_string())?
+                .filter_map(|e| e.ok())
+                .find(|e| Path::new(&e.destination).exists());
+            Ok(found)
+        });
+        result.unwrap_or_else(|e| {
+            warn!("Catalog lookup failed: {}", e);
+            None
+        })
+    }
+
+    /// Add an imported file
+    pub fn record(&self, app: &tauri::AppHandle, hash: u64, size: u64, source: &Path, card_id: Option<&str>, destination: &Path) {
+        let imported = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let result = self.with_conn(app, |conn| {
+            conn.execute(
+                "INSERT INTO imports (source_path, card_id, destination, hash, size, imported) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    source.to_string_lossy(),
+                    card_id,
+                    destination.to_string_lossy(),
+                    hash as i64,
+                    size as i64,
+                    imported as i64
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+        });
+        if let Err(e) = result {
+            warn!("Failed to update import catalog: {}", e);
+        }
+    }
+
+    /// Whether any file from this card has been imported before, without
+    /// loading the card's whole history
+    pub fn has_card(&self, app: &tauri::AppHandle, card_id: &str) -> Result<bool, String> {
+        self.with_conn(app, |conn| {
+            conn.query_row("SELECT 1 FROM imports WHERE card_id = ?1 LIMIT 1", params![card_id], |row| row.get::<_, i64>(0))
+                .optional()
+                .map(|row| row.is_some())
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<CatalogEntry> {
+    Ok(CatalogEntry {
+        source_path: row.get("source_path")?,
+        card_id: row.get("card_id")?,
+        destination: row.get("destination")?,
+        hash: format!("{:016x}", row.get::<_, i64>("hash")? as u64),
+        size: row.get::<_, i64>("size")? as u64,
+        imported: row.get::<_, i64>("imported")? as u64,
+    })
+}
+
+#[tauri::command]
+pub fn query_import_history(
+    filter: Option<HistoryFilter>,
+    app: tauri::AppHandle,
+    catalog: tauri::State<'_, Catalog>,
+) -> Result<Vec<CatalogEntry>, CamPorterError> {
+    let filter = filter.unwrap_or_default();
+    let hash = filter
+        .hash
+        .as_deref()
+        .map(|h| u64::from_str_radix(h, 16).map(|h| h as i64).map_err(|_| format!("Invalid hash: {}", h)))
+        .transpose()?;
+
+    Ok(catalog.with_conn(&app, |conn| {
+        let mut statement = conn
+            .prepare(
+                "SELECT * FROM imports
+                 WHERE (?1 IS NULL OR card_id = ?1)
+                   AND (?2 IS NULL OR instr(source_path, ?2) > 0)
+                   AND (?3 IS NULL OR instr(destination, ?3) > 0)
+                   AND (?4 IS NULL OR hash = ?4)
+                   AND (?5 IS NULL OR imported >= ?5)
+                   AND (?6 IS NULL OR imported <= ?6)
+                 ORDER BY imported DESC
+                 LIMIT ?7",
+            )
+            .map_err(|e| e.to_string())?;
+        let entries = statement
+            .query_map(
+                params![
+                    filter.card_id,
+                    filter.source_path,
+                    filter.destination,
+                    hash,
+                    filter.since.map(|t| t as i64),
+                    filter.until.map(|t| t as i64),
+                    filter.limit.map(i64::from).unwrap_or(-1)
+                ],
+                entry_from_row,
+            )
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string());
+        entries
+    })?)
+}
+
+/// "Did I already offload this card?"
+#[tauri::command]
+pub fn was_card_imported(mount_point: String, app: tauri::AppHandle, catalog: tauri::State<'_, Catalog>) -> Result<bool, CamPorterError> {
+    let card_id = crate::card_fingerprint(&mount_point).ok_or("Drive not found")?;
+    Ok(catalog.has_card(&app, &card_id)?)
+}
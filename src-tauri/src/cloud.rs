@@ -0,0 +1,103 @@
+// Sources whose camera roll is already auto-synced to a cloud service, so the
+// UI can tell which files exist only on the device. What the cloud holds is
+// taken from a user-provided manifest and/or a locally synced copy of the
+// cloud library (e.g. a Dropbox "Camera Uploads" folder).
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use crate::error::CamPorterError;
+use crate::{card_fingerprint, config};
+
+const CLOUD_SOURCES_KEY: &str = "cloud_sources";
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CloudSource {
+    /// Text or CSV export listing the cloud's files, one per line as
+    /// `name` or `name,size`
+    pub manifest_path: Option<String>,
+    /// Folder the cloud service keeps in sync on this computer
+    pub synced_folder: Option<String>,
+}
+
+/// What the cloud holds: lowercased file names, with sizes where known
+#[derive(Default)]
+struct CloudContents {
+    with_size: HashSet<(String, u64)>,
+    names_only: HashSet<String>,
+}
+
+impl CloudContents {
+    fn load(source: &CloudSource) -> Result<Self, String> {
+        let mut contents = CloudContents::default();
+
+        if let Some(manifest_path) = &source.manifest_path {
+            let manifest = fs::read_to_string(manifest_path).map_err(|e| format!("Failed to read manifest: {}", e))?;
+            for line in manifest.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                let mut fields = line.split(',').map(|f| f.trim().trim_matches('"'));
+                let name = fields.next().unwrap_or_default();
+                // Keep only the file name, in case the export lists paths
+                let name = name.rsplit(['/', '\\']).next().unwrap_or(name).to_lowercase();
+                match fields.next().and_then(|size| size.parse().ok()) {
+                    Some(size) => contents.with_size.insert((name, size)),
+                    None => contents.names_only.insert(name),
+                };
+            }
+        }
+
+        if let Some(synced_folder) = &source.synced_folder {
+            for entry in WalkDir::new(synced_folder).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+                if let Ok(metadata) = entry.metadata() {
+                    let name = entry.file_name().to_string_lossy().to_lowercase();
+                    contents.with_size.insert((name, metadata.len()));
+                }
+            }
+        }
+        Ok(contents)
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_lowercase()) else {
+            return false;
+        };
+        if self.names_only.contains(&name) {
+            return true;
+        }
+        fs::metadata(path).is_ok_and(|m| self.with_size.contains(&(name, m.len())))
+    }
+}
+
+/// Mark the card at `mount_point` as cloud-synced, or clear it with None
+#[tauri::command]
+pub fn set_cloud_source(mount_point: String, source: Option<CloudSource>, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    let fingerprint = card_fingerprint(&mount_point).ok_or("Drive not found")?;
+    let mut sources: HashMap<String, CloudSource> = config::get(&app, CLOUD_SOURCES_KEY)?.unwrap_or_default();
+    match source {
+        Some(source) => sources.insert(fingerprint, source),
+        None => sources.remove(&fingerprint),
+    };
+    Ok(config::set(&app, CLOUD_SOURCES_KEY, &sources)?)
+}
+
+#[tauri::command]
+pub fn get_cloud_source(mount_point: String, app: tauri::AppHandle) -> Result<Option<CloudSource>, CamPorterError> {
+    let fingerprint = card_fingerprint(&mount_point).ok_or("Drive not found")?;
+    let mut sources: HashMap<String, CloudSource> = config::get(&app, CLOUD_SOURCES_KEY)?.unwrap_or_default();
+    Ok(sources.remove(&fingerprint))
+}
+
+/// For each file, whether the cloud already has it. All false when the card
+/// isn't marked as cloud-synced.
+#[tauri::command]
+pub fn check_files_in_cloud(mount_point: String, file_paths: Vec<String>, app: tauri::AppHandle) -> Result<Vec<bool>, CamPorterError> {
+    let source = get_cloud_source(mount_point, app)?;
+    let Some(source) = source else {
+        return Ok(vec![false; file_paths.len()]);
+    };
+
+    let contents = CloudContents::load(&source)?;
+    Ok(file_paths.iter().map(|p| contents.contains(Path::new(p))).collect())
+}
@@ -0,0 +1,46 @@
+// Persisted app settings, stored as one JSON object in the app data dir
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+fn config_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(app_data_dir.join("config.json"))
+}
+
+fn load(app: &tauri::AppHandle) -> Result<Map<String, Value>, String> {
+    let config_file = config_path(app)?;
+    if !config_file.exists() {
+        return Ok(Map::new());
+    }
+
+    let config_content = fs::read_to_string(config_file).map_err(|e| e.to_string())?;
+    match serde_json::from_str(&config_content).map_err(|e| e.to_string())? {
+        Value::Object(map) => Ok(map),
+        _ => Err("Config file is not a JSON object".to_string()),
+    }
+}
+
+/// Read one setting, or None if it was never saved
+pub fn get<T: DeserializeOwned>(app: &tauri::AppHandle, key: &str) -> Result<Option<T>, String> {
+    match load(app)?.remove(key) {
+        Some(value) => serde_json::from_value(value).map(Some).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Save one setting, keeping all others intact
+pub fn set<T: Serialize>(app: &tauri::AppHandle, key: &str, value: &T) -> Result<(), String> {
+    let config_file = config_path(app)?;
+    if let Some(app_data_dir) = config_file.parent() {
+        fs::create_dir_all(app_data_dir).map_err(|e| e.to_string())?;
+    }
+
+    let mut config = load(app)?;
+    config.insert(key.to_string(), serde_json::to_value(value).map_err(|e| e.to_string())?);
+
+    fs::write(config_file, Value::Object(config).to_string()).map_err(|e| e.to_string())
+}
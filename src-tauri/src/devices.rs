@@ -0,0 +1,277 @@
+// Phones and cameras that don't mount as a drive. Each backend lists a
+// device's media as `MediaFile`s whose paths are device URLs such as
+// `mtp://0/4711/IMG_0001.JPG` (kind, device, object, name; the object may
+// be a folder like `store_00010001/DCIM/100CANON`), and downloads files for
+// import. Imports stage the files in the temp workspace under
+// their own names, so rename templates, EXIF dates and sidecar pairing work
+// as they do for a card, and then run the regular import pipeline on them.
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager};
+use ts_rs::TS;
+
+use crate::dto::MediaFile;
+use crate::error::CamPorterError;
+use crate::import::{self, FileError, ImportOptions, ImportReport};
+use crate::jobs::JobManager;
+use crate::undo::UndoJournal;
+#[cfg(feature = "gphoto2")]
+use crate::gphoto;
+use crate::{ios, is_image_extension, is_video_extension, mtp, raw, workspace, MEDIA_EXTENSIONS};
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceKind {
+    /// Media Transfer Protocol, used by Android phones and some cameras
+    Mtp,
+    /// iPhones and iPads, through libimobiledevice's AFC tools
+    Ios,
+    /// Cameras in PTP mode, through gphoto2 in builds with the `gphoto2`
+    /// feature
+    Ptp,
+}
+
+impl DeviceKind {
+    fn scheme(self) -> &'static str {
+        match self {
+            DeviceKind::Mtp => "mtp",
+            DeviceKind::Ios => "ios",
+            DeviceKind::Ptp => "ptp",
+        }
+    }
+
+    fn from_scheme(scheme: &str) -> Option<Self> {
+        match scheme {
+            "mtp" => Some(DeviceKind::Mtp),
+            "ios" => Some(DeviceKind::Ios),
+            "ptp" => Some(DeviceKind::Ptp),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct ConnectedDevice {
+    /// URL such as `mtp://0`, which its files' paths start with
+    pub id: String,
+    pub kind: DeviceKind,
+    pub name: String,
+}
+
+#[cfg(not(feature = "gphoto2"))]
+const NO_PTP: &str = "This build of CamPorter can't talk to PTP cameras";
+
+/// A file on a device, as a backend lists it
+pub struct DeviceObject {
+    /// The backend's handle for the file, or the folder holding it
+    pub object: String,
+    pub name: String,
+    pub size: u64,
+    /// Unix timestamp, 0 if the device doesn't say
+    pub modified: u64,
+}
+
+/// A device URL taken apart
+struct DevicePath {
+    kind: DeviceKind,
+    device: String,
+    object: String,
+    name: String,
+}
+
+impl DevicePath {
+    fn parse(path: &str) -> Option<Self> {
+        let (scheme, rest) = path.split_once("://")?;
+        let kind = DeviceKind::from_scheme(scheme)?;
+        let (device, rest) = rest.split_once('/')?;
+        let (object, name) = rest.rsplit_once('/')?;
+        // A name of ".." would reach out of the staging folder
+        let name = Path::new(name).file_name()?.to_string_lossy().to_string();
+        Some(DevicePath { kind, device: device.to_string(), object: object.to_string(), name })
+    }
+
+    fn download(&self, target: &Path) -> Result<(), String> {
+        match self.kind {
+            DeviceKind::Mtp => mtp::download(&self.device, &self.object, target),
+            DeviceKind::Ios => ios::download(&self.device, &self.object, &self.name, target),
+            #[cfg(feature = "gphoto2")]
+            DeviceKind::Ptp => gphoto::download(&self.device, &self.object, &self.name, target),
+            #[cfg(not(feature = "gphoto2"))]
+            DeviceKind::Ptp => Err(NO_PTP.to_string()),
+        }
+    }
+
+    /// The Live Photo clip that goes with a photo from an iPhone
+    fn motion_clip(&self) -> Option<DevicePath> {
+        let extension = Path::new(&self.name).extension()?.to_str()?.to_lowercase();
+        if self.kind != DeviceKind::Ios || !is_image_extension(&extension) {
+            return None;
+        }
+        let name = ios::motion_clip(&self.device, &self.object, &self.name)?;
+        Some(DevicePath { kind: self.kind, device: self.device.clone(), object: self.object.clone(), name })
+    }
+
+    fn url(&self) -> String {
+        format!("{}://{}/{}/{}", self.kind.scheme(), self.device, self.object, self.name)
+    }
+}
+
+fn media_file(device_id: &str, object: DeviceObject) -> Option<MediaFile> {
+    let extension = Path::new(&object.name).extension()?.to_str()?.to_lowercase();
+    if !MEDIA_EXTENSIONS.contains(&extension.as_str()) && !raw::RAW_EXTENSIONS.contains(&extension.as_str()) {
+        return None;
+    }
+    Some(MediaFile {
+        path: format!("{}/{}/{}", device_id, object.object, object.name),
+        name: object.name,
+        size: object.size,
+        modified: object.modified,
+        is_image: is_image_extension(&extension),
+        is_video: is_video_extension(&extension),
+        extension,
+        captured: None,
+        duration: None,
+    })
+}
+
+#[derive(Serialize, Clone)]
+struct DownloadProgress {
+    processed: usize,
+    total: usize,
+    current_file: String,
+}
+
+/// Phones and cameras connected over USB without mounting as a drive
+#[tauri::command]
+pub fn list_devices() -> Vec<ConnectedDevice> {
+    let mut devices = Vec::new();
+    match mtp::list_devices() {
+        Ok(found) => devices.extend(found.into_iter().map(|(device, name)| ConnectedDevice {
+            id: format!("{}://{}", DeviceKind::Mtp.scheme(), device),
+            kind: DeviceKind::Mtp,
+            name,
+        })),
+        Err(e) => log::warn!("Failed to look for MTP devices: {}", e),
+    }
+    match ios::list_devices() {
+        Ok(found) => devices.extend(found.into_iter().map(|(udid, name)| ConnectedDevice {
+            id: format!("{}://{}", DeviceKind::Ios.scheme(), udid),
+            kind: DeviceKind::Ios,
+            name,
+        })),
+        Err(e) => log::warn!("Failed to look for iOS devices: {}", e),
+    }
+    #[cfg(feature = "gphoto2")]
+    match gphoto::list_devices() {
+        Ok(found) => devices.extend(found.into_iter().map(|(port, model)| ConnectedDevice {
+            id: format!("{}://{}", DeviceKind::Ptp.scheme(), port),
+            kind: DeviceKind::Ptp,
+            name: model,
+        })),
+        Err(e) => log::warn!("Failed to look for PTP cameras: {}", e),
+    }
+    devices
+}
+
+/// The photos and videos on a device, from its DCIM folders
+#[tauri::command]
+pub async fn list_device_media(device_id: String) -> Result<Vec<MediaFile>, CamPorterError> {
+    let (scheme, device) = device_id.split_once("://").ok_or_else(|| CamPorterError::invalid_input("Not a device"))?;
+    let objects = match DeviceKind::from_scheme(scheme) {
+        Some(DeviceKind::Mtp) => mtp::list_media(device)?,
+        Some(DeviceKind::Ios) => ios::list_media(device)?,
+        #[cfg(feature = "gphoto2")]
+        Some(DeviceKind::Ptp) => gphoto::list_media(device)?,
+        #[cfg(not(feature = "gphoto2"))]
+        Some(DeviceKind::Ptp) => return Err(CamPorterError::invalid_input(NO_PTP)),
+        None => return Err(CamPorterError::invalid_input(format!("Unknown kind of device: {}", scheme))),
+    };
+    Ok(objects.into_iter().filter_map(|object| media_file(&device_id, object)).collect())
+}
+
+/// Download files from a device into the temp workspace and import them like
+/// files from a card. Files that fail to download are reported as failed.
+#[tauri::command]
+pub async fn import_device_files(
+    file_paths: Vec<String>,
+    target_path: String,
+    options: Option<ImportOptions>,
+    window: tauri::Window,
+    jobs: tauri::State<'_, JobManager>,
+    journal: tauri::State<'_, UndoJournal>,
+) -> Result<ImportReport, CamPorterError> {
+    let options = options.unwrap_or_default();
+    let pending = jobs.track_direct(&[], &target_path, &options);
+    let app = window.app_handle();
+    let progress = pending.reporter(app);
+    // Each file, and whether it's imported itself rather than as a sidecar
+    let mut files: Vec<(DevicePath, bool)> = file_paths.iter().filter_map(|path| DevicePath::parse(path)).map(|file| (file, true)).collect();
+    if files.is_empty() {
+        return Err(CamPorterError::invalid_input("No device files selected"));
+    }
+    // Live Photo clips come along with their photos, which the import pairs them with
+    if options.include_sidecars {
+        let selected: HashSet<&String> = file_paths.iter().collect();
+        let clips: Vec<DevicePath> = files.iter().filter_map(|(file, _)| file.motion_clip()).filter(|clip| !selected.contains(&clip.url())).collect();
+        files.extend(clips.into_iter().map(|clip| (clip, false)));
+    }
+
+    // Removed with everything staged in it once the import is done
+    let staging = workspace::scratch()?;
+
+    let mut staged = Vec::new();
+    let mut failed = Vec::new();
+    let mut stems: HashMap<String, &str> = HashMap::new();
+    let mut names = HashSet::new();
+    for (i, (file, imported)) in files.iter().enumerate() {
+        // The import then stops before its first file
+        if pending.control().is_cancelled() {
+            break;
+        }
+        let _ = app.emit(
+            "device-download-progress",
+            DownloadProgress { processed: i, total: files.len(), current_file: file.name.clone() },
+        );
+        // Files keep their names, and files that share a stem (RAW+JPG, Live
+        // Photos) stay together; the same stem from another folder goes in a
+        // folder of its own. An MTP object is the file's own id rather than
+        // its folder, so there only a repeated name is set apart.
+        let first = match file.kind {
+            DeviceKind::Mtp => names.insert(file.name.to_lowercase()),
+            DeviceKind::Ios | DeviceKind::Ptp => {
+                let stem = Path::new(&file.name).file_stem().map_or(String::new(), |s| s.to_string_lossy().to_lowercase());
+                *stems.entry(stem).or_insert(&file.object) == file.object
+            }
+        };
+        let target: PathBuf = if first {
+            staging.path().join(&file.name)
+        } else {
+            staging.path().join(&file.object).join(&file.name)
+        };
+        let result = target
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(|e| e.to_string())
+            .and_then(|()| file.download(&target));
+        match result {
+            Ok(()) if *imported => staged.push(target.to_string_lossy().to_string()),
+            Ok(()) => {}
+            Err(e) => failed.push(FileError::new(Path::new(&file.url()), format!("Download failed: {}", e))),
+        }
+    }
+
+    let mut report = if staged.is_empty() {
+        ImportReport::default()
+    } else {
+        import::run_import(&staged, &target_path, options, &progress, pending.control(), &journal)?
+    };
+    if !failed.is_empty() {
+        report.summary += &format!(", {} failed to download", failed.len());
+        report.failed += failed.len();
+        report.errors.extend(failed);
+    }
+    Ok(report)
+}
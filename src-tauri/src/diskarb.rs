@@ -0,0 +1,428 @@
+// Mounting, unmounting and ejecting disks on macOS through DiskArbitration,
+// the framework `diskutil` and the Finder use. Each request is answered on a
+// run loop, with a dissenter saying why it was refused, so a busy volume is
+// told apart from one that's gone, and the disk disappearing is reported
+// rather than guessed from the mount list. Volumes nothing mounted are found
+// from the description DiskArbitration keeps of every disk, which also gives
+// the bus IOKit found each disk on.
+use std::ffi::{c_char, c_void, CStr, CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::error::CamPorterError;
+use crate::partitions::UnmountedPartition;
+use crate::volume::Bus;
+
+type CFTypeRef = *const c_void;
+type DASessionRef = *const c_void;
+type DADiskRef = *const c_void;
+type DADissenterRef = *const c_void;
+type DiskCallback = extern "C" fn(disk: DADiskRef, dissenter: DADissenterRef, context: *mut c_void);
+/// For disks appearing as well as disappearing
+type DisappearedCallback = extern "C" fn(disk: DADiskRef, context: *mut c_void);
+
+const UNMOUNT_WHOLE: u32 = 0x0000_0001;
+const UNMOUNT_FORCE: u32 = 0x0008_0000;
+const EJECT_DEFAULT: u32 = 0;
+const MOUNT_DEFAULT: u32 = 0;
+const UTF8: u32 = 0x0800_0100;
+const NUMBER_SINT64: isize = 4;
+const RUN_TIMED_OUT: i32 = 3;
+
+const RETURN_BUSY: i32 = 0xF8DA_0002_u32 as i32;
+const RETURN_EXCLUSIVE: i32 = 0xF8DA_0004_u32 as i32;
+const RETURN_NOT_FOUND: i32 = 0xF8DA_0006_u32 as i32;
+const RETURN_NOT_MOUNTED: i32 = 0xF8DA_0007_u32 as i32;
+const RETURN_NOT_PERMITTED: i32 = 0xF8DA_0008_u32 as i32;
+const RETURN_NOT_PRIVILEGED: i32 = 0xF8DA_0009_u32 as i32;
+
+/// How long DiskArbitration gets to answer, which includes asking any app
+/// with files open on the volume to let go
+const ANSWER_WAIT: Duration = Duration::from_secs(30);
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    static kCFRunLoopDefaultMode: CFTypeRef;
+    fn CFRelease(cf: CFTypeRef);
+    fn CFEqual(a: CFTypeRef, b: CFTypeRef) -> u8;
+    fn CFRunLoopGetCurrent() -> CFTypeRef;
+    fn CFRunLoopRunInMode(mode: CFTypeRef, seconds: f64, return_after_source_handled: u8) -> i32;
+    fn CFURLCreateFromFileSystemRepresentation(allocator: CFTypeRef, buffer: *const u8, length: isize, is_directory: u8) -> CFTypeRef;
+    fn CFStringGetCString(string: CFTypeRef, buffer: *mut c_char, size: isize, encoding: u32) -> u8;
+    fn CFURLGetFileSystemRepresentation(url: CFTypeRef, resolve_against_base: u8, buffer: *mut u8, size: isize) -> u8;
+    fn CFDictionaryGetValue(dictionary: CFTypeRef, key: CFTypeRef) -> CFTypeRef;
+    fn CFBooleanGetValue(boolean: CFTypeRef) -> u8;
+    fn CFNumberGetValue(number: CFTypeRef, number_type: isize, value: *mut c_void) -> u8;
+    fn CFUUIDCreateString(allocator: CFTypeRef, uuid: CFTypeRef) -> CFTypeRef;
+}
+
+#[link(name = "DiskArbitration", kind = "framework")]
+extern "C" {
+    static kDADiskDescriptionVolumePathKey: CFTypeRef;
+    static kDADiskDescriptionVolumeMountableKey: CFTypeRef;
+    static kDADiskDescriptionVolumeNameKey: CFTypeRef;
+    static kDADiskDescriptionVolumeUUIDKey: CFTypeRef;
+    static kDADiskDescriptionVolumeKindKey: CFTypeRef;
+    static kDADiskDescriptionMediaRemovableKey: CFTypeRef;
+    static kDADiskDescriptionMediaSizeKey: CFTypeRef;
+    static kDADiskDescriptionMediaBSDNameKey: CFTypeRef;
+    static kDADiskDescriptionDeviceInternalKey: CFTypeRef;
+    static kDADiskDescriptionDeviceProtocolKey: CFTypeRef;
+    fn DASessionCreate(allocator: CFTypeRef) -> DASessionRef;
+    fn DASessionScheduleWithRunLoop(session: DASessionRef, run_loop: CFTypeRef, mode: CFTypeRef);
+    fn DASessionUnscheduleFromRunLoop(session: DASessionRef, run_loop: CFTypeRef, mode: CFTypeRef);
+    fn DADiskCreateFromVolumePath(allocator: CFTypeRef, session: DASessionRef, path: CFTypeRef) -> DADiskRef;
+    fn DADiskCreateFromBSDName(allocator: CFTypeRef, session: DASessionRef, name: *const c_char) -> DADiskRef;
+    fn DADiskCopyWholeDisk(disk: DADiskRef) -> DADiskRef;
+    fn DADiskCopyDescription(disk: DADiskRef) -> CFTypeRef;
+    fn DADiskMount(disk: DADiskRef, path: CFTypeRef, options: u32, callback: DiskCallback, context: *mut c_void);
+    fn DADiskUnmount(disk: DADiskRef, options: u32, callback: DiskCallback, context: *mut c_void);
+    fn DADiskEject(disk: DADiskRef, options: u32, callback: DiskCallback, context: *mut c_void);
+    fn DADissenterGetStatus(dissenter: DADissenterRef) -> i32;
+    fn DADissenterGetStatusString(dissenter: DADissenterRef) -> CFTypeRef;
+    fn DARegisterDiskAppearedCallback(session: DASessionRef, matching: CFTypeRef, callback: DisappearedCallback, context: *mut c_void);
+    fn DARegisterDiskDisappearedCallback(session: DASessionRef, matching: CFTypeRef, callback: DisappearedCallback, context: *mut c_void);
+    fn DAUnregisterCallback(session: DASessionRef, callback: *mut c_void, context: *mut c_void);
+}
+
+/// A Core Foundation object, released when dropped
+struct Owned(CFTypeRef);
+
+impl Drop for Owned {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.0) };
+    }
+}
+
+/// Why a request was refused: DiskArbitration's status and, when it gives
+/// one, the reason the process in the way reported
+struct Dissent {
+    status: i32,
+    reason: Option<String>,
+}
+
+impl Dissent {
+    fn into_error(self, action: &str) -> CamPorterError {
+        let message = match self.reason {
+            Some(reason) => format!("{} failed: {}", action, reason),
+            None => format!("{} failed (DiskArbitration error {:#x})", action, self.status),
+        };
+        match self.status {
+            RETURN_BUSY | RETURN_EXCLUSIVE => CamPorterError::busy(message),
+            RETURN_NOT_FOUND | RETURN_NOT_MOUNTED => CamPorterError::not_found(message),
+            RETURN_NOT_PERMITTED | RETURN_NOT_PRIVILEGED => CamPorterError::PermissionDenied { message },
+            _ => CamPorterError::from(message),
+        }
+    }
+}
+
+/// Filled in by `answered` once a request completes
+#[derive(Default)]
+struct Answer {
+    done: bool,
+    dissent: Option<Dissent>,
+}
+
+extern "C" fn answered(_disk: DADiskRef, dissenter: DADissenterRef, context: *mut c_void) {
+    let answer = unsafe { &mut *(context as *mut Answer) };
+    answer.done = true;
+    if !dissenter.is_null() {
+        let reason = unsafe { DADissenterGetStatusString(dissenter) };
+        answer.dissent = Some(Dissent {
+            status: unsafe { DADissenterGetStatus(dissenter) },
+            reason: (!reason.is_null()).then(|| string(reason)).flatten(),
+        });
+    }
+}
+
+/// Watched for by `disappeared`
+struct Departure {
+    disk: DADiskRef,
+    gone: bool,
+}
+
+extern "C" fn disappeared(disk: DADiskRef, context: *mut c_void) {
+    let departure = unsafe { &mut *(context as *mut Departure) };
+    if unsafe { CFEqual(disk, departure.disk) } != 0 {
+        departure.gone = true;
+    }
+}
+
+/// What DiskArbitration knows about a disk: its volume, media and device
+struct Description(Owned);
+
+impl Description {
+    fn of(disk: DADiskRef) -> Option<Self> {
+        let description = unsafe { DADiskCopyDescription(disk) };
+        (!description.is_null()).then(|| Description(Owned(description)))
+    }
+
+    fn value(&self, key: CFTypeRef) -> Option<CFTypeRef> {
+        let value = unsafe { CFDictionaryGetValue(self.0 .0, key) };
+        (!value.is_null()).then_some(value)
+    }
+
+    fn flag(&self, key: CFTypeRef) -> bool {
+        self.value(key).is_some_and(|value| unsafe { CFBooleanGetValue(value) } != 0)
+    }
+
+    fn text(&self, key: CFTypeRef) -> Option<String> {
+        self.value(key).and_then(string).filter(|text| !text.is_empty())
+    }
+
+    fn number(&self, key: CFTypeRef) -> Option<i64> {
+        let mut number = 0i64;
+        let value = self.value(key)?;
+        (unsafe { CFNumberGetValue(value, NUMBER_SINT64, (&mut number as *mut i64).cast()) } != 0).then_some(number)
+    }
+
+    /// Where the volume is mounted, if it is
+    fn mount_point(&self) -> Option<PathBuf> {
+        let url = self.value(unsafe { kDADiskDescriptionVolumePathKey })?;
+        let mut buffer = [0u8; 1024];
+        if unsafe { CFURLGetFileSystemRepresentation(url, 1, buffer.as_mut_ptr(), buffer.len() as isize) } == 0 {
+            return None;
+        }
+        let len = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+        Some(PathBuf::from(OsStr::from_bytes(&buffer[..len])))
+    }
+
+    /// A mountable volume nothing has mounted, on a card or an external disk
+    fn unmounted_partition(&self) -> Option<UnmountedPartition> {
+        let external = unsafe { self.flag(kDADiskDescriptionMediaRemovableKey) || !self.flag(kDADiskDescriptionDeviceInternalKey) };
+        if !unsafe { self.flag(kDADiskDescriptionVolumeMountableKey) } || !external || self.mount_point().is_some() {
+            return None;
+        }
+        unsafe {
+            Some(UnmountedPartition {
+                device_id: format!("/dev/{}", self.text(kDADiskDescriptionMediaBSDNameKey)?),
+                label: self.text(kDADiskDescriptionVolumeNameKey),
+                file_system: self.text(kDADiskDescriptionVolumeKindKey).unwrap_or_default(),
+                total_bytes: self.number(kDADiskDescriptionMediaSizeKey).unwrap_or(0) as u64,
+            })
+        }
+    }
+}
+
+extern "C" fn appeared(disk: DADiskRef, context: *mut c_void) {
+    let found = unsafe { &mut *(context as *mut Vec<UnmountedPartition>) };
+    if let Some(partition) = Description::of(disk).and_then(|description| description.unmounted_partition()) {
+        found.push(partition);
+    }
+}
+
+fn string(string: CFTypeRef) -> Option<String> {
+    let mut buffer = [0 as c_char; 512];
+    if unsafe { CFStringGetCString(string, buffer.as_mut_ptr(), buffer.len() as isize, UTF8) } == 0 {
+        return None;
+    }
+    Some(unsafe { CStr::from_ptr(buffer.as_ptr()) }.to_string_lossy().to_string())
+}
+
+/// A DiskArbitration session scheduled on this thread's run loop, which
+/// `wait` runs to deliver its callbacks
+struct Session(Owned);
+
+impl Session {
+    fn new() -> Result<Self, CamPorterError> {
+        let session = unsafe { DASessionCreate(std::ptr::null()) };
+        if session.is_null() {
+            return Err("Failed to start a DiskArbitration session".into());
+        }
+        unsafe { DASessionScheduleWithRunLoop(session, CFRunLoopGetCurrent(), kCFRunLoopDefaultMode) };
+        Ok(Session(Owned(session)))
+    }
+
+    /// The disk for a BSD name such as "disk4s1", with or without "/dev/"
+    fn disk(&self, device: &str) -> Result<Owned, CamPorterError> {
+        let name = CString::new(device.trim_start_matches("/dev/"))
+            .map_err(|_| CamPorterError::invalid_input(format!("Not a disk name: {}", device)))?;
+        let disk = unsafe { DADiskCreateFromBSDName(std::ptr::null(), self.0 .0, name.as_ptr()) };
+        if disk.is_null() {
+            return Err(CamPorterError::not_found(format!("{} is no longer attached", device)));
+        }
+        Ok(Owned(disk))
+    }
+
+    /// The disk whose volume is mounted at `mount_point`
+    fn volume(&self, mount_point: &Path) -> Result<Owned, CamPorterError> {
+        let path = mount_point.as_os_str().as_bytes();
+        let url = unsafe { CFURLCreateFromFileSystemRepresentation(std::ptr::null(), path.as_ptr(), path.len() as isize, 1) };
+        if url.is_null() {
+            return Err(CamPorterError::invalid_input(format!("Not a volume path: {}", mount_point.display())));
+        }
+        let url = Owned(url);
+        let disk = unsafe { DADiskCreateFromVolumePath(std::ptr::null(), self.0 .0, url.0) };
+        if disk.is_null() {
+            return Err(CamPorterError::not_found(format!("No volume is mounted at {}", mount_point.display())));
+        }
+        Ok(Owned(disk))
+    }
+
+    /// Run the run loop until `finished` says so or `limit` is up
+    fn wait(&self, limit: Duration, finished: impl Fn() -> bool) -> bool {
+        let started = Instant::now();
+        while !finished() {
+            if started.elapsed() >= limit {
+                return false;
+            }
+            unsafe { CFRunLoopRunInMode(kCFRunLoopDefaultMode, 0.25, 1) };
+        }
+        true
+    }
+
+    /// Run the run loop until nothing more is delivered, such as the
+    /// appearance of every disk already attached after registering for them
+    fn settle(&self) {
+        let started = Instant::now();
+        while started.elapsed() < ANSWER_WAIT {
+            if unsafe { CFRunLoopRunInMode(kCFRunLoopDefaultMode, 0.25, 1) } == RUN_TIMED_OUT {
+                break;
+            }
+        }
+    }
+
+    /// Send a request and wait for its answer
+    fn request(&self, action: &str, send: impl FnOnce(*mut c_void)) -> Result<(), CamPorterError> {
+        let mut answer = Answer::default();
+        let context = &mut answer as *mut Answer;
+        send(context.cast());
+        // The callback writes through `context` while the run loop runs
+        let answered = self.wait(ANSWER_WAIT, || unsafe { (*context).done });
+        if !answered {
+            return Err(CamPorterError::busy(format!("{} failed: no answer from DiskArbitration", action)));
+        }
+        match answer.dissent {
+            Some(dissent) => Err(dissent.into_error(action)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        unsafe { DASessionUnscheduleFromRunLoop(self.0 .0, CFRunLoopGetCurrent(), kCFRunLoopDefaultMode) };
+    }
+}
+
+fn unmount_options(force: bool) -> u32 {
+    if force {
+        UNMOUNT_FORCE
+    } else {
+        0
+    }
+}
+
+/// Mountable volumes on cards and external disks that aren't mounted
+pub fn unmounted() -> Result<Vec<UnmountedPartition>, CamPorterError> {
+    let session = Session::new()?;
+    let mut found = Vec::new();
+    let context = &mut found as *mut Vec<UnmountedPartition>;
+    unsafe { DARegisterDiskAppearedCallback(session.0 .0, std::ptr::null(), appeared, context.cast()) };
+    session.settle();
+    unsafe { DAUnregisterCallback(session.0 .0, appeared as *mut c_void, context.cast()) };
+    Ok(found)
+}
+
+/// The device of the volume at `mount_point`, such as "/dev/disk4s1"
+pub fn device(mount_point: &Path) -> Result<String, CamPorterError> {
+    let session = Session::new()?;
+    let volume = session.volume(mount_point)?;
+    unsafe { Description::of(volume.0).and_then(|description| description.text(kDADiskDescriptionMediaBSDNameKey)) }
+        .map(|name| format!("/dev/{}", name))
+        .ok_or_else(|| CamPorterError::not_found(format!("No disk found for {}", mount_point.display())))
+}
+
+/// The bus of the disk holding the volume at `mount_point`, from the
+/// protocol IOKit reports for its device, such as "USB" or "Secure Digital"
+pub fn bus(mount_point: &Path) -> Option<Bus> {
+    let session = Session::new().ok()?;
+    let volume = session.volume(mount_point).ok()?;
+    let description = Description::of(volume.0)?;
+    let internal = unsafe { description.flag(kDADiskDescriptionDeviceInternalKey) };
+    match unsafe { description.text(kDADiskDescriptionDeviceProtocolKey) }.as_deref() {
+        Some("USB") => Some(Bus::Usb),
+        Some("Secure Digital" | "SD") => Some(Bus::Sd),
+        Some("FireWire") => Some(Bus::Firewire),
+        Some("Thunderbolt") => Some(Bus::Thunderbolt),
+        Some("Virtual Interface" | "Disk Image") => Some(Bus::Virtual),
+        _ if internal => Some(Bus::Internal),
+        _ => None,
+    }
+}
+
+/// The UUID of the volume at `mount_point`, which FAT and exFAT cards get
+/// from their serial, without starting `diskutil`
+pub fn volume_uuid(mount_point: &Path) -> Option<String> {
+    let session = Session::new().ok()?;
+    let volume = session.volume(mount_point).ok()?;
+    let description = Description::of(volume.0)?;
+    let uuid = description.value(unsafe { kDADiskDescriptionVolumeUUIDKey })?;
+    let text = unsafe { CFUUIDCreateString(std::ptr::null(), uuid) };
+    if text.is_null() {
+        return None;
+    }
+    let text = Owned(text);
+    string(text.0)
+}
+
+/// The whole disk holding the volume at `mount_point`, such as "/dev/disk4"
+pub fn whole_disk(mount_point: &Path) -> Option<String> {
+    let session = Session::new().ok()?;
+    let volume = session.volume(mount_point).ok()?;
+    let whole = unsafe { DADiskCopyWholeDisk(volume.0) };
+    if whole.is_null() {
+        return None;
+    }
+    let whole = Owned(whole);
+    let name = unsafe { Description::of(whole.0)?.text(kDADiskDescriptionMediaBSDNameKey)? };
+    Some(format!("/dev/{}", name))
+}
+
+/// Mount the volume on `device`, such as "/dev/disk4s1", where the system
+/// mounts volumes (under /Volumes), and return the mount point
+pub fn mount(device: &str) -> Result<PathBuf, CamPorterError> {
+    let session = Session::new()?;
+    let disk = session.disk(device)?;
+    if let Some(mount_point) = Description::of(disk.0).and_then(|description| description.mount_point()) {
+        return Ok(mount_point);
+    }
+    session.request("Mount", |context| unsafe { DADiskMount(disk.0, std::ptr::null(), MOUNT_DEFAULT, answered, context) })?;
+    Description::of(disk.0)
+        .and_then(|description| description.mount_point())
+        .ok_or_else(|| CamPorterError::from(format!("{} was mounted but its mount point is unknown", device)))
+}
+
+/// Unmount the volume at `mount_point`, and only that volume
+pub fn unmount(mount_point: &Path, force: bool) -> Result<(), CamPorterError> {
+    let session = Session::new()?;
+    let disk = session.volume(mount_point)?;
+    session.request("Unmount", |context| unsafe { DADiskUnmount(disk.0, unmount_options(force), answered, context) })
+}
+
+/// Unmount every volume on the disk holding `mount_point` and eject it.
+/// Returns whether the disk then disappeared within `removal_wait`; one that
+/// ejected but is still attached, like some built-in readers, stays powered.
+pub fn eject(mount_point: &Path, force: bool, removal_wait: Duration) -> Result<bool, CamPorterError> {
+    let session = Session::new()?;
+    let volume = session.volume(mount_point)?;
+    let whole = unsafe { DADiskCopyWholeDisk(volume.0) };
+    if whole.is_null() {
+        return Err(CamPorterError::not_found(format!("No disk found for {}", mount_point.display())));
+    }
+    let whole = Owned(whole);
+
+    session.request("Unmount", |context| unsafe {
+        DADiskUnmount(whole.0, UNMOUNT_WHOLE | unmount_options(force), answered, context)
+    })?;
+
+    let mut departure = Departure { disk: whole.0, gone: false };
+    let context = &mut departure as *mut Departure;
+    unsafe { DARegisterDiskDisappearedCallback(session.0 .0, std::ptr::null(), disappeared, context.cast()) };
+    let ejected = session.request("Eject", |context| unsafe { DADiskEject(whole.0, EJECT_DEFAULT, answered, context) });
+    let gone = ejected.is_ok() && session.wait(removal_wait, || unsafe { (*context).gone });
+    unsafe { DAUnregisterCallback(session.0 .0, disappeared as *mut c_void, context.cast()) };
+    ejected?;
+    Ok(gone)
+}
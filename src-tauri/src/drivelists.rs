@@ -0,0 +1,59 @@
+// Volumes the user has hidden from the drive list, such as a backup disk
+// that's always plugged in, and the cards allowed to be imported without
+// asking, both by volume serial so they hold wherever the volume is mounted
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::config;
+use crate::dto::RemovableDrive;
+use crate::error::CamPorterError;
+
+const LISTS_KEY: &str = "drive_lists";
+
+#[derive(Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct DriveLists {
+    /// Serials of volumes left out of `list_removable_drives`
+    pub hidden: Vec<String>,
+    /// Serials of the only cards auto-import may take; empty for any card
+    pub auto_import: Vec<String>,
+}
+
+impl DriveLists {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        config::get(app, LISTS_KEY).ok().flatten().unwrap_or_default()
+    }
+
+    /// Leave out hidden volumes and mark which of the rest may be
+    /// auto-imported. A volume without a serial can't be listed, so it's
+    /// never hidden, and only auto-imported while the allow list is empty.
+    pub fn apply(&self, mut drives: Vec<RemovableDrive>) -> Vec<RemovableDrive> {
+        drives.retain(|drive| drive.serial.as_ref().is_none_or(|serial| !self.hidden.contains(serial)));
+        for drive in &mut drives {
+            drive.auto_import_allowed =
+                self.auto_import.is_empty() || drive.serial.as_ref().is_some_and(|serial| self.auto_import.contains(serial));
+        }
+        drives
+    }
+}
+
+#[tauri::command]
+pub fn get_drive_lists(app: tauri::AppHandle) -> DriveLists {
+    DriveLists::load(&app)
+}
+
+#[tauri::command]
+pub fn set_drive_lists(lists: DriveLists, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    Ok(config::set(&app, LISTS_KEY, &lists)?)
+}
+
+/// Add the volume with `serial` to the hidden list
+#[tauri::command]
+pub fn hide_drive(serial: String, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    let mut lists = DriveLists::load(&app);
+    if !lists.hidden.contains(&serial) {
+        lists.hidden.push(serial);
+    }
+    Ok(config::set(&app, LISTS_KEY, &lists)?)
+}
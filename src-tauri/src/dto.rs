@@ -0,0 +1,131 @@
+// The types commands take and return, in one place. Each derives `TS`, and
+// `cargo test export_bindings` writes matching TypeScript to src/types/bindings
+// (see .cargo/config.toml) for the frontend to import instead of keeping its
+// own copies.
+use serde::Serialize;
+use ts_rs::TS;
+
+pub use crate::benchmark::{BenchmarkReport, FileTimings, Stage, StageTimes};
+pub use crate::devices::{ConnectedDevice, DeviceKind};
+pub use crate::drivelists::DriveLists;
+pub use crate::eject::EjectStatus;
+pub use crate::error::CamPorterError;
+pub use crate::estimate::{DriveBenchmark, ImportEstimate};
+pub use crate::format::{FileSystemType, FormatClearance};
+pub use crate::ftp::{FtpSettings, FtpStatus};
+pub use crate::health::{CardHealth, HealthStatus, ReadErrorHistory, SmartReport};
+pub use crate::import::{
+    ConflictStrategy, DeletionReport, FileError, FileOrder, ImportOptions, ImportProgress, ImportReport, Milestone,
+    MirrorReport, Preallocation, RetainedFile, RetryPolicy,
+};
+pub use crate::layout::Vendor;
+pub use crate::normalize::NameForm;
+pub use crate::partitions::UnmountedPartition;
+pub use crate::profiles::DriveProfile;
+pub use crate::scan::{ScanChunk, ScanProgress};
+pub use crate::sniff::Anomaly;
+pub use crate::trim::ClipTrim;
+pub use crate::verify::{VerifiedFile, VerifyReport, VerifyStatus};
+pub use crate::volume::Bus;
+
+/// How sure the drive list is that a drive is a card or other removable
+/// media rather than a disk inside the computer
+#[derive(Serialize, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    /// The OS reports it on a USB, SD or other external bus
+    Definite,
+    /// The bus is unknown, and only its name or mount point suggest it
+    Maybe,
+}
+
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct RemovableDrive {
+    pub name: String,
+    pub mount_point: String,
+    pub device_id: String,
+    /// User whose session mounted the card, when it isn't the current user
+    pub mounted_by: Option<String>,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    /// Such as "exfat", "vfat" or "apfs", as the OS names it
+    pub file_system: String,
+    /// Mounted read-only or write-protected, so files can't be deleted from it
+    pub is_read_only: bool,
+    /// The card's lock switch is set, which can be undone by sliding it back
+    pub is_write_protected: bool,
+    /// Name given to the volume when it was formatted, such as "EOS_DIGITAL"
+    pub label: Option<String>,
+    /// Volume serial number or file system UUID, which stays the same across
+    /// readers and sessions until the card is formatted
+    pub serial: Option<String>,
+    /// How the disk is attached, where the OS says
+    pub bus: Option<Bus>,
+    /// The whole disk this volume is a partition of, such as "/dev/sdb";
+    /// every partition on the same card has the same one
+    pub physical_device: Option<String>,
+    pub confidence: Confidence,
+    /// Holds DCIM, PRIVATE/AVCHD or other folders a camera writes
+    pub is_camera_card: bool,
+    /// The camera maker its folders point to, such as `canon` for 100CANON
+    pub camera_hint: Option<Vendor>,
+    /// Settings saved for this card, matched by serial
+    pub profile: Option<DriveProfile>,
+    /// On the auto-import allow list, or the list is empty
+    pub auto_import_allowed: bool,
+    /// The card picked last time, matched by serial, for the UI to select
+    /// again and scan
+    pub last_used: bool,
+}
+
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct MediaFile {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    /// Unix timestamp
+    pub modified: u64,
+    pub extension: String,
+    pub is_image: bool,
+    pub is_video: bool,
+    /// Unix timestamp of capture (EXIF, else mtime), in enriched scans only
+    pub captured: Option<i64>,
+    /// Seconds, for videos in enriched scans
+    pub duration: Option<f64>,
+}
+
+#[derive(Serialize, Default, TS)]
+#[ts(export)]
+pub struct TypeStats {
+    pub count: usize,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Default, TS)]
+#[ts(export)]
+pub struct SelectionStats {
+    pub count: usize,
+    pub total_bytes: u64,
+    pub images: TypeStats,
+    pub raw: TypeStats,
+    pub videos: TypeStats,
+    pub audio: TypeStats,
+    pub other: TypeStats,
+    /// Capture date range as Unix timestamps, from file modification times
+    /// (which cameras set when the shot is taken)
+    pub earliest: Option<u64>,
+    pub latest: Option<u64>,
+    /// Selected paths that no longer exist
+    pub missing: usize,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct DestinationLimits {
+    pub file_system: String,
+    pub max_file_size: Option<u64>,
+    pub oversized_files: Vec<String>,
+}
@@ -0,0 +1,294 @@
+// Unmounting a card and, where the platform can, powering it down, so the
+// app can tell a card that's safe to pull from one that's merely unmounted
+// with writes possibly still cached by the reader. Before unmounting, files still open on the card
+// are looked for, so the refusal can say who has them rather than the OS's
+// generic "resource busy".
+use log::{info, warn};
+use serde::Serialize;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use sysinfo::Disks;
+use ts_rs::TS;
+
+use crate::error::CamPorterError;
+#[cfg(target_os = "macos")]
+use crate::diskarb;
+#[cfg(target_os = "linux")]
+use crate::gvfs;
+#[cfg(target_os = "linux")]
+use crate::udisks::Udisks;
+#[cfg(unix)]
+use crate::watchdog;
+
+/// How long Windows gets to release a volume's open files, and then to drop
+/// the ejected volume, before it's treated as still in use; and how long an
+/// ejected disk on macOS gets to disappear
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+const REMOVAL_WAIT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum EjectStatus {
+    /// Flushed and detached or powered down; the card can be pulled
+    SafeToRemove,
+    /// Unmounted, but the device is still powered and may be holding writes.
+    /// Windows either releases the device or leaves the volume mounted.
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    UnmountedStillPowered,
+}
+
+/// Indexers that hold files briefly and let go when asked to unmount
+#[cfg(unix)]
+const TRANSIENT_HOLDERS: [&str; 4] = ["mds", "mds_stores", "mdworker", "fseventsd"];
+
+/// Processes with files open on the volume at `mount_point`, by pid and
+/// name. lsof given a mount point lists every open file on that volume;
+/// without lsof nothing is found and the unmount itself has the last word.
+#[cfg(unix)]
+fn open_files(mount_point: &str) -> Vec<(u32, String)> {
+    use std::process::Command;
+
+    let Ok(output) = watchdog::output(Command::new("lsof").args(["-w", "-F", "pc"]).arg(mount_point), watchdog::PROBE_TIMEOUT) else {
+        return Vec::new();
+    };
+    let mut processes: Vec<(u32, String)> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(pid) = line.strip_prefix('p') {
+            processes.push((pid.parse().unwrap_or(0), String::new()));
+        } else if let (Some(command), Some(process)) = (line.strip_prefix('c'), processes.last_mut()) {
+            process.1 = command.to_string();
+        }
+    }
+    processes.retain(|(_, command)| !TRANSIENT_HOLDERS.iter().any(|holder| command.starts_with(holder)));
+    processes
+}
+
+/// Windows can't list who has files open on a volume; locking it in
+/// `eject_platform` refuses instead
+#[cfg(not(unix))]
+fn open_files(_mount_point: &str) -> Vec<(u32, String)> {
+    Vec::new()
+}
+
+/// Refuse to unmount a card with files open on it, naming who has them
+fn check_open_files(mount_point: &str) -> Result<(), CamPorterError> {
+    let processes = open_files(mount_point);
+    if processes.iter().any(|(pid, _)| *pid == std::process::id()) {
+        return Err(CamPorterError::busy("CamPorter still has files open on the card, such as a preview; close it and try again"));
+    }
+    let mut names: Vec<&str> = processes.iter().map(|(_, command)| command.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+    match names.as_slice() {
+        [] => Ok(()),
+        names => Err(CamPorterError::busy(format!("Files on the card are open in {}", names.join(", ")))),
+    }
+}
+
+pub(crate) fn is_mounted(mount_point: &str) -> bool {
+    Disks::new_with_refreshed_list()
+        .iter()
+        .any(|disk| disk.mount_point() == Path::new(mount_point))
+}
+
+/// Unmount through udisks, then power the drive off, or have GVFS let go of
+/// a camera it mounted. Power-off fails if
+/// another partition on the card is still mounted, which leaves it powered.
+#[cfg(target_os = "linux")]
+fn eject_platform(mount_point: &str, force: bool) -> Result<EjectStatus, CamPorterError> {
+    if gvfs::is_gvfs(mount_point) {
+        gvfs::unmount(mount_point)?;
+        return Ok(EjectStatus::SafeToRemove);
+    }
+    let disks = Disks::new_with_refreshed_list();
+    let device = disks
+        .iter()
+        .find(|disk| disk.mount_point() == Path::new(mount_point))
+        .map(|disk| disk.name().to_string_lossy().to_string())
+        .ok_or_else(|| CamPorterError::not_found("Drive not found"))?;
+
+    let udisks = Udisks::connect()?;
+    udisks.unmount(&device, force)?;
+    match udisks.power_off(&device) {
+        Ok(()) => Ok(EjectStatus::SafeToRemove),
+        Err(e) => {
+            warn!("Unmounted {} but couldn't power it off: {}", device, e);
+            Ok(EjectStatus::UnmountedStillPowered)
+        }
+    }
+}
+
+/// Unmount every volume on the disk and eject it through DiskArbitration.
+/// If another volume on the card is busy, only this one is unmounted.
+#[cfg(target_os = "macos")]
+fn eject_platform(mount_point: &str, force: bool) -> Result<EjectStatus, CamPorterError> {
+    match diskarb::eject(Path::new(mount_point), force, REMOVAL_WAIT) {
+        Ok(true) => Ok(EjectStatus::SafeToRemove),
+        Ok(false) => {
+            warn!("Ejected the disk holding {} but it's still attached", mount_point);
+            Ok(EjectStatus::UnmountedStillPowered)
+        }
+        Err(e @ CamPorterError::NotFound { .. }) => Err(e),
+        Err(e) => {
+            warn!("Couldn't eject the disk holding {}: {}", mount_point, e);
+            diskarb::unmount(Path::new(mount_point), force)?;
+            Ok(EjectStatus::UnmountedStillPowered)
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    use std::ffi::c_void;
+
+    pub const GENERIC_READ: u32 = 0x8000_0000;
+    pub const GENERIC_WRITE: u32 = 0x4000_0000;
+    pub const FILE_SHARE_READ: u32 = 0x0000_0001;
+    pub const FILE_SHARE_WRITE: u32 = 0x0000_0002;
+    pub const OPEN_EXISTING: u32 = 3;
+    pub const FSCTL_LOCK_VOLUME: u32 = 0x0009_0018;
+    pub const FSCTL_DISMOUNT_VOLUME: u32 = 0x0009_0020;
+    pub const IOCTL_STORAGE_MEDIA_REMOVAL: u32 = 0x002D_4804;
+    pub const IOCTL_STORAGE_EJECT_MEDIA: u32 = 0x002D_4808;
+    pub const INVALID_HANDLE_VALUE: *mut c_void = -1isize as *mut c_void;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn CreateFileW(
+            file_name: *const u16,
+            desired_access: u32,
+            share_mode: u32,
+            security_attributes: *mut c_void,
+            creation_disposition: u32,
+            flags_and_attributes: u32,
+            template_file: *mut c_void,
+        ) -> *mut c_void;
+        pub fn DeviceIoControl(
+            device: *mut c_void,
+            io_control_code: u32,
+            in_buffer: *mut c_void,
+            in_buffer_size: u32,
+            out_buffer: *mut c_void,
+            out_buffer_size: u32,
+            bytes_returned: *mut u32,
+            overlapped: *mut c_void,
+        ) -> i32;
+        pub fn FlushFileBuffers(file: *mut c_void) -> i32;
+        pub fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+}
+
+/// An open handle on a volume such as `\\.\E:`, closed when dropped
+#[cfg(target_os = "windows")]
+struct Volume(*mut std::ffi::c_void);
+
+#[cfg(target_os = "windows")]
+impl Volume {
+    fn open(mount_point: &str) -> std::io::Result<Self> {
+        let letter = mount_point.trim_end_matches(['\\', '/']);
+        let name: Vec<u16> = format!("\\\\.\\{}", letter).encode_utf16().chain(Some(0)).collect();
+        let handle = unsafe {
+            win32::CreateFileW(
+                name.as_ptr(),
+                win32::GENERIC_READ | win32::GENERIC_WRITE,
+                win32::FILE_SHARE_READ | win32::FILE_SHARE_WRITE,
+                std::ptr::null_mut(),
+                win32::OPEN_EXISTING,
+                0,
+                std::ptr::null_mut(),
+            )
+        };
+        if handle == win32::INVALID_HANDLE_VALUE {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Volume(handle))
+    }
+
+    fn control(&self, code: u32, input: &mut [u8]) -> std::io::Result<()> {
+        let mut returned = 0;
+        let ok = unsafe {
+            win32::DeviceIoControl(
+                self.0,
+                code,
+                input.as_mut_ptr().cast(),
+                input.len() as u32,
+                std::ptr::null_mut(),
+                0,
+                &mut returned,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for Volume {
+    fn drop(&mut self) {
+        unsafe { win32::CloseHandle(self.0) };
+    }
+}
+
+/// Flush, lock and dismount the volume, then eject the media, as "Safely
+/// Remove Hardware" does for a card reader. The lock is refused while any
+/// file on the card is open, so it's retried until the removal wait is up;
+/// forcing dismounts the volume anyway, cutting off whoever had files open.
+#[cfg(target_os = "windows")]
+fn eject_platform(mount_point: &str, force: bool) -> Result<EjectStatus, CamPorterError> {
+    let volume = Volume::open(mount_point).map_err(|e| CamPorterError::io("Eject failed", e))?;
+    unsafe { win32::FlushFileBuffers(volume.0) };
+
+    let started = std::time::Instant::now();
+    while let Err(e) = volume.control(win32::FSCTL_LOCK_VOLUME, &mut []) {
+        if started.elapsed() >= REMOVAL_WAIT {
+            warn!("Couldn't lock {}: {}", mount_point, e);
+            if force {
+                break;
+            }
+            return Err(CamPorterError::busy("Eject failed: the drive is still in use"));
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+    volume.control(win32::FSCTL_DISMOUNT_VOLUME, &mut []).map_err(|e| CamPorterError::io("Unmount failed", e))?;
+    // PREVENT_MEDIA_REMOVAL with PreventMediaRemoval = FALSE
+    volume.control(win32::IOCTL_STORAGE_MEDIA_REMOVAL, &mut [0]).map_err(|e| CamPorterError::io("Eject failed", e))?;
+    volume.control(win32::IOCTL_STORAGE_EJECT_MEDIA, &mut []).map_err(|e| CamPorterError::io("Eject failed", e))?;
+    drop(volume);
+
+    while is_mounted(mount_point) {
+        if started.elapsed() >= REMOVAL_WAIT * 2 {
+            return Err(CamPorterError::busy("Eject failed: the drive is still attached"));
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+    Ok(EjectStatus::SafeToRemove)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn eject_platform(_mount_point: &str, _force: bool) -> Result<EjectStatus, CamPorterError> {
+    Err("Unsupported platform".into())
+}
+
+/// Eject the card at `mount_point` and report whether it's safe to remove.
+/// `force` unmounts it even while files on it are open.
+pub fn eject(mount_point: &str, force: bool) -> Result<EjectStatus, CamPorterError> {
+    if !force {
+        check_open_files(mount_point)?;
+    }
+    let status = eject_platform(mount_point, force)?;
+    // Some readers keep the volume listed for a moment after unmounting
+    if is_mounted(mount_point) {
+        thread::sleep(Duration::from_millis(500));
+        if is_mounted(mount_point) {
+            return Err(CamPorterError::busy(format!("{} is still mounted", mount_point)));
+        }
+    }
+    info!("Ejected {}: {:?}", mount_point, status);
+    Ok(status)
+}
+
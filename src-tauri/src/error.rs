@@ -0,0 +1,111 @@
+// The error every command returns. It's tagged by `kind`, which doubles as a
+// stable error code, so the UI can tell a missing destination from a
+// permission problem or a full disk and offer the matching way out, while
+// `message` stays readable for anything shown as-is.
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, ErrorKind};
+use ts_rs::TS;
+
+use crate::watchdog::ProcessError;
+
+#[derive(Serialize, Debug, TS)]
+#[ts(export)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CamPorterError {
+    /// A file, folder or drive that isn't there, or is no longer
+    NotFound { message: String },
+    PermissionDenied { message: String },
+    /// The volume filled up while writing
+    DiskFull { message: String },
+    /// The selection won't fit in the destination, found before copying
+    InsufficientSpace { required: u64, available: u64, missing: u64 },
+    /// Mounted read-only or write-protected, like a locked SD card
+    ReadOnly { message: String },
+    /// A drive can't be unmounted because files on it are open
+    Busy { message: String },
+    /// An external tool ran past its time limit and was killed
+    TimedOut { program: String, seconds: u64 },
+    /// An external tool couldn't be started
+    ToolFailed { program: String, message: String },
+    /// Arguments or settings the command can't work with
+    InvalidInput { message: String },
+    Other { message: String },
+}
+
+impl CamPorterError {
+    /// An I/O failure, classified by its kind, with what was being done
+    pub fn io(context: impl fmt::Display, error: io::Error) -> Self {
+        Self::classify(error.kind(), format!("{}: {}", context, error))
+    }
+
+    fn classify(kind: ErrorKind, message: String) -> Self {
+        match kind {
+            ErrorKind::NotFound => CamPorterError::NotFound { message },
+            ErrorKind::PermissionDenied => CamPorterError::PermissionDenied { message },
+            ErrorKind::StorageFull | ErrorKind::QuotaExceeded => CamPorterError::DiskFull { message },
+            ErrorKind::ReadOnlyFilesystem => CamPorterError::ReadOnly { message },
+            _ => CamPorterError::Other { message },
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        CamPorterError::NotFound { message: message.into() }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        CamPorterError::InvalidInput { message: message.into() }
+    }
+
+    pub fn busy(message: impl Into<String>) -> Self {
+        CamPorterError::Busy { message: message.into() }
+    }
+}
+
+impl fmt::Display for CamPorterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CamPorterError::InsufficientSpace { missing, .. } => {
+                write!(f, "Insufficient space in destination ({} more bytes needed)", missing)
+            }
+            CamPorterError::TimedOut { program, seconds } => write!(f, "{} timed out after {} s", program, seconds),
+            CamPorterError::ToolFailed { program, message } => write!(f, "Failed to run {}: {}", program, message),
+            CamPorterError::NotFound { message }
+            | CamPorterError::PermissionDenied { message }
+            | CamPorterError::DiskFull { message }
+            | CamPorterError::ReadOnly { message }
+            | CamPorterError::Busy { message }
+            | CamPorterError::InvalidInput { message }
+            | CamPorterError::Other { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CamPorterError {}
+
+impl From<String> for CamPorterError {
+    fn from(message: String) -> Self {
+        CamPorterError::Other { message }
+    }
+}
+
+impl From<&str> for CamPorterError {
+    fn from(message: &str) -> Self {
+        CamPorterError::Other { message: message.to_string() }
+    }
+}
+
+impl From<io::Error> for CamPorterError {
+    fn from(error: io::Error) -> Self {
+        Self::classify(error.kind(), error.to_string())
+    }
+}
+
+impl From<ProcessError> for CamPorterError {
+    fn from(error: ProcessError) -> Self {
+        match error {
+            ProcessError::Spawn { program, message } => CamPorterError::ToolFailed { program, message },
+            ProcessError::TimedOut { program, seconds } => CamPorterError::TimedOut { program, seconds },
+        }
+    }
+}
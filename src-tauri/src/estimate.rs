@@ -0,0 +1,197 @@
+// How long an import will take, worked out before it starts so the UI can
+// warn "this will take ~40 minutes". Sizes come from the selection and speed
+// from writing a short sample to the destination and reading one from the
+// card; the slower of the two sets the pace. A card benchmarked with
+// `benchmark_drive` is timed over several of its largest files instead, and
+// that speed is kept for the next time it's inserted.
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+use walkdir::WalkDir;
+
+use crate::error::CamPorterError;
+use crate::{card_fingerprint, config, humanize, import, longpath};
+
+/// Bytes written and read for each speed sample
+const SAMPLE_SIZE: usize = 32 * 1024 * 1024;
+/// Reading less than this times the card's latency rather than its speed
+const MIN_READ_SAMPLE: u64 = 4 * 1024 * 1024;
+/// Opening, verifying and timestamping each file, on top of moving its bytes
+const PER_FILE_OVERHEAD: Duration = Duration::from_millis(20);
+/// How much `benchmark_drive` reads in all, spread over the largest files
+const BENCHMARK_BYTES: usize = 256 * 1024 * 1024;
+const BENCHMARK_FILES: usize = 8;
+/// Read speeds measured by `benchmark_drive`, by card fingerprint
+const DRIVE_SPEEDS_KEY: &str = "drive_read_speeds";
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct ImportEstimate {
+    pub file_count: usize,
+    pub total_bytes: u64,
+    /// Sampled destination write speed, in bytes per second
+    pub write_bytes_per_second: u64,
+    /// Source read speed, in bytes per second: the card's benchmark if it
+    /// has one, else sampled, unless every file was too small to time
+    pub read_bytes_per_second: Option<u64>,
+    /// The read speed came from `benchmark_drive` rather than a sample
+    pub read_benchmarked: bool,
+    /// Expected duration
+    pub seconds: u64,
+    pub summary: String,
+}
+
+fn rate(bytes: usize, elapsed: Duration) -> u64 {
+    (bytes as f64 / elapsed.as_secs_f64().max(0.001)) as u64
+}
+
+/// Time writing a sample file to `dir` and flushing it to disk. The contents
+/// vary so compressing file systems and NAS boxes can't shortcut them.
+fn sample_write(dir: &Path) -> std::io::Result<u64> {
+    let path = dir.join(format!(".camporter-estimate-{}", std::process::id()));
+    let data: Vec<u8> = (0..SAMPLE_SIZE as u64).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+    let started = Instant::now();
+    let result = File::create(&path).and_then(|mut file| {
+        file.write_all(&data)?;
+        file.sync_all()
+    });
+    let elapsed = started.elapsed();
+    let _ = fs::remove_file(&path);
+    result.map(|()| rate(SAMPLE_SIZE, elapsed))
+}
+
+/// Time reading up to `size` bytes of `path`, returning how many were read.
+/// The sample comes from the middle, as the start may still be cached from
+/// generating thumbnails.
+fn read_middle(path: &Path, size: usize) -> std::io::Result<(usize, Duration)> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let sample = (len as usize).min(size);
+    file.seek(SeekFrom::Start((len - sample as u64) / 2))?;
+    let mut buffer = vec![0; sample];
+    let started = Instant::now();
+    file.read_exact(&mut buffer)?;
+    Ok((sample, started.elapsed()))
+}
+
+fn sample_read(path: &Path) -> std::io::Result<u64> {
+    let (bytes, elapsed) = read_middle(path, SAMPLE_SIZE)?;
+    Ok(rate(bytes, elapsed))
+}
+
+fn benchmarked_speed(app: &tauri::AppHandle, fingerprint: &str) -> Option<u64> {
+    let speeds: HashMap<String, u64> = config::get(app, DRIVE_SPEEDS_KEY).ok().flatten()?;
+    speeds.get(fingerprint).copied()
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct DriveBenchmark {
+    pub files_read: usize,
+    pub bytes_read: u64,
+    pub read_bytes_per_second: u64,
+    pub summary: String,
+}
+
+/// Time reading a sample of the largest files on the card at `mount_point`
+/// and keep the speed for estimating its imports
+#[tauri::command]
+pub async fn benchmark_drive(mount_point: String, app: tauri::AppHandle) -> Result<DriveBenchmark, CamPorterError> {
+    let root = longpath::extend(Path::new(&mount_point));
+    if !root.is_dir() {
+        return Err(CamPorterError::not_found("Drive path does not exist"));
+    }
+    let mut files: Vec<(walkdir::DirEntry, u64)> = WalkDir::new(&root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok().map(|m| (e, m.len())))
+        .filter(|(_, size)| *size >= MIN_READ_SAMPLE)
+        .collect();
+    if files.is_empty() {
+        return Err(CamPorterError::invalid_input("The card has no files large enough to time"));
+    }
+    files.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    files.truncate(BENCHMARK_FILES);
+
+    let per_file = BENCHMARK_BYTES / files.len();
+    let (mut bytes_read, mut elapsed) = (0, Duration::ZERO);
+    for (entry, _) in &files {
+        let (bytes, took) = read_middle(entry.path(), per_file)
+            .map_err(|e| CamPorterError::io(format!("Failed to read {}", longpath::display(entry.path())), e))?;
+        bytes_read += bytes;
+        elapsed += took;
+    }
+    let read_bytes_per_second = rate(bytes_read, elapsed).max(1);
+
+    if let Some(fingerprint) = card_fingerprint(&mount_point) {
+        let mut speeds: HashMap<String, u64> = config::get(&app, DRIVE_SPEEDS_KEY)?.unwrap_or_default();
+        speeds.insert(fingerprint, read_bytes_per_second);
+        config::set(&app, DRIVE_SPEEDS_KEY, &speeds)?;
+    }
+    Ok(DriveBenchmark {
+        files_read: files.len(),
+        bytes_read: bytes_read as u64,
+        read_bytes_per_second,
+        summary: format!(
+            "Reads at {} ({} from {} files)",
+            humanize::speed(read_bytes_per_second),
+            humanize::bytes(bytes_read as u64),
+            files.len()
+        ),
+    })
+}
+
+/// Sum up the selection and sample both ends of the copy to predict how
+/// long importing it into `target_path` will take
+#[tauri::command]
+pub async fn estimate_import(
+    file_paths: Vec<String>,
+    target_path: String,
+    app: tauri::AppHandle,
+) -> Result<ImportEstimate, CamPorterError> {
+    let sizes: Vec<(&String, u64)> =
+        file_paths.iter().filter_map(|p| fs::metadata(p).ok().map(|m| (p, m.len()))).collect();
+    let total_bytes: u64 = sizes.iter().map(|(_, size)| size).sum();
+
+    // The destination may not exist yet; the import would create it, so
+    // sample the nearest folder that does
+    let target = longpath::extend(Path::new(&target_path));
+    let dir = target.ancestors().find(|dir| dir.is_dir()).ok_or_else(|| CamPorterError::not_found("Destination drive not found"))?;
+    let write = sample_write(dir).map_err(|e| CamPorterError::io(format!("Failed to write to {}", longpath::display(dir)), e))?;
+    let benchmarked = import::source_drive(&file_paths)
+        .and_then(|mount_point| card_fingerprint(&mount_point))
+        .and_then(|fingerprint| benchmarked_speed(&app, &fingerprint));
+    let read = benchmarked.or_else(|| {
+        sizes
+            .iter()
+            .max_by_key(|(_, size)| *size)
+            .filter(|(_, size)| *size >= MIN_READ_SAMPLE)
+            .and_then(|(path, _)| sample_read(Path::new(path)).ok())
+    });
+
+    let bytes_per_second = read.map_or(write, |read| read.min(write)).max(1);
+    let transfer = total_bytes as f64 / bytes_per_second as f64;
+    let seconds = (transfer + PER_FILE_OVERHEAD.as_secs_f64() * sizes.len() as f64).ceil() as u64;
+    let summary = format!(
+        "{} files, {}: about {} at {}",
+        sizes.len(),
+        humanize::bytes(total_bytes),
+        humanize::duration(seconds),
+        humanize::speed(bytes_per_second)
+    );
+
+    Ok(ImportEstimate {
+        file_count: sizes.len(),
+        total_bytes,
+        write_bytes_per_second: write,
+        read_bytes_per_second: read,
+        read_benchmarked: benchmarked.is_some(),
+        seconds,
+        summary,
+    })
+}
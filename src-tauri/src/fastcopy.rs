@@ -0,0 +1,108 @@
+// Kernel-side copies for files that stay on one volume: copy_file_range on
+// Linux (reflinks on Btrfs/XFS, server-side copies on NFS/SMB), clonefile on
+// APFS and CopyFileEx block cloning on ReFS. Each returns Ok(false) when the
+// fast path doesn't apply, leaving the caller to copy the data itself.
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Bytes per copy_file_range call, so progress keeps moving on big files
+#[cfg(target_os = "linux")]
+const CHUNK: u64 = 16 * 1024 * 1024;
+
+/// Copy `src` (opened at offset 0, `len` bytes long) to the new file `dest`,
+/// calling `copied` with the bytes done after each step
+#[cfg(target_os = "linux")]
+pub fn copy(src: &File, _src_path: &Path, dest: &Path, len: u64, mut copied: impl FnMut(u64)) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    use std::os::unix::io::AsRawFd;
+
+    let dest_dir = dest.parent().unwrap_or(Path::new("."));
+    if src.metadata()?.dev() != std::fs::metadata(dest_dir)?.dev() {
+        return Ok(false);
+    }
+
+    let out = File::create(dest)?;
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = unsafe {
+            libc::copy_file_range(
+                src.as_raw_fd(),
+                std::ptr::null_mut(),
+                out.as_raw_fd(),
+                std::ptr::null_mut(),
+                remaining.min(CHUNK) as usize,
+                0,
+            )
+        };
+        if n < 0 {
+            let error = io::Error::last_os_error();
+            let unsupported = matches!(
+                error.raw_os_error(),
+                Some(libc::EXDEV | libc::ENOSYS | libc::EOPNOTSUPP | libc::EINVAL)
+            );
+            if unsupported && remaining == len {
+                drop(out);
+                std::fs::remove_file(dest)?;
+                return Ok(false);
+            }
+            return Err(error);
+        }
+        if n == 0 {
+            break; // Source shrank since it was measured
+        }
+        remaining -= n as u64;
+        copied(n as u64);
+    }
+    Ok(true)
+}
+
+#[cfg(target_os = "macos")]
+pub fn copy(src: &File, _src_path: &Path, dest: &Path, len: u64, mut copied: impl FnMut(u64)) -> io::Result<bool> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::AsRawFd;
+
+    let dest_name = std::ffi::CString::new(dest.as_os_str().as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    // clonefile refuses to replace an existing file
+    let _ = std::fs::remove_file(dest);
+    if unsafe { libc::fclonefileat(src.as_raw_fd(), libc::AT_FDCWD, dest_name.as_ptr(), 0) } != 0 {
+        let error = io::Error::last_os_error();
+        // Not APFS, or source and destination on different volumes
+        if matches!(error.raw_os_error(), Some(libc::ENOTSUP | libc::EXDEV)) {
+            return Ok(false);
+        }
+        return Err(error);
+    }
+    copied(len);
+    Ok(true)
+}
+
+/// CopyFileEx clones blocks itself on ReFS (Dev Drives included), but
+/// reports no progress through std, so it's only used where it clones
+#[cfg(target_os = "windows")]
+pub fn copy(_src: &File, src_path: &Path, dest: &Path, len: u64, mut copied: impl FnMut(u64)) -> io::Result<bool> {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    let volume = |path: &Path| {
+        // Mount points have no \\?\ prefix, so compare without one
+        let path = crate::longpath::strip(&std::path::absolute(path).ok()?);
+        disks
+            .iter()
+            .filter(|disk| path.starts_with(disk.mount_point()))
+            .max_by_key(|disk| disk.mount_point().as_os_str().len())
+    };
+    let (Some(src_volume), Some(dest_volume)) = (volume(src_path), volume(dest.parent().unwrap_or(Path::new(".")))) else {
+        return Ok(false);
+    };
+    if src_volume.mount_point() != dest_volume.mount_point() || !src_volume.file_system().eq_ignore_ascii_case("refs") {
+        return Ok(false);
+    }
+
+    std::fs::copy(src_path, dest)?;
+    copied(len);
+    Ok(true)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn copy(_src: &File, _src_path: &Path, _dest: &Path, _len: u64, _copied: impl FnMut(u64)) -> io::Result<bool> {
+    Ok(false)
+}
@@ -0,0 +1,171 @@
+// Locating ffmpeg/ffprobe (bundled sidecar, managed download in app data, or
+// PATH) and installing a static build when none is found
+use log::{info, warn};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::{Emitter, Manager};
+use which::which;
+
+use crate::error::CamPorterError;
+
+const BUILDS_URL: &str = "https://github.com/BtbN/FFmpeg-Builds/releases/download/latest";
+
+/// Static build archive for this platform, if one is published
+fn build_asset() -> Option<&'static str> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => Some("ffmpeg-master-latest-linux64-gpl.tar.xz"),
+        ("linux", "aarch64") => Some("ffmpeg-master-latest-linuxarm64-gpl.tar.xz"),
+        ("windows", "x86_64") => Some("ffmpeg-master-latest-win64-gpl.zip"),
+        ("windows", "aarch64") => Some("ffmpeg-master-latest-winarm64-gpl.zip"),
+        _ => None,
+    }
+}
+
+static MANAGED_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Remember where managed binaries live; called once from setup
+pub fn init(app: &tauri::AppHandle) {
+    if let Ok(app_data_dir) = app.path().app_data_dir() {
+        let _ = MANAGED_DIR.set(app_data_dir.join("ffmpeg"));
+    }
+}
+
+fn executable(name: &str) -> String {
+    format!("{}{}", name, std::env::consts::EXE_SUFFIX)
+}
+
+fn managed_path(name: &str) -> Option<PathBuf> {
+    MANAGED_DIR.get().map(|dir| dir.join(executable(name)))
+}
+
+/// Find a tool: a sidecar bundled next to the app, then the managed
+/// download, then PATH
+fn find(name: &str) -> Option<PathBuf> {
+    let bundled = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(executable(name))));
+    bundled
+        .into_iter()
+        .chain(managed_path(name))
+        .find(|path| path.is_file())
+        .or_else(|| which(name).ok())
+}
+
+pub fn ffmpeg_path() -> Option<PathBuf> {
+    find("ffmpeg")
+}
+
+pub fn ffprobe_path() -> Option<PathBuf> {
+    find("ffprobe")
+}
+
+#[derive(Serialize)]
+pub struct FfmpegStatus {
+    pub path: Option<String>,
+    pub managed: bool,
+    /// Whether `install_ffmpeg` has a build for this platform
+    pub installable: bool,
+}
+
+#[tauri::command]
+pub fn get_ffmpeg_status() -> FfmpegStatus {
+    let path = ffmpeg_path();
+    FfmpegStatus {
+        managed: path.is_some() && path == managed_path("ffmpeg"),
+        path: path.map(|p| p.to_string_lossy().to_string()),
+        installable: build_asset().is_some(),
+    }
+}
+
+fn download(url: &str) -> Result<Vec<u8>, String> {
+    let response = ureq::get(url).call().map_err(|e| format!("Download of {} failed: {}", url, e))?;
+    let mut data = Vec::new();
+    response.into_reader().read_to_end(&mut data).map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+/// Look up an asset in the release's `checksums.sha256` ("<hex>  <name>")
+fn expected_checksum(checksums: &str, asset: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let (hash, name) = line.split_once(char::is_whitespace)?;
+        (name.trim().trim_start_matches('*') == asset).then(|| hash.to_lowercase())
+    })
+}
+
+/// Pull the ffmpeg and ffprobe binaries out of a build archive into `dir`
+fn extract(archive: &[u8], asset: &str, dir: &Path) -> Result<usize, String> {
+    let wanted = [executable("ffmpeg"), executable("ffprobe")];
+    let mut extracted = 0;
+
+    let mut write = |name: &str, reader: &mut dyn Read| -> Result<(), String> {
+        let path = dir.join(name);
+        let mut file = fs::File::create(&path).map_err(|e| e.to_string())?;
+        std::io::copy(reader, &mut file).map_err(|e| e.to_string())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+        }
+        extracted += 1;
+        Ok(())
+    };
+
+    if asset.ends_with(".zip") {
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).map_err(|e| e.to_string())?;
+        for i in 0..zip.len() {
+            let mut entry = zip.by_index(i).map_err(|e| e.to_string())?;
+            let name = Path::new(entry.name()).file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+            if wanted.contains(&name) {
+                write(&name, &mut entry)?;
+            }
+        }
+    } else {
+        let mut tar_data = Vec::new();
+        lzma_rs::xz_decompress(&mut std::io::Cursor::new(archive), &mut tar_data).map_err(|e| e.to_string())?;
+        let mut tar = tar::Archive::new(std::io::Cursor::new(tar_data));
+        for entry in tar.entries().map_err(|e| e.to_string())? {
+            let mut entry = entry.map_err(|e| e.to_string())?;
+            let path = entry.path().map_err(|e| e.to_string())?.into_owned();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+            if wanted.contains(&name) {
+                write(&name, &mut entry)?;
+            }
+        }
+    }
+    Ok(extracted)
+}
+
+/// Download a static ffmpeg build into app data, verify it against the
+/// release's published SHA-256, and install ffmpeg and ffprobe from it
+#[tauri::command]
+pub async fn install_ffmpeg(app: tauri::AppHandle) -> Result<String, CamPorterError> {
+    let asset = build_asset().ok_or("No managed ffmpeg build for this platform; install ffmpeg from your package manager")?;
+    let dir = MANAGED_DIR.get().ok_or("App data directory unavailable")?;
+
+    let _ = app.emit("ffmpeg-install-progress", "Downloading checksums");
+    let checksums = String::from_utf8_lossy(&download(&format!("{}/checksums.sha256", BUILDS_URL))?).to_string();
+    let expected = expected_checksum(&checksums, asset).ok_or(format!("No checksum published for {}", asset))?;
+
+    let _ = app.emit("ffmpeg-install-progress", format!("Downloading {}", asset));
+    let archive = download(&format!("{}/{}", BUILDS_URL, asset))?;
+
+    let actual = Sha256::digest(&archive).iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    if actual != expected {
+        warn!("ffmpeg checksum mismatch: expected {}, got {}", expected, actual);
+        return Err("Downloaded ffmpeg failed checksum verification".into());
+    }
+
+    let _ = app.emit("ffmpeg-install-progress", "Installing");
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    if extract(&archive, asset, dir)? == 0 {
+        return Err("ffmpeg not found in the downloaded archive".into());
+    }
+
+    let installed = managed_path("ffmpeg").ok_or("App data directory unavailable")?;
+    info!("Installed ffmpeg to {}", installed.display());
+    Ok(installed.to_string_lossy().to_string())
+}
@@ -0,0 +1,326 @@
+// Formatting a card in place after it has been offloaded, which is what the
+// camera would do anyway but without a trip back to it. A card is only
+// formatted with a clearance token, handed out when the offload history
+// shows every file an import would take from it (media and their sidecars)
+// was imported, and the whole card could be read; the token is tied to the
+// card as it was checked and can be used once, within a few minutes. Files
+// the camera keeps for itself, such as clip indexes and folder catalogs, are
+// never imported and so aren't checked.
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+use walkdir::WalkDir;
+
+use crate::error::CamPorterError;
+use crate::jobs::JobManager;
+use crate::{longpath, media_file, offload, sidecars};
+
+/// How long a clearance stays good, so one can't be used on a card that has
+/// been back in the camera since
+const CLEARANCE_LIFETIME: Duration = Duration::from_secs(5 * 60);
+/// Volume labels on FAT32 and exFAT hold at most 11 characters
+const MAX_LABEL: usize = 11;
+
+/// Folders the OS or a desktop keeps on a card for itself, never imported
+const LITTER_FOLDERS: &[&str] = &[
+    "system volume information",
+    "$recycle.bin",
+    ".spotlight-v100",
+    ".fseventsd",
+    ".trashes",
+    ".temporaryitems",
+    "lost.dir",
+];
+
+/// Files the OS or a desktop leaves on a card for itself
+const LITTER_FILES: &[&str] = &[".ds_store", "thumbs.db", "desktop.ini", ".metadata_never_index"];
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FileSystemType {
+    /// What cameras use on SDXC and CFexpress cards, over 32 GB
+    Exfat,
+    /// For SDHC cards and older cameras that don't read exFAT
+    Fat32,
+}
+
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct FormatClearance {
+    /// Pass to `format_drive` as its `confirmation_token`
+    pub token: String,
+    pub device_id: String,
+    /// Media files and sidecars on the card, every one of them imported before
+    pub offloaded_files: usize,
+}
+
+/// A clearance given out, and the card it was given for
+struct Clearance {
+    device_id: String,
+    mount_point: String,
+    serial: String,
+    issued: Instant,
+}
+
+/// Clearances not yet used, by token
+#[derive(Default)]
+pub struct FormatClearances {
+    issued: Mutex<HashMap<String, Clearance>>,
+}
+
+fn new_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Whether a file or folder is something the OS keeps on the card rather
+/// than anything the camera wrote, such as macOS's `._` resource forks
+fn is_litter(entry: &walkdir::DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy().to_lowercase();
+    if entry.file_type().is_dir() {
+        LITTER_FOLDERS.contains(&name.as_str())
+    } else {
+        LITTER_FILES.contains(&name.as_str()) || name.starts_with("._")
+    }
+}
+
+/// Check that every media file on the card at `mount_point`, and every
+/// sidecar beside one, has been imported and
+/// hand out a token for formatting it. A card that can't be read in full, or
+/// that has nothing on it that was checked, gets none.
+#[tauri::command]
+pub fn request_format_clearance(
+    mount_point: String,
+    app: tauri::AppHandle,
+    clearances: tauri::State<'_, FormatClearances>,
+) -> Result<FormatClearance, CamPorterError> {
+    let drive = crate::removable_drives()
+        .into_iter()
+        .find(|d| d.mount_point == mount_point)
+        .ok_or_else(|| CamPorterError::not_found(format!("{} is not a removable drive", mount_point)))?;
+    if drive.is_write_protected {
+        return Err(CamPorterError::invalid_input("The card's lock switch is set; slide it back to format the card"));
+    }
+    let serial = drive
+        .serial
+        .clone()
+        .ok_or_else(|| CamPorterError::invalid_input("The card has no volume serial, so its imports can't be matched to it"))?;
+    let history = offload::load(&app, &serial);
+
+    let root = Path::new(&mount_point);
+    // A Live Photo clip is media and a sidecar both, so it's counted once
+    let mut checked = HashSet::new();
+    let mut pending = 0;
+    for entry in WalkDir::new(longpath::extend(root)).into_iter().filter_entry(|entry| !is_litter(entry)) {
+        // A folder that couldn't be read may hold files that were never imported
+        let entry = entry.map_err(|e| {
+            CamPorterError::invalid_input(format!("Part of the card couldn't be read, so it can't be cleared for formatting: {}", e))
+        })?;
+        if media_file(&entry).is_none() {
+            continue;
+        }
+        let sidecars = sidecars::find_sidecars(entry.path());
+        for path in std::iter::once(entry.path().to_path_buf()).chain(sidecars) {
+            let path = longpath::strip(&path);
+            if !checked.insert(path.clone()) {
+                continue;
+            }
+            if offload::file_key(&path, root).is_none_or(|key| !history.contains(&key)) {
+                pending += 1;
+            }
+        }
+    }
+    let files = checked.len();
+    if files == 0 {
+        return Err(CamPorterError::invalid_input("There are no media files on the card to check"));
+    }
+    if pending > 0 {
+        return Err(CamPorterError::invalid_input(format!(
+            "{} of {} files on the card haven't been imported yet",
+            pending, files
+        )));
+    }
+
+    let token = new_token()?;
+    let mut issued = clearances.issued.lock().map_err(|e| e.to_string())?;
+    issued.retain(|_, clearance| clearance.issued.elapsed() < CLEARANCE_LIFETIME);
+    issued.insert(
+        token.clone(),
+        Clearance { device_id: drive.device_id.clone(), mount_point, serial, issued: Instant::now() },
+    );
+    Ok(FormatClearance { token, device_id: drive.device_id, offloaded_files: files })
+}
+
+/// The label to give the new file system: the card's current one, cut to fit
+fn new_label(label: Option<String>, fs_type: FileSystemType) -> String {
+    let label: String = label.unwrap_or_else(|| "UNTITLED".to_string()).chars().take(MAX_LABEL).collect();
+    match fs_type {
+        FileSystemType::Fat32 => label.to_uppercase(),
+        FileSystemType::Exfat => label,
+    }
+}
+
+/// Erase the card `device_id` with a new `fs_type` file system, keeping its
+/// label, and return where it was mounted again. Needs a token from
+/// `request_format_clearance` for the same card.
+#[tauri::command]
+pub async fn format_drive(
+    device_id: String,
+    fs_type: FileSystemType,
+    confirmation_token: String,
+    clearances: tauri::State<'_, FormatClearances>,
+    jobs: tauri::State<'_, JobManager>,
+) -> Result<String, CamPorterError> {
+    // Used up whether or not the format goes ahead
+    let clearance = clearances.issued.lock().map_err(|e| e.to_string())?.remove(&confirmation_token);
+    let Some(clearance) = clearance.filter(|c| c.device_id == device_id && c.issued.elapsed() < CLEARANCE_LIFETIME) else {
+        return Err(CamPorterError::PermissionDenied {
+            message: "The confirmation is missing, expired or for another card; check the card again".to_string(),
+        });
+    };
+    let mount_point = clearance.mount_point;
+    let Some(drive) = crate::removable_drives()
+        .into_iter()
+        .find(|d| d.mount_point == mount_point && d.serial.as_ref() == Some(&clearance.serial))
+    else {
+        return Err(CamPorterError::not_found("The card was removed or replaced since it was checked"));
+    };
+    if jobs.using(&mount_point) > 0 {
+        return Err(CamPorterError::busy("An import is still using this card"));
+    }
+    let label = new_label(drive.label, fs_type);
+
+    let formatted = format(&device_id, &mount_point, fs_type, &label)?;
+    info!("Formatted {} ({}) as {:?}, now at {}", device_id, mount_point, fs_type, formatted);
+    Ok(formatted)
+}
+
+#[cfg(target_os = "linux")]
+fn format(device: &str, _mount_point: &str, fs_type: FileSystemType, label: &str) -> Result<String, CamPorterError> {
+    let kind = match fs_type {
+        FileSystemType::Exfat => "exfat",
+        FileSystemType::Fat32 => "vfat",
+    };
+    crate::udisks::Udisks::connect()?.format(device, kind, label)?;
+    crate::partitions::mount(device)
+}
+
+/// `diskutil eraseVolume` unmounts the volume, erases it and mounts the new one
+#[cfg(target_os = "macos")]
+fn format(_device: &str, mount_point: &str, fs_type: FileSystemType, label: &str) -> Result<String, CamPorterError> {
+    use std::process::Command;
+
+    let device = crate::diskarb::device(Path::new(mount_point))?;
+    let personality = match fs_type {
+        FileSystemType::Exfat => "ExFAT",
+        FileSystemType::Fat32 => "MS-DOS FAT32",
+    };
+    let output = crate::watchdog::output(
+        Command::new("diskutil").args(["eraseVolume", personality, label, &device]),
+        crate::watchdog::FORMAT_TIMEOUT,
+    )?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().map(str::trim).rfind(|line| !line.is_empty()).unwrap_or("diskutil failed");
+        return Err(CamPorterError::from(format!("Format failed: {}", reason)));
+    }
+    Ok(crate::diskarb::mount(&device)?.to_string_lossy().to_string())
+}
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    use std::ffi::c_void;
+
+    /// Media types FormatEx takes
+    pub const FMIFS_REMOVABLE: u32 = 0x0B;
+    pub const FMIFS_HARDDISK: u32 = 0x0C;
+    /// The callback command that reports the outcome, a BOOLEAN
+    pub const FMIFS_DONE: u32 = 11;
+
+    pub type FormatCallback = extern "system" fn(command: u32, sub_action: u32, action_info: *mut c_void) -> u8;
+    pub type FormatEx = unsafe extern "system" fn(
+        drive_root: *const u16,
+        media_flag: u32,
+        format: *const u16,
+        label: *const u16,
+        quick_format: i32,
+        cluster_size: u32,
+        callback: FormatCallback,
+    );
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn LoadLibraryW(file_name: *const u16) -> *mut c_void;
+        pub fn GetProcAddress(module: *mut c_void, proc_name: *const u8) -> *mut c_void;
+        pub fn FreeLibrary(module: *mut c_void) -> i32;
+    }
+}
+
+/// Set by `format_progress` when FormatEx finishes
+#[cfg(target_os = "windows")]
+static FORMATTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+#[cfg(target_os = "windows")]
+extern "system" fn format_progress(command: u32, _sub_action: u32, action_info: *mut std::ffi::c_void) -> u8 {
+    if command == win32::FMIFS_DONE && !action_info.is_null() {
+        FORMATTED.store(unsafe { *(action_info as *const u8) } != 0, std::sync::atomic::Ordering::SeqCst);
+    }
+    1
+}
+
+/// Quick-format through FormatEx in fmifs.dll, the routine Explorer's Format
+/// dialog calls. It reports through a callback without a context, so
+/// formats are run one at a time.
+#[cfg(target_os = "windows")]
+fn format(_device: &str, mount_point: &str, fs_type: FileSystemType, label: &str) -> Result<String, CamPorterError> {
+    use std::sync::atomic::Ordering;
+
+    static RUNNING: Mutex<()> = Mutex::new(());
+    let _running = RUNNING.lock().map_err(|e| e.to_string())?;
+    let wide = |text: &str| -> Vec<u16> { text.encode_utf16().chain(Some(0)).collect() };
+
+    let library = unsafe { win32::LoadLibraryW(wide("fmifs.dll").as_ptr()) };
+    if library.is_null() {
+        return Err("Formatting isn't available on this version of Windows".into());
+    }
+    let entry = unsafe { win32::GetProcAddress(library, c"FormatEx".as_ptr().cast()) };
+    if entry.is_null() {
+        unsafe { win32::FreeLibrary(library) };
+        return Err("Formatting isn't available on this version of Windows".into());
+    }
+    let format_ex: win32::FormatEx = unsafe { std::mem::transmute(entry) };
+    let media = if crate::volume::is_removable_media(Path::new(mount_point)) { win32::FMIFS_REMOVABLE } else { win32::FMIFS_HARDDISK };
+    let file_system = match fs_type {
+        FileSystemType::Exfat => "exFAT",
+        FileSystemType::Fat32 => "FAT32",
+    };
+
+    FORMATTED.store(false, Ordering::SeqCst);
+    unsafe {
+        format_ex(
+            wide(mount_point).as_ptr(),
+            media,
+            wide(file_system).as_ptr(),
+            wide(label).as_ptr(),
+            1,
+            0,
+            format_progress,
+        )
+    };
+    unsafe { win32::FreeLibrary(library) };
+    if !FORMATTED.load(Ordering::SeqCst) {
+        return Err(CamPorterError::busy(format!("Format of {} failed; close any files open on the card and try again", mount_point)));
+    }
+    Ok(mount_point.to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn format(_device: &str, _mount_point: &str, _fs_type: FileSystemType, _label: &str) -> Result<String, CamPorterError> {
+    Err("Formatting drives isn't supported on this platform yet".into())
+}
@@ -0,0 +1,860 @@
+// Receiving shots from cameras that push them over Wi-Fi: many Canon, Nikon
+// and Sony bodies upload each file to an FTP server as it's taken. A small
+// server writes uploads into a staging folder, one subfolder per camera
+// address, and once a camera goes quiet for a moment the batch is queued as
+// an ordinary import job, so it's renamed, verified and recorded in the
+// history like a card, and removed from staging once its copy is verified.
+// Explicit FTPS (AUTH TLS) is offered when a certificate is configured.
+// Uploads are written under a temporary name and only handed on once
+// complete; anything left in staging by an earlier session is imported when
+// the receiver next starts.
+use log::{info, warn};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{IpAddr, Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+use ts_rs::TS;
+use walkdir::WalkDir;
+
+use crate::config;
+use crate::error::CamPorterError;
+use crate::import::ImportOptions;
+use crate::jobs::JobManager;
+
+const SETTINGS_KEY: &str = "ftp_receiver";
+
+/// Suffix of uploads still being written
+const PARTIAL_SUFFIX: &str = ".camporter-part";
+
+/// Cameras keep the control connection open between shots
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// How long a camera gets to open the data connection it asked for
+const DATA_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest command line accepted; paths on cameras are short
+const MAX_LINE: u64 = 4096;
+
+/// Wrong passwords before the connection is dropped
+const MAX_LOGIN_ATTEMPTS: u32 = 3;
+
+/// How the receiver is set up; saved when it's started
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct FtpSettings {
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    /// Where uploads wait to be imported; None for a folder in the app data dir
+    pub staging_path: Option<String>,
+    /// Destination of the imports, as for `start_import_job`
+    pub target_path: String,
+    pub options: ImportOptions,
+    /// PEM certificate chain and private key, to accept AUTH TLS
+    pub certificate_path: Option<String>,
+    pub private_key_path: Option<String>,
+    /// Quiet time after a camera's last upload before its batch is imported
+    pub batch_delay_secs: u64,
+}
+
+impl Default for FtpSettings {
+    fn default() -> Self {
+        FtpSettings {
+            port: 2121,
+            username: "camera".to_string(),
+            password: String::new(),
+            staging_path: None,
+            target_path: String::new(),
+            options: ImportOptions::default(),
+            certificate_path: None,
+            private_key_path: None,
+            batch_delay_secs: 5,
+        }
+    }
+}
+
+/// What `get_ftp_receiver_status` reports
+#[derive(Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct FtpStatus {
+    pub running: bool,
+    pub port: u16,
+    /// This computer's address on the local network, to enter on the camera
+    pub address: Option<String>,
+    pub tls: bool,
+    pub staging_path: Option<String>,
+    /// Cameras connected right now
+    pub connections: usize,
+    /// Uploads received since the receiver started
+    pub received_files: u64,
+    /// Import jobs queued from uploads, oldest first
+    pub jobs: Vec<u64>,
+}
+
+/// The running server, if any
+#[derive(Default)]
+pub struct FtpReceiver {
+    server: Mutex<Option<Server>>,
+    /// Uploads already queued for import, so a restart doesn't queue those
+    /// still waiting in staging a second time
+    queued: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+/// State shared by the server's threads
+#[derive(Default)]
+struct Shared {
+    stop: AtomicBool,
+    received: AtomicU64,
+    /// Control connections by id, so stopping can hang up on them
+    connections: Mutex<HashMap<u64, TcpStream>>,
+    jobs: Mutex<Vec<u64>>,
+    queued: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+struct Server {
+    port: u16,
+    tls: bool,
+    staging: PathBuf,
+    shared: Arc<Shared>,
+}
+
+impl Server {
+    fn status(&self) -> FtpStatus {
+        FtpStatus {
+            running: true,
+            port: self.port,
+            address: local_address().map(|ip| ip.to_string()),
+            tls: self.tls,
+            staging_path: Some(self.staging.to_string_lossy().to_string()),
+            connections: self.shared.connections.lock().map(|c| c.len()).unwrap_or(0),
+            received_files: self.shared.received.load(Ordering::Relaxed),
+            jobs: self.shared.jobs.lock().map(|j| j.clone()).unwrap_or_default(),
+        }
+    }
+
+    /// Stop accepting and hang up on every camera; an upload under way
+    /// finishes first
+    fn stop(&self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        if let Ok(connections) = self.shared.connections.lock() {
+            for stream in connections.values() {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+}
+
+/// The address other devices on the network reach this computer at: the
+/// one the OS would send from. Connecting a UDP socket sends nothing.
+fn local_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    socket.local_addr().ok().map(|a| a.ip()).filter(|ip| !ip.is_unspecified())
+}
+
+fn tls_config(settings: &FtpSettings) -> Result<Option<Arc<ServerConfig>>, CamPorterError> {
+    let (Some(certificate), Some(key)) = (&settings.certificate_path, &settings.private_key_path) else {
+        return Ok(None);
+    };
+    let invalid = |what: &str, e: &dyn std::fmt::Display| CamPorterError::invalid_input(format!("{}: {}", what, e));
+    let chain = CertificateDer::pem_file_iter(certificate)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| invalid("Failed to read the certificate", &e))?;
+    let key = PrivateKeyDer::from_pem_file(key).map_err(|e| invalid("Failed to read the private key", &e))?;
+    let config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(chain, key))
+        .map_err(|e| invalid("The certificate can't be used", &e))?;
+    Ok(Some(Arc::new(config)))
+}
+
+fn staging_dir(settings: &FtpSettings, app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    match &settings.staging_path {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(app.path().app_data_dir().map_err(|e| e.to_string())?.join("ftp-inbox")),
+    }
+}
+
+/// Uploads finished in an earlier session and not yet imported; ones cut
+/// off partway are removed
+fn leftovers(staging: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(staging).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        if entry.file_name().to_string_lossy().ends_with(PARTIAL_SUFFIX) {
+            let _ = fs::remove_file(entry.path());
+        } else {
+            files.push(entry.into_path());
+        }
+    }
+    files
+}
+
+/// What sessions tell the import worker about
+enum Upload {
+    Stored(PathBuf),
+    Renamed(PathBuf, PathBuf),
+}
+
+/// Queue uploads as one import once cameras have gone quiet, starting with
+/// those left from before
+fn import_worker(
+    app: tauri::AppHandle,
+    settings: FtpSettings,
+    shared: Arc<Shared>,
+    mut batch: Vec<PathBuf>,
+    uploads: mpsc::Receiver<Upload>,
+) {
+    let delay = Duration::from_secs(settings.batch_delay_secs.max(1));
+    let mut last = Instant::now();
+    loop {
+        let disconnected = match uploads.recv_timeout(delay.saturating_sub(last.elapsed())) {
+            Ok(Upload::Stored(path)) => {
+                // A new file under a name that was imported before
+                if let Ok(mut queued) = shared.queued.lock() {
+                    queued.remove(&path);
+                }
+                batch.retain(|p| p != &path);
+                batch.push(path);
+                last = Instant::now();
+                continue;
+            }
+            Ok(Upload::Renamed(from, to)) => {
+                batch.iter_mut().filter(|p| **p == from).for_each(|p| p.clone_from(&to));
+                last = Instant::now();
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => true,
+        };
+        // Deleted again by the camera, or queued before a restart
+        if let Ok(mut queued) = shared.queued.lock() {
+            batch.retain(|p| p.is_file() && queued.insert(p.clone()));
+        }
+        if !batch.is_empty() {
+            let paths: Vec<String> = batch.drain(..).map(|p| p.to_string_lossy().to_string()).collect();
+            let count = paths.len();
+            match app.state::<JobManager>().enqueue(paths, settings.target_path.clone(), settings.options.clone().for_staged_files()) {
+                Ok(id) => {
+                    info!("Queued import job {} for {} uploaded files", id, count);
+                    if let Ok(mut jobs) = shared.jobs.lock() {
+                        jobs.push(id);
+                    }
+                    let _ = app.emit("ftp-import-queued", serde_json::json!({ "job_id": id, "files": count }));
+                }
+                Err(e) => warn!("Failed to queue uploaded files: {}", e),
+            }
+        }
+        if disconnected {
+            return;
+        }
+        last = Instant::now();
+    }
+}
+
+/// Start the receiver with `settings`, which are saved for next time,
+/// restarting it if it's already running
+#[tauri::command]
+pub fn start_ftp_receiver(
+    settings: FtpSettings,
+    app: tauri::AppHandle,
+    receiver: tauri::State<'_, FtpReceiver>,
+) -> Result<FtpStatus, CamPorterError> {
+    if settings.target_path.trim().is_empty() {
+        return Err(CamPorterError::invalid_input("Choose a destination for uploaded files"));
+    }
+    if settings.username.is_empty() || settings.password.is_empty() {
+        return Err(CamPorterError::invalid_input("The receiver needs a user name and password for cameras to log in with"));
+    }
+    if settings.certificate_path.is_some() != settings.private_key_path.is_some() {
+        return Err(CamPorterError::invalid_input("FTPS needs both a certificate and its private key"));
+    }
+    let tls = tls_config(&settings)?;
+    let staging = staging_dir(&settings, &app)?;
+    fs::create_dir_all(&staging).map_err(|e| CamPorterError::io(format!("Failed to create {}", staging.display()), e))?;
+    config::set(&app, SETTINGS_KEY, &settings)?;
+
+    let mut server = receiver.server.lock().map_err(|e| e.to_string())?;
+    if let Some(running) = server.take() {
+        running.stop();
+    }
+    let listener = TcpListener::bind(("0.0.0.0", settings.port)).map_err(|e| match e.kind() {
+        io::ErrorKind::AddrInUse => CamPorterError::busy(format!("Port {} is already in use", settings.port)),
+        _ => CamPorterError::io(format!("Failed to listen on port {}", settings.port), e),
+    })?;
+    listener.set_nonblocking(true).map_err(|e| CamPorterError::io("Failed to set up the listener", e))?;
+
+    let shared = Arc::new(Shared { queued: receiver.queued.clone(), ..Default::default() });
+    let (uploads, received) = mpsc::channel();
+    {
+        let (app, settings, shared, left) = (app.clone(), settings.clone(), shared.clone(), leftovers(&staging));
+        thread::spawn(move || import_worker(app, settings, shared, left, received));
+    }
+    let running = Server { port: settings.port, tls: tls.is_some(), staging: staging.clone(), shared: shared.clone() };
+    let status = running.status();
+    *server = Some(running);
+    info!("FTP receiver listening on port {}{}", settings.port, if status.tls { " with FTPS" } else { "" });
+
+    thread::spawn(move || accept_loop(listener, app, Arc::new(settings), tls, staging, shared, uploads));
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn stop_ftp_receiver(receiver: tauri::State<'_, FtpReceiver>) -> Result<(), CamPorterError> {
+    if let Some(server) = receiver.server.lock().map_err(|e| e.to_string())?.take() {
+        server.stop();
+        info!("FTP receiver stopped");
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_ftp_receiver_status(receiver: tauri::State<'_, FtpReceiver>) -> Result<FtpStatus, CamPorterError> {
+    let server = receiver.server.lock().map_err(|e| e.to_string())?;
+    Ok(server.as_ref().map(Server::status).unwrap_or_default())
+}
+
+/// The settings last started with, or the defaults
+#[tauri::command]
+pub fn get_ftp_settings(app: tauri::AppHandle) -> Result<FtpSettings, CamPorterError> {
+    Ok(config::get(&app, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+fn accept_loop(
+    listener: TcpListener,
+    app: tauri::AppHandle,
+    settings: Arc<FtpSettings>,
+    tls: Option<Arc<ServerConfig>>,
+    staging: PathBuf,
+    shared: Arc<Shared>,
+    uploads: Sender<Upload>,
+) {
+    let mut next_id = 0;
+    while !shared.stop.load(Ordering::Relaxed) {
+        let (stream, peer) = match listener.accept() {
+            Ok(accepted) => accepted,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(200));
+                continue;
+            }
+            Err(e) => {
+                warn!("FTP accept failed: {}", e);
+                thread::sleep(Duration::from_millis(200));
+                continue;
+            }
+        };
+        next_id += 1;
+        let id = next_id;
+        if let (Ok(mut connections), Ok(clone)) = (shared.connections.lock(), stream.try_clone()) {
+            connections.insert(id, clone);
+        }
+        info!("FTP connection from {}", peer);
+        let session = Session {
+            app: app.clone(),
+            settings: settings.clone(),
+            tls: tls.clone(),
+            shared: shared.clone(),
+            uploads: uploads.clone(),
+            // IPv6 addresses can't go in a folder name on Windows as they are
+            root: staging.join(peer.ip().to_string().replace(':', "-")),
+            peer,
+            cwd: Vec::new(),
+            logged_in: false,
+            user: None,
+            attempts: 0,
+            protect_data: false,
+            passive: None,
+            active: None,
+            rename_from: None,
+        };
+        thread::spawn(move || {
+            let shared = session.shared.clone();
+            if let Err(e) = session.run(stream) {
+                warn!("FTP session with {} ended: {}", peer, e);
+            }
+            if let Ok(mut connections) = shared.connections.lock() {
+                connections.remove(&id);
+            };
+        });
+    }
+}
+
+/// A connection, in the clear or after AUTH TLS / PROT P
+enum Stream {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ServerConnection, TcpStream>>),
+}
+
+impl Stream {
+    fn tls(config: &Arc<ServerConfig>, stream: TcpStream) -> io::Result<Stream> {
+        let connection = ServerConnection::new(config.clone()).map_err(io::Error::other)?;
+        Ok(Stream::Tls(Box::new(StreamOwned::new(connection, stream))))
+    }
+
+    /// Shut the write side down cleanly, so the camera knows the data is all there
+    fn finish(mut self) -> io::Result<()> {
+        if let Stream::Tls(tls) = &mut self {
+            tls.conn.send_close_notify();
+        }
+        self.flush()
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buf),
+            Stream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buf),
+            Stream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// One camera's control connection
+struct Session {
+    app: tauri::AppHandle,
+    settings: Arc<FtpSettings>,
+    tls: Option<Arc<ServerConfig>>,
+    shared: Arc<Shared>,
+    uploads: Sender<Upload>,
+    /// This camera's folder in staging, which is "/" to it
+    root: PathBuf,
+    peer: SocketAddr,
+    /// Folders under the root, as the camera sees them
+    cwd: Vec<String>,
+    logged_in: bool,
+    user: Option<String>,
+    attempts: u32,
+    /// PROT P: data connections are encrypted too
+    protect_data: bool,
+    passive: Option<TcpListener>,
+    /// Where to connect for data after PORT
+    active: Option<SocketAddr>,
+    rename_from: Option<PathBuf>,
+}
+
+/// What to do after answering a command
+enum Next {
+    Continue,
+    StartTls,
+    Quit,
+}
+
+impl Session {
+    fn run(mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+        let mut control = BufReader::new(Stream::Plain(stream.try_clone()?));
+        reply(control.get_mut(), 220, "CamPorter ready")?;
+        loop {
+            let mut line = String::new();
+            if Read::by_ref(&mut control).take(MAX_LINE).read_line(&mut line)? == 0 || self.shared.stop.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+            match self.command(&command.to_ascii_uppercase(), argument, control.get_mut())? {
+                Next::Continue => {}
+                Next::StartTls => {
+                    let Some(config) = &self.tls else { continue };
+                    control = BufReader::new(Stream::tls(config, stream.try_clone()?)?);
+                }
+                Next::Quit => return Ok(()),
+            }
+        }
+    }
+
+    fn command(&mut self, command: &str, argument: &str, out: &mut Stream) -> io::Result<Next> {
+        let tls_active = matches!(out, Stream::Tls(_));
+        match command {
+            "USER" => {
+                self.user = Some(argument.to_string());
+                self.logged_in = false;
+                reply(out, 331, "Password required")?;
+            }
+            "PASS" => {
+                let valid = self.user.as_deref() == Some(&self.settings.username) && argument == self.settings.password;
+                if valid {
+                    self.logged_in = true;
+                    reply(out, 230, "Logged in")?;
+                } else {
+                    self.attempts += 1;
+                    // Slows down guessing
+                    thread::sleep(Duration::from_secs(1));
+                    reply(out, 530, "Login incorrect")?;
+                    if self.attempts >= MAX_LOGIN_ATTEMPTS {
+                        return Ok(Next::Quit);
+                    }
+                }
+            }
+            "AUTH" if matches!(argument.to_ascii_uppercase().as_str(), "TLS" | "SSL" | "TLS-C") => {
+                if tls_active {
+                    reply(out, 503, "Already using TLS")?;
+                } else if self.tls.is_none() {
+                    reply(out, 504, "TLS is not set up on this server")?;
+                } else {
+                    reply(out, 234, "Proceed with negotiation")?;
+                    return Ok(Next::StartTls);
+                }
+            }
+            "AUTH" => reply(out, 504, "Unsupported security mechanism")?,
+            "PBSZ" => reply(out, 200, "PBSZ=0")?,
+            "PROT" => match argument.to_ascii_uppercase().as_str() {
+                "C" => {
+                    self.protect_data = false;
+                    reply(out, 200, "Data connections in the clear")?;
+                }
+                "P" if tls_active => {
+                    self.protect_data = true;
+                    reply(out, 200, "Data connections encrypted")?;
+                }
+                "P" => reply(out, 503, "AUTH TLS first")?,
+                _ => reply(out, 504, "Unsupported protection level")?,
+            },
+            "FEAT" => {
+                let mut features = vec!["EPSV", "PASV", "SIZE", "UTF8"];
+                if self.tls.is_some() {
+                    features.extend(["AUTH TLS", "PBSZ", "PROT"]);
+                }
+                let listing: String = features.iter().map(|f| format!(" {}\r\n", f)).collect();
+                out.write_all(format!("211-Features:\r\n{}211 End\r\n", listing).as_bytes())?;
+            }
+            "SYST" => reply(out, 215, "UNIX Type: L8")?,
+            "OPTS" => reply(out, 200, "OK")?,
+            "NOOP" => reply(out, 200, "OK")?,
+            "QUIT" => {
+                reply(out, 221, "Goodbye")?;
+                return Ok(Next::Quit);
+            }
+            _ if !self.logged_in => reply(out, 530, "Log in first")?,
+            _ => self.file_command(command, argument, out)?,
+        }
+        Ok(Next::Continue)
+    }
+
+    /// Commands that only work once logged in
+    fn file_command(&mut self, command: &str, argument: &str, out: &mut Stream) -> io::Result<()> {
+        match command {
+            "TYPE" | "MODE" | "STRU" => {
+                let supported = match command {
+                    "TYPE" => ["A", "A N", "I", "L 8"].contains(&argument.to_ascii_uppercase().as_str()),
+                    "MODE" => argument.eq_ignore_ascii_case("S"),
+                    _ => argument.eq_ignore_ascii_case("F"),
+                };
+                // Everything is stored as sent; cameras only send binary
+                if supported {
+                    reply(out, 200, "OK")
+                } else {
+                    reply(out, 504, "Not supported")
+                }
+            }
+            "PWD" | "XPWD" => reply(out, 257, &format!("\"/{}\"", self.cwd.join("/"))),
+            "CWD" | "XCWD" => match self.resolve(argument) {
+                // Cameras often change into their upload folder without making it first
+                Some(folders) if fs::create_dir_all(self.local(&folders)).is_ok() => {
+                    self.cwd = folders;
+                    reply(out, 250, "OK")
+                }
+                _ => reply(out, 550, "No such folder"),
+            },
+            "CDUP" | "XCUP" => {
+                self.cwd.pop();
+                reply(out, 250, "OK")
+            }
+            "MKD" | "XMKD" => match self.resolve(argument) {
+                Some(folders) if fs::create_dir_all(self.local(&folders)).is_ok() => {
+                    reply(out, 257, &format!("\"/{}\" created", folders.join("/")))
+                }
+                _ => reply(out, 550, "Can't create folder"),
+            },
+            "PASV" => self.passive(out, false),
+            "EPSV" => self.passive(out, true),
+            "PORT" => match parse_port(argument).filter(|address| address.ip() == self.peer.ip()) {
+                // Only back to the camera itself, so the server can't be used to reach other hosts
+                Some(address) => {
+                    self.active = Some(address);
+                    self.passive = None;
+                    reply(out, 200, "OK")
+                }
+                None => reply(out, 501, "Bad address"),
+            },
+            "STOR" => self.store(argument, out),
+            "LIST" | "NLST" => self.list(argument, command == "NLST", out),
+            "SIZE" => match self.resolve(argument).map(|f| self.local(&f)).and_then(|p| fs::metadata(p).ok()) {
+                Some(metadata) if metadata.is_file() => reply(out, 213, &metadata.len().to_string()),
+                _ => reply(out, 550, "No such file"),
+            },
+            "DELE" => match self.resolve(argument).map(|f| self.local(&f)) {
+                Some(path) if fs::remove_file(&path).is_ok() => reply(out, 250, "Deleted"),
+                _ => reply(out, 550, "Can't delete"),
+            },
+            "RNFR" => match self.resolve(argument).map(|f| self.local(&f)).filter(|p| p.exists()) {
+                Some(path) => {
+                    self.rename_from = Some(path);
+                    reply(out, 350, "Ready for RNTO")
+                }
+                None => reply(out, 550, "No such file"),
+            },
+            "RNTO" => {
+                let Some(from) = self.rename_from.take() else {
+                    return reply(out, 503, "RNFR first");
+                };
+                match self.resolve(argument).map(|f| self.local(&f)) {
+                    Some(to) if fs::rename(&from, &to).is_ok() => {
+                        let _ = self.uploads.send(Upload::Renamed(from, to));
+                        reply(out, 250, "Renamed")
+                    }
+                    _ => reply(out, 550, "Can't rename"),
+                }
+            }
+            _ => reply(out, 502, "Not implemented"),
+        }
+    }
+
+    /// Folders under the root that `path` names, seen from the current folder
+    fn resolve(&self, path: &str) -> Option<Vec<String>> {
+        resolve(&self.cwd, path)
+    }
+
+    fn local(&self, folders: &[String]) -> PathBuf {
+        folders.iter().fold(self.root.clone(), |path, part| path.join(part))
+    }
+
+    fn passive(&mut self, out: &mut Stream, extended: bool) -> io::Result<()> {
+        let ip = match out {
+            Stream::Plain(stream) => stream.local_addr()?.ip(),
+            Stream::Tls(stream) => stream.sock.local_addr()?.ip(),
+        };
+        let listener = TcpListener::bind((ip, 0))?;
+        let port = listener.local_addr()?.port();
+        self.passive = Some(listener);
+        self.active = None;
+        match ip {
+            IpAddr::V4(v4) if !extended => {
+                let [a, b, c, d] = v4.octets();
+                reply(out, 227, &format!("Entering Passive Mode ({},{},{},{},{},{})", a, b, c, d, port >> 8, port & 0xff))
+            }
+            IpAddr::V6(_) if !extended => reply(out, 522, "Use EPSV"),
+            _ => reply(out, 229, &format!("Entering Extended Passive Mode (|||{}|)", port)),
+        }
+    }
+
+    /// The data connection set up by the last PASV, EPSV or PORT
+    fn data(&mut self) -> io::Result<Stream> {
+        let stream = if let Some(listener) = self.passive.take() {
+            listener.set_nonblocking(true)?;
+            let started = Instant::now();
+            loop {
+                match listener.accept() {
+                    // Only from the camera that asked for it
+                    Ok((stream, peer)) if peer.ip() == self.peer.ip() => break stream,
+                    Ok(_) => {}
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock && started.elapsed() < DATA_TIMEOUT => {
+                        thread::sleep(Duration::from_millis(20));
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        } else if let Some(address) = self.active.take() {
+            TcpStream::connect_timeout(&address, DATA_TIMEOUT)?
+        } else {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, "No PASV or PORT first"));
+        };
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(DATA_TIMEOUT))?;
+        match (&self.tls, self.protect_data) {
+            (Some(config), true) => Stream::tls(config, stream),
+            _ => Ok(Stream::Plain(stream)),
+        }
+    }
+
+    fn store(&mut self, argument: &str, out: &mut Stream) -> io::Result<()> {
+        let Some(folders) = self.resolve(argument).filter(|f| !f.is_empty() && !self.local(f).is_dir()) else {
+            return reply(out, 553, "Bad file name");
+        };
+        let path = self.local(&folders);
+        let name = &folders[folders.len() - 1];
+        let partial = path.with_file_name(format!(".{}{}", name, PARTIAL_SUFFIX));
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let file = match File::create(&partial) {
+            Ok(file) => file,
+            Err(e) => return reply(out, 553, &format!("Can't create file: {}", e)),
+        };
+        reply(out, 150, "Ready to receive")?;
+        let received = self.data().and_then(|mut data| {
+            let mut writer = BufWriter::new(file);
+            let size = io::copy(&mut data, &mut writer)?;
+            writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            Ok(size)
+        });
+        match received.and_then(|size| fs::rename(&partial, &path).map(|_| size)) {
+            Ok(size) => {
+                info!("Received {} ({} bytes) from {}", path.display(), size, self.peer);
+                self.shared.received.fetch_add(1, Ordering::Relaxed);
+                let _ = self.app.emit("ftp-received", serde_json::json!({ "path": path, "size": size }));
+                let _ = self.uploads.send(Upload::Stored(path));
+                reply(out, 226, "Transfer complete")
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&partial);
+                warn!("Upload of {} from {} failed: {}", path.display(), self.peer, e);
+                reply(out, 426, "Transfer failed")
+            }
+        }
+    }
+
+    fn list(&mut self, argument: &str, names_only: bool, out: &mut Stream) -> io::Result<()> {
+        // Options such as "-la" that some clients send
+        let argument = if argument.starts_with('-') { "" } else { argument };
+        let Some(dir) = self.resolve(argument).map(|f| self.local(&f)) else {
+            return reply(out, 550, "No such folder");
+        };
+        let mut entries: Vec<_> = fs::read_dir(&dir)
+            .map(|entries| entries.filter_map(|e| e.ok()).collect())
+            .unwrap_or_default();
+        entries.retain(|e| !e.file_name().to_string_lossy().ends_with(PARTIAL_SUFFIX));
+        entries.sort_by_key(|e| e.file_name());
+        let listing: String = entries
+            .iter()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if names_only {
+                    return Some(format!("{}\r\n", name));
+                }
+                let metadata = entry.metadata().ok()?;
+                let modified = metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).unwrap_or_default();
+                Some(listing_line(&name, metadata.is_dir(), metadata.len(), modified.as_secs()))
+            })
+            .collect();
+        reply(out, 150, "Here comes the listing")?;
+        match self.data().and_then(|mut data| data.write_all(listing.as_bytes()).and_then(|_| data.finish())) {
+            Ok(()) => reply(out, 226, "Listing sent"),
+            Err(_) => reply(out, 426, "Listing failed"),
+        }
+    }
+}
+
+/// Folders under a camera's root that `path` names, seen from `cwd`; None
+/// for anything trying to leave the root or that makes no sense as a file name
+fn resolve(cwd: &[String], path: &str) -> Option<Vec<String>> {
+    let mut folders = if path.starts_with('/') { Vec::new() } else { cwd.to_vec() };
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                folders.pop()?;
+            }
+            _ if part.contains(['\\', ':', '\0']) || part.ends_with(PARTIAL_SUFFIX) => return None,
+            _ => folders.push(part.to_string()),
+        }
+    }
+    Some(folders)
+}
+
+/// One entry of a LIST reply, in the `ls -l` form cameras and clients parse
+fn listing_line(name: &str, is_dir: bool, size: u64, modified: u64) -> String {
+    let date = chrono::DateTime::from_timestamp(modified as i64, 0).unwrap_or_default();
+    let mode = if is_dir { "drwxr-xr-x" } else { "-rw-r--r--" };
+    format!("{} 1 ftp ftp {} {} {}\r\n", mode, size, date.format("%b %d %H:%M"), name)
+}
+
+fn reply(out: &mut Stream, code: u16, message: &str) -> io::Result<()> {
+    out.write_all(format!("{} {}\r\n", code, message).as_bytes())?;
+    out.flush()
+}
+
+/// The address in a PORT argument, "h1,h2,h3,h4,p1,p2"
+fn parse_port(argument: &str) -> Option<SocketAddr> {
+    let numbers: Vec<u8> = argument.split(',').map(|n| n.trim().parse().ok()).collect::<Option<_>>()?;
+    let [a, b, c, d, p1, p2] = numbers[..] else {
+        return None;
+    };
+    Some(SocketAddr::from(([a, b, c, d], u16::from(p1) << 8 | u16::from(p2))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folders(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_port_arguments() {
+        assert_eq!(parse_port("192,168,1,20,4,1"), Some(SocketAddr::from(([192, 168, 1, 20], 1025))));
+        assert_eq!(parse_port(" 10, 0, 0, 1, 0, 21 "), Some(SocketAddr::from(([10, 0, 0, 1], 21))));
+        assert_eq!(parse_port("192,168,1,20,4"), None);
+        assert_eq!(parse_port("192,168,1,20,4,1,9"), None);
+        assert_eq!(parse_port("192,168,1,256,4,1"), None);
+        assert_eq!(parse_port(""), None);
+    }
+
+    #[test]
+    fn resolves_paths_within_the_root() {
+        let cwd = folders(&["DCIM"]);
+        assert_eq!(resolve(&cwd, "100CANON/IMG_0001.JPG"), Some(folders(&["DCIM", "100CANON", "IMG_0001.JPG"])));
+        assert_eq!(resolve(&cwd, "/IMG_0001.JPG"), Some(folders(&["IMG_0001.JPG"])));
+        assert_eq!(resolve(&cwd, "./a//b/../c"), Some(folders(&["DCIM", "a", "c"])));
+        assert_eq!(resolve(&cwd, ".."), Some(Vec::new()));
+        assert_eq!(resolve(&cwd, ""), Some(cwd.clone()));
+    }
+
+    #[test]
+    fn refuses_paths_leaving_the_root() {
+        assert_eq!(resolve(&[], ".."), None);
+        assert_eq!(resolve(&folders(&["DCIM"]), "../../etc"), None);
+        assert_eq!(resolve(&[], "C:\\Windows"), None);
+        assert_eq!(resolve(&[], "a\\..\\b"), None);
+        assert_eq!(resolve(&[], "nul\0byte"), None);
+        assert_eq!(resolve(&[], &format!(".IMG_0001.JPG{}", PARTIAL_SUFFIX)), None);
+    }
+
+    #[test]
+    fn formats_listing_lines() {
+        // 2024-06-01 14:05:09 UTC
+        let modified = 1_717_250_709;
+        assert_eq!(listing_line("IMG_0001.JPG", false, 2048, modified), "-rw-r--r-- 1 ftp ftp 2048 Jun 01 14:05 IMG_0001.JPG\r\n");
+        assert_eq!(listing_line("100CANON", true, 4096, modified), "drwxr-xr-x 1 ftp ftp 4096 Jun 01 14:05 100CANON\r\n");
+    }
+
+    #[test]
+    fn keeps_finished_leftovers_and_removes_partial_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let camera = dir.path().join("192.168.1.20");
+        fs::create_dir_all(&camera).unwrap();
+        fs::write(camera.join("IMG_0001.JPG"), b"done").unwrap();
+        let partial = camera.join(format!(".IMG_0002.JPG{}", PARTIAL_SUFFIX));
+        fs::write(&partial, b"cut off").unwrap();
+
+        assert_eq!(leftovers(dir.path()), vec![camera.join("IMG_0001.JPG")]);
+        assert!(!partial.exists());
+    }
+}
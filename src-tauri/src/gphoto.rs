@@ -0,0 +1,117 @@
+// Cameras connected in PTP mode (most Canon, Nikon and Sony bodies on a USB
+// cable), through the gphoto2 command-line tool and the libgphoto2 camera
+// drivers behind it. Built with the `gphoto2` feature. Cameras are told
+// apart by their USB port, such as "usb:001,012", which changes when the
+// cable is replugged. On Linux the desktop's own gphoto2 volume monitor may
+// hold the camera, in which case gphoto2 reports that it can't claim it.
+use std::path::Path;
+use std::process::Command;
+use which::which;
+
+use crate::devices::DeviceObject;
+use crate::watchdog;
+
+/// The `gphoto2` binary, if one is installed
+fn gphoto2() -> Option<Command> {
+    which("gphoto2").ok().map(Command::new)
+}
+
+fn run(command: &mut Command, timeout: std::time::Duration) -> Result<String, String> {
+    let output = watchdog::output(command, timeout)?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if output.status.success() {
+        Ok(stdout)
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // gphoto2 explains itself in an "*** Error ..." block on stderr
+        let reason = stderr.lines().map(str::trim).rfind(|line| !line.is_empty() && !line.starts_with("***"));
+        Err(reason.unwrap_or_else(|| stdout.trim()).to_string())
+    }
+}
+
+/// A camera by its port, so commands go to it and not another one
+fn camera(port: &str) -> Result<Command, String> {
+    let mut command = gphoto2().ok_or("gphoto2 is not installed")?;
+    command.args(["--port", port]);
+    Ok(command)
+}
+
+/// Port and model of every camera, from the table `--auto-detect` prints
+/// under a "Model  Port" header and a line of dashes
+pub fn list_devices() -> Result<Vec<(String, String)>, String> {
+    let Some(mut command) = gphoto2() else {
+        return Ok(Vec::new());
+    };
+    let output = run(command.arg("--auto-detect"), watchdog::PROBE_TIMEOUT)?;
+    Ok(output
+        .lines()
+        .skip_while(|line| !line.starts_with("---"))
+        .skip(1)
+        .filter_map(|line| {
+            let (model, port) = line.trim_end().rsplit_once(char::is_whitespace)?;
+            // Serial and PTP/IP ports are offered too, but only USB ones are cameras that are there
+            port.starts_with("usb:").then(|| (port.to_string(), model.trim().to_string()))
+        })
+        .collect())
+}
+
+/// A folder listed by `--list-files`, from its "There are 2 files in folder
+/// '/store_00010001/DCIM/100CANON':" header
+fn folder_header(line: &str) -> Option<&str> {
+    let (_, folder) = line.split_once(" in folder '")?;
+    folder.trim_end_matches(':').strip_suffix('\'')
+}
+
+/// Name, size and time of a "#1     IMG_0001.JPG  rd  4523 KB image/jpeg
+/// 1717250602" line. Sizes are only given in whole kilobytes.
+fn parse_file(line: &str) -> Option<(String, u64, u64)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let name = fields.get(1)?;
+    let kb = fields.iter().position(|&field| field == "KB")?;
+    let size = fields.get(kb - 1)?.parse::<u64>().ok()? * 1024;
+    let modified = fields.last().filter(|field| field.len() >= 9).and_then(|field| field.parse().ok()).unwrap_or(0);
+    Some((name.to_string(), size, modified))
+}
+
+/// Files in every folder of every storage on the camera, each with the
+/// folder that holds it (without the leading slash) as its object
+pub fn list_media(port: &str) -> Result<Vec<DeviceObject>, String> {
+    let output = run(camera(port)?.arg("--list-files"), watchdog::DEVICE_LIST_TIMEOUT)?;
+    let mut folder = None;
+    let mut objects = Vec::new();
+    for line in output.lines() {
+        if let Some(header) = folder_header(line) {
+            folder = Some(header.trim_start_matches('/').to_string());
+        } else if let (Some(folder), true) = (&folder, line.starts_with('#')) {
+            if let Some((name, size, modified)) = parse_file(line) {
+                objects.push(DeviceObject { object: folder.clone(), name, size, modified });
+            }
+        }
+    }
+    Ok(objects)
+}
+
+/// Copy `name` from `folder` on the camera to `target`. gphoto2 fetches files
+/// by their number in the folder, so the folder is listed again to find it.
+pub fn download(port: &str, folder: &str, name: &str, target: &Path) -> Result<(), String> {
+    let folder = format!("/{}", folder);
+    let listing = run(camera(port)?.args(["--folder", &folder, "--no-recurse", "--list-files"]), watchdog::DEVICE_LIST_TIMEOUT)?;
+    let number = listing
+        .lines()
+        .filter(|line| line.starts_with('#'))
+        .position(|line| parse_file(line).is_some_and(|(listed, _, _)| listed == name))
+        .ok_or_else(|| format!("{} is no longer on the camera", name))?
+        + 1;
+    // --filename expands %-patterns, so a literal % is doubled
+    let filename = target.to_string_lossy().replace('%', "%%");
+    run(
+        camera(port)?
+            .args(["--folder", &folder, "--no-recurse", "--get-file", &number.to_string()])
+            .args(["--filename", &filename, "--force-overwrite"]),
+        watchdog::DEVICE_TRANSFER_TIMEOUT,
+    )?;
+    if !target.is_file() {
+        return Err("gphoto2 wrote nothing".to_string());
+    }
+    Ok(())
+}
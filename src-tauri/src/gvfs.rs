@@ -0,0 +1,119 @@
+// Cameras GNOME mounts through its gphoto2 GVFS backend, which sysinfo
+// doesn't list: they turn up as folders under /run/user/<uid>/gvfs named
+// like "gphoto2:host=Canon_Inc._Canon_EOS_R5", a FUSE view of the camera's
+// storage with DCIM inside a folder per card slot ("store_00010001"). They
+// are listed as drives and read like a card; while GVFS holds the camera,
+// the gphoto2 tool can't claim it.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::dto::{Bus, Confidence, RemovableDrive};
+use crate::error::CamPorterError;
+use crate::{layout, volume, watchdog};
+
+const PREFIX: &str = "gphoto2:host=";
+
+fn gvfs_root() -> PathBuf {
+    Path::new("/run/user").join(unsafe { libc::getuid() }.to_string()).join("gvfs")
+}
+
+/// Undo the %-escapes GVFS uses for characters not allowed in a host name
+fn unescape(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%').then(|| text.get(i + 1..i + 3)).flatten().and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// The camera's name from its mount's host, "Canon_Inc._Canon_EOS_R5"
+/// becoming "Canon Inc. Canon EOS R5"; cameras gphoto2 has no name for are
+/// known by their port, such as "[usb:001,012]"
+fn camera_name(host: &str) -> String {
+    unescape(host).replace('_', " ")
+}
+
+/// The storage folder holding DCIM, so the vendor comes from its folders
+fn media_root(root: &Path) -> PathBuf {
+    fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .find(|dir| dir.join("DCIM").is_dir())
+        .unwrap_or_else(|| root.to_path_buf())
+}
+
+/// A drive entry for each camera GVFS has mounted
+pub fn mounts() -> Vec<RemovableDrive> {
+    let Ok(entries) = fs::read_dir(gvfs_root()) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter_map(|entry| {
+            let dir_name = entry.file_name().to_string_lossy().to_string();
+            let host = dir_name.strip_prefix(PREFIX)?.to_string();
+            let mount_point = entry.path();
+            let (_, camera_hint) = layout::camera_card(&media_root(&mount_point));
+            Some(RemovableDrive {
+                name: format!("{} (camera)", camera_name(&host)),
+                mount_point: mount_point.to_string_lossy().to_string(),
+                device_id: dir_name,
+                mounted_by: None,
+                // Cameras don't report their storage sizes through GVFS
+                total_bytes: 0,
+                available_bytes: 0,
+                file_system: "gvfs".to_string(),
+                is_read_only: volume::is_read_only(&mount_point),
+                is_write_protected: false,
+                label: None,
+                serial: None,
+                // The volume monitor finds cameras on USB; ones over PTP/IP
+                // are only mounted by hand
+                bus: (!unescape(&host).contains("ptpip:")).then_some(Bus::Usb),
+                physical_device: None,
+                confidence: Confidence::Definite,
+                is_camera_card: true,
+                camera_hint,
+                profile: None,
+                auto_import_allowed: true,
+                last_used: false,
+            })
+        })
+        .collect()
+}
+
+pub fn is_gvfs(mount_point: &str) -> bool {
+    Path::new(mount_point).starts_with(gvfs_root())
+}
+
+/// Have GVFS let go of the camera. It commits each change on the camera as
+/// it's made, so nothing is left to flush once the mount is gone.
+pub fn unmount(mount_point: &str) -> Result<(), CamPorterError> {
+    let host = Path::new(mount_point)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix(PREFIX))
+        .ok_or_else(|| CamPorterError::not_found(format!("{} is not a GVFS camera mount", mount_point)))?;
+    let uri = format!("gphoto2://{}/", host);
+    let output = watchdog::output(Command::new("gio").args(["mount", "-u", &uri]), watchdog::MOUNT_TIMEOUT)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CamPorterError::busy(format!("Unmount failed: {}", stderr.trim())));
+    }
+    Ok(())
+}
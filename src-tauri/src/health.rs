@@ -0,0 +1,250 @@
+// Early warning for a card that's wearing out. Drives that speak SMART
+// (SATA and NVMe disks, including most behind USB bridges) are asked through
+// smartctl, from smartmontools, for their verdict, reallocated sectors and
+// lifetime writes. SD and CF cards almost never answer, so every import also
+// counts the read errors it hits on its source card, kept by volume serial
+// across sessions; a card that keeps failing reads is the one to retire.
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use ts_rs::TS;
+use which::which;
+
+use crate::config;
+use crate::dto::RemovableDrive;
+use crate::error::CamPorterError;
+use crate::watchdog;
+
+const HISTORY_KEY: &str = "card_health";
+
+/// Reallocated, pending or uncorrectable sectors a drive may show before
+/// it's called failing rather than worn
+const FAILING_SECTORS: u64 = 50;
+
+/// Rated endurance used up, as NVMe and some SSDs report it, from which a
+/// drive is called worn
+const WORN_PERCENT: u8 = 90;
+
+#[derive(Serialize, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// Nothing known either way: no SMART and no imports from it yet
+    Unknown,
+    Healthy,
+    /// Still readable, but errors or wear say it's worth replacing
+    Worn,
+    /// SMART says it's failing, or reads keep failing; copy it off now
+    Failing,
+}
+
+/// What the drive reports about itself through SMART
+#[derive(Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct SmartReport {
+    /// The drive's own overall verdict
+    pub passed: Option<bool>,
+    pub reallocated_sectors: Option<u64>,
+    /// Sectors waiting to be reallocated once they're next written
+    pub pending_sectors: Option<u64>,
+    /// Sectors or blocks the drive couldn't read back, media errors on NVMe
+    pub uncorrectable_errors: Option<u64>,
+    pub bytes_written: Option<u64>,
+    pub power_on_hours: Option<u64>,
+    /// Share of its rated endurance used, which can pass 100
+    pub percentage_used: Option<u8>,
+}
+
+/// Read errors seen on one card over every import from it
+#[derive(Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct ReadErrorHistory {
+    pub imports: u64,
+    /// Imports that hit at least one read error
+    pub imports_with_errors: u64,
+    pub read_errors: u64,
+    /// Whether the latest import from it hit any
+    pub last_import_had_errors: bool,
+    /// Seconds since the Unix epoch
+    pub last_error_at: Option<u64>,
+}
+
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct CardHealth {
+    pub status: HealthStatus,
+    /// Why the status isn't healthy, one line each
+    pub warnings: Vec<String>,
+    pub smart: Option<SmartReport>,
+    /// Why there's no SMART report, such as "smartctl is not installed"
+    pub smart_unavailable: Option<String>,
+    /// None for cards without a volume serial, which can't be told apart
+    /// from one import to the next
+    pub history: Option<ReadErrorHistory>,
+}
+
+fn load(app: &tauri::AppHandle) -> Result<BTreeMap<String, ReadErrorHistory>, String> {
+    Ok(config::get(app, HISTORY_KEY)?.unwrap_or_default())
+}
+
+/// Count an import's read errors against the card its files came from.
+/// Imports from more than one card, or from a card without a serial,
+/// aren't counted.
+pub fn record_import(app: &tauri::AppHandle, file_paths: &[String], read_errors: u64) {
+    let drives = crate::removable_drives();
+    let Some(serial) = card_serial(&drives, file_paths) else {
+        return;
+    };
+    let mut histories = load(app).unwrap_or_default();
+    let history = histories.entry(serial).or_default();
+    history.imports += 1;
+    history.last_import_had_errors = read_errors > 0;
+    if read_errors > 0 {
+        history.imports_with_errors += 1;
+        history.read_errors += read_errors;
+        history.last_error_at = Some(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs());
+    }
+    if let Err(e) = config::set(app, HISTORY_KEY, &histories) {
+        warn!("Failed to save card health: {}", e);
+    }
+}
+
+/// The serial of the one volume holding every file
+fn card_serial(drives: &[RemovableDrive], file_paths: &[String]) -> Option<String> {
+    let mut serials = file_paths.iter().map(|path| {
+        drives
+            .iter()
+            .filter(|d| d.mount_point != "/" && std::path::Path::new(path).starts_with(&d.mount_point))
+            .max_by_key(|d| d.mount_point.len())
+            .and_then(|d| d.serial.clone())
+    });
+    let first = serials.next()??;
+    serials.all(|serial| serial.as_ref() == Some(&first)).then_some(first)
+}
+
+/// SMART and read-error history for the card at `mount_point`
+#[tauri::command]
+pub fn get_card_health(mount_point: String, app: tauri::AppHandle) -> Result<CardHealth, CamPorterError> {
+    let drive = crate::removable_drives()
+        .into_iter()
+        .find(|drive| drive.mount_point == mount_point)
+        .ok_or_else(|| CamPorterError::not_found(format!("{} is no longer mounted", mount_point)))?;
+    let device = smart_device(&drive).ok_or_else(|| "The OS doesn't say which disk it's on".to_string());
+    let (smart, smart_unavailable) = match device.and_then(|device| query(&device)) {
+        Ok(report) => (Some(report), None),
+        Err(reason) => (None, Some(reason)),
+    };
+    let history = drive.serial.as_ref().map(|serial| load(&app).unwrap_or_default().remove(serial).unwrap_or_default());
+    Ok(assess(smart, smart_unavailable, history))
+}
+
+/// The device smartctl is pointed at for a drive
+fn smart_device(drive: &RemovableDrive) -> Option<String> {
+    if cfg!(target_os = "windows") {
+        // smartctl takes a drive letter such as "E:" and finds its disk itself
+        Some(drive.mount_point.trim_end_matches('\\').to_string())
+    } else {
+        drive.physical_device.clone()
+    }
+}
+
+/// Ask smartctl about `device`; bridges it doesn't recognise are asked
+/// again as plain SATA, which is what most USB disk enclosures carry
+fn query(device: &str) -> Result<SmartReport, String> {
+    let smartctl = which("smartctl").map_err(|_| "smartctl is not installed".to_string())?;
+    let first = smartctl_json(Command::new(&smartctl).args(["--json", "-a", device]))?;
+    let json = match parse(&first) {
+        Some(report) => return Ok(report),
+        None if first.pointer("/device/protocol").and_then(Value::as_str) == Some("NVMe") => first,
+        None => smartctl_json(Command::new(&smartctl).args(["--json", "-a", "-d", "sat", device]))?,
+    };
+    parse(&json).ok_or_else(|| {
+        // smartctl explains itself in its messages, such as "Permission denied"
+        json.pointer("/smartctl/messages/0/string")
+            .and_then(Value::as_str)
+            .map(|message| message.trim_start_matches(device).trim_start_matches(": ").to_string())
+            .unwrap_or_else(|| "The drive doesn't report SMART".to_string())
+    })
+}
+
+/// smartctl's JSON output; its exit status is a bit mask that's non-zero
+/// for worrying drives too, so it's not taken as failure
+fn smartctl_json(command: &mut Command) -> Result<Value, String> {
+    let output = watchdog::output(command, watchdog::PROBE_TIMEOUT)?;
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Unreadable smartctl output: {}", e))
+}
+
+/// The report in smartctl's JSON, if the drive gave one
+fn parse(json: &Value) -> Option<SmartReport> {
+    let passed = json.pointer("/smart_status/passed").and_then(Value::as_bool);
+    let nvme = json.get("nvme_smart_health_information_log");
+    let ata = json.pointer("/ata_smart_attributes/table").and_then(Value::as_array);
+    if passed.is_none() && nvme.is_none() && ata.is_none() {
+        return None;
+    }
+
+    let attribute = |id: u64| {
+        ata?.iter().find(|a| a.get("id").and_then(Value::as_u64) == Some(id))?.pointer("/raw/value")?.as_u64()
+    };
+    let nvme_field = |name: &str| nvme?.get(name)?.as_u64();
+    let block_size = json.get("logical_block_size").and_then(Value::as_u64).unwrap_or(512);
+    Some(SmartReport {
+        passed,
+        reallocated_sectors: attribute(5),
+        pending_sectors: attribute(197),
+        uncorrectable_errors: attribute(198).or_else(|| attribute(187)).or_else(|| nvme_field("media_errors")),
+        // NVMe counts writes in thousands of 512-byte units
+        bytes_written: attribute(241).map(|lbas| lbas * block_size).or_else(|| nvme_field("data_units_written").map(|units| units * 512_000)),
+        power_on_hours: json.pointer("/power_on_time/hours").and_then(Value::as_u64).or_else(|| attribute(9)),
+        percentage_used: nvme_field("percentage_used").map(|percent| percent.min(255) as u8),
+    })
+}
+
+/// Weigh what's known into a status, with the reasons behind it
+fn assess(smart: Option<SmartReport>, smart_unavailable: Option<String>, history: Option<ReadErrorHistory>) -> CardHealth {
+    let mut status = HealthStatus::Unknown;
+    let mut warnings = Vec::new();
+    let mut flag = |level: HealthStatus, warning: String| {
+        if level == HealthStatus::Failing || status != HealthStatus::Failing {
+            status = level;
+        }
+        warnings.push(warning);
+    };
+
+    if let Some(report) = &smart {
+        if report.passed == Some(false) {
+            flag(HealthStatus::Failing, "The drive reports that it is failing".to_string());
+        }
+        let bad_sectors = [report.reallocated_sectors, report.pending_sectors, report.uncorrectable_errors]
+            .into_iter()
+            .flatten()
+            .sum::<u64>();
+        if bad_sectors >= FAILING_SECTORS {
+            flag(HealthStatus::Failing, format!("{} bad or reallocated sectors", bad_sectors));
+        } else if bad_sectors > 0 {
+            flag(HealthStatus::Worn, format!("{} bad or reallocated sectors", bad_sectors));
+        }
+        if let Some(used) = report.percentage_used.filter(|&used| used >= WORN_PERCENT) {
+            flag(HealthStatus::Worn, format!("{}% of its rated endurance used", used));
+        }
+    }
+    if let Some(history) = &history {
+        // Errors on more than one import aren't a one-off bad contact
+        if history.imports_with_errors > 1 && history.last_import_had_errors {
+            flag(
+                HealthStatus::Failing,
+                format!("{} read errors in {} of {} imports", history.read_errors, history.imports_with_errors, history.imports),
+            );
+        } else if history.imports_with_errors > 0 {
+            flag(HealthStatus::Worn, format!("{} read errors in {} imports", history.read_errors, history.imports));
+        }
+    }
+    if status == HealthStatus::Unknown && (smart.is_some() || history.as_ref().is_some_and(|h| h.imports > 0)) {
+        status = HealthStatus::Healthy;
+    }
+    CardHealth { status, warnings, smart, smart_unavailable, history }
+}
@@ -0,0 +1,66 @@
+// A user-configured shell command run after each import, for kicking off
+// backups, rsync jobs or watched-folder scans. `{destination}` and `{count}`
+// in the command are replaced with the import's target and copied count.
+use log::{info, warn};
+use std::process::Command;
+use std::thread;
+
+use crate::error::CamPorterError;
+use crate::{config, watchdog};
+
+const HOOK_KEY: &str = "post_import_hook";
+
+/// Quote a value for the platform shell, so paths with spaces or quotes
+/// reach the command as one argument
+fn shell_quote(value: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+/// Start the configured hook, if any, without waiting for it
+pub fn run_post_import(app: &tauri::AppHandle, destination: &str, count: usize) {
+    let hook: Option<String> = config::get(app, HOOK_KEY).ok().flatten();
+    let Some(hook) = hook.filter(|h| !h.trim().is_empty()) else {
+        return;
+    };
+
+    let command = hook.replace("{destination}", &shell_quote(destination)).replace("{count}", &count.to_string());
+    thread::spawn(move || {
+        info!("Running post-import hook: {}", command);
+        match watchdog::output(&mut shell(&command), watchdog::HOOK_TIMEOUT) {
+            Ok(output) if output.status.success() => info!("Post-import hook finished"),
+            Ok(output) => warn!(
+                "Post-import hook failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => warn!("Post-import hook failed: {}", e),
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_post_import_hook(app: tauri::AppHandle) -> Result<Option<String>, CamPorterError> {
+    Ok(config::get(&app, HOOK_KEY)?)
+}
+
+/// Set the hook command, or clear it with None
+#[tauri::command]
+pub fn set_post_import_hook(command: Option<String>, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    Ok(config::set(&app, HOOK_KEY, &command)?)
+}
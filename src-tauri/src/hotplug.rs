@@ -0,0 +1,160 @@
+// Announcing cards as they're inserted and removed, so the UI doesn't have to
+// keep calling `list_removable_drives`. The platform's device notifications
+// (udev, DiskArbitration through `diskutil activity`, drive letters gaining
+// or losing a volume on Windows) only say that something changed; the drive
+// list is then compared with the last one to emit `drive-added` and
+// `drive-removed` with the drive.
+use log::{info, warn};
+use std::collections::HashMap;
+#[cfg(not(target_os = "windows"))]
+use std::io::{BufRead, BufReader};
+#[cfg(not(target_os = "windows"))]
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::dto::RemovableDrive;
+
+/// Gaps between rechecks after a device event, as a card's volume is mounted
+/// a moment after the device itself appears
+const SETTLE_CHECKS: [Duration; 3] = [Duration::from_millis(500), Duration::from_millis(1500), Duration::from_secs(3)];
+/// How often the list is compared when there are no device notifications
+const FALLBACK_INTERVAL: Duration = Duration::from_secs(3);
+
+struct DriveWatch {
+    app: tauri::AppHandle,
+    known: HashMap<String, RemovableDrive>,
+}
+
+impl DriveWatch {
+    fn current(app: &tauri::AppHandle) -> HashMap<String, RemovableDrive> {
+        crate::visible_drives(app).into_iter().map(|d| (d.mount_point.clone(), d)).collect()
+    }
+
+    fn refresh(&mut self) {
+        let current = Self::current(&self.app);
+        for (mount_point, drive) in &current {
+            if !self.known.contains_key(mount_point) {
+                info!("Drive added: {}", mount_point);
+                let _ = self.app.emit("drive-added", drive);
+            }
+        }
+        for (mount_point, drive) in &self.known {
+            if !current.contains_key(mount_point) {
+                info!("Drive removed: {}", mount_point);
+                crate::volume::forget(std::path::Path::new(mount_point));
+                let _ = self.app.emit("drive-removed", drive);
+            }
+        }
+        self.known = current;
+    }
+
+    fn poll(mut self) {
+        loop {
+            thread::sleep(FALLBACK_INTERVAL);
+            self.refresh();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn monitor_command() -> Option<Command> {
+    let mut command = Command::new("udevadm");
+    command.args(["monitor", "--udev", "--subsystem-match=block"]);
+    Some(command)
+}
+
+#[cfg(target_os = "macos")]
+fn monitor_command() -> Option<Command> {
+    let mut command = Command::new("diskutil");
+    command.arg("activity");
+    Some(command)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn monitor_command() -> Option<Command> {
+    None
+}
+
+/// Whether a line from the monitor reports a change, rather than being a
+/// header or a kernel-side duplicate
+#[cfg(not(target_os = "windows"))]
+fn is_event(line: &str) -> bool {
+    if cfg!(target_os = "linux") {
+        line.starts_with("UDEV")
+    } else {
+        line.starts_with("***Disk")
+    }
+}
+
+/// Start the platform's device monitor, sending on the channel for each event
+#[cfg(not(target_os = "windows"))]
+fn spawn_monitor() -> std::io::Result<mpsc::Receiver<()>> {
+    let mut command = monitor_command()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Unsupported, "no device notifications on this platform"))?;
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+    let stdout = child.stdout.take().ok_or_else(|| std::io::Error::other("monitor has no output"))?;
+
+    let (events, received) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if is_event(&line) && events.send(()).is_err() {
+                break;
+            }
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+    });
+    Ok(received)
+}
+
+/// Windows has no notifications without a window to receive them, but the
+/// drive letters with a volume on them are cheap enough to check every
+/// half second
+#[cfg(target_os = "windows")]
+fn spawn_monitor() -> std::io::Result<mpsc::Receiver<()>> {
+    let (events, received) = mpsc::channel();
+    thread::spawn(move || {
+        let mut drives = crate::volume::mounted_drives();
+        loop {
+            thread::sleep(Duration::from_millis(500));
+            let current = crate::volume::mounted_drives();
+            if current != drives {
+                drives = current;
+                if events.send(()).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    Ok(received)
+}
+
+/// Start the thread that watches for drives, falling back to comparing the
+/// list every few seconds where device notifications aren't available
+pub fn start(app: tauri::AppHandle) {
+    thread::spawn(move || {
+        let known = DriveWatch::current(&app);
+        let mut watch = DriveWatch { app, known };
+        let events = match spawn_monitor() {
+            Ok(events) => events,
+            Err(e) => {
+                warn!("No device notifications ({}), checking for drives every {} s", e, FALLBACK_INTERVAL.as_secs());
+                return watch.poll();
+            }
+        };
+
+        while events.recv().is_ok() {
+            // A card brings a burst of events (disk, then each partition)
+            for gap in SETTLE_CHECKS {
+                thread::sleep(gap);
+                while events.try_recv().is_ok() {}
+                watch.refresh();
+            }
+        }
+        warn!("Device monitor stopped, checking for drives every {} s", FALLBACK_INTERVAL.as_secs());
+        watch.poll();
+    });
+}
@@ -0,0 +1,139 @@
+// Human-readable sizes, durations and dates in the user's locale, shared by
+// progress events, reports and notifications so they all read the same
+use chrono::{Local, TimeZone};
+use std::sync::OnceLock;
+
+struct Locale {
+    decimal_comma: bool,
+    date_format: &'static str,
+    time_format: &'static str,
+}
+
+impl Locale {
+    /// Conventions for a locale tag such as "en-US" or "de_DE"
+    fn from_tag(tag: &str) -> Self {
+        let tag = tag.replace('_', "-");
+        let language = tag.split('-').next().unwrap_or_default().to_lowercase();
+        let region = tag.split('-').nth(1).unwrap_or_default().to_uppercase();
+
+        let decimal_comma = matches!(
+            language.as_str(),
+            "de" | "fr" | "es" | "it" | "nl" | "pt" | "ru" | "pl" | "sv" | "da" | "nb" | "nn" | "no" | "fi" | "cs"
+                | "sk" | "tr" | "uk" | "hu" | "ro" | "el" | "id" | "vi" | "lt" | "sl" | "hr"
+        );
+        let date_format = match language.as_str() {
+            "en" if region == "US" || region == "PH" => "%m/%d/%Y",
+            "en" | "fr" | "es" | "it" | "pt" | "el" | "vi" | "id" => "%d/%m/%Y",
+            "de" | "ru" | "pl" | "cs" | "sk" | "fi" | "nb" | "nn" | "no" | "da" | "tr" | "uk" | "ro" | "sl" | "hr" => "%d.%m.%Y",
+            "nl" => "%d-%m-%Y",
+            "ja" | "zh" => "%Y/%m/%d",
+            _ => "%Y-%m-%d",
+        };
+        let time_format = if language == "en" && region == "US" { "%-I:%M %p" } else { "%H:%M" };
+
+        Locale { decimal_comma, date_format, time_format }
+    }
+}
+
+fn locale() -> &'static Locale {
+    static LOCALE: OnceLock<Locale> = OnceLock::new();
+    LOCALE.get_or_init(|| Locale::from_tag(&sys_locale::get_locale().unwrap_or_default()))
+}
+
+fn decimal(value: f64) -> String {
+    let formatted = format!("{:.1}", value);
+    if locale().decimal_comma {
+        formatted.replace('.', ",")
+    } else {
+        formatted
+    }
+}
+
+/// Byte count in binary units, e.g. "1.5 GB" (or "1,5 GB")
+pub fn bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let exponent = ((bytes as f64).log(1024.0).floor() as usize).min(UNITS.len() - 1);
+    format!("{} {}", decimal(bytes as f64 / 1024f64.powi(exponent as i32)), UNITS[exponent])
+}
+
+pub fn speed(bytes_per_second: u64) -> String {
+    format!("{}/s", bytes(bytes_per_second))
+}
+
+/// Duration in the two largest units, e.g. "1 h 5 min" or "45 s"
+pub fn duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{} s", seconds),
+        (0, _) if seconds == 0 => format!("{} min", minutes),
+        (0, _) => format!("{} min {} s", minutes, seconds),
+        (_, 0) => format!("{} h", hours),
+        _ => format!("{} h {} min", hours, minutes),
+    }
+}
+
+/// Local date and time of a Unix timestamp
+pub fn date_time(timestamp: i64) -> String {
+    let locale = locale();
+    match Local.timestamp_opt(timestamp, 0).earliest() {
+        Some(time) => time.format(&format!("{} {}", locale.date_format, locale.time_format)).to_string(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sizes as formatted in a locale with a decimal point, whatever the
+    /// machine running the tests uses
+    fn bytes_with_point(value: u64) -> String {
+        bytes(value).replace(',', ".")
+    }
+
+    #[test]
+    fn formats_bytes_in_binary_units() {
+        assert_eq!(bytes_with_point(0), "0 B");
+        assert_eq!(bytes_with_point(1023), "1023 B");
+        assert_eq!(bytes_with_point(1024), "1.0 KB");
+        assert_eq!(bytes_with_point(1536 * 1024 * 1024), "1.5 GB");
+        assert_eq!(bytes_with_point(5 * 1024u64.pow(5)), "5120.0 TB");
+        assert_eq!(speed(512), "512 B/s");
+    }
+
+    #[test]
+    fn formats_durations_in_two_largest_units() {
+        assert_eq!(duration(0), "0 s");
+        assert_eq!(duration(45), "45 s");
+        assert_eq!(duration(120), "2 min");
+        assert_eq!(duration(125), "2 min 5 s");
+        assert_eq!(duration(3600), "1 h");
+        assert_eq!(duration(3900 + 30), "1 h 5 min");
+    }
+
+    #[test]
+    fn picks_conventions_by_locale() {
+        let us = Locale::from_tag("en-US");
+        assert!(!us.decimal_comma);
+        assert_eq!((us.date_format, us.time_format), ("%m/%d/%Y", "%-I:%M %p"));
+
+        let uk = Locale::from_tag("en_GB");
+        assert_eq!((uk.date_format, uk.time_format), ("%d/%m/%Y", "%H:%M"));
+
+        let german = Locale::from_tag("de_DE");
+        assert!(german.decimal_comma);
+        assert_eq!(german.date_format, "%d.%m.%Y");
+
+        assert_eq!(Locale::from_tag("ja-JP").date_format, "%Y/%m/%d");
+        assert_eq!(Locale::from_tag("").date_format, "%Y-%m-%d");
+    }
+
+    #[test]
+    fn leaves_out_of_range_timestamps_blank() {
+        assert_eq!(date_time(i64::MAX), "");
+        assert!(!date_time(0).is_empty());
+    }
+}
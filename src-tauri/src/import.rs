@@ -0,0 +1,2154 @@
+// The import engine: copies a selection to the destination with conflict
+// handling, renaming, sidecars and pause-on-full, reporting as it goes
+use chrono::Local;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use ts_rs::TS;
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::auditlog::{self, LogEntry, Outcome};
+use crate::benchmark::{Benchmark, BenchmarkReport, Stage};
+use crate::catalog::Catalog;
+use crate::dto::RemovableDrive;
+use crate::eject::{self, EjectStatus};
+use crate::error::CamPorterError;
+use crate::metrics::{Session, SessionMetrics};
+use crate::normalize::NameForm;
+use crate::offload::OffloadTracker;
+use crate::power::SleepInhibitor;
+use crate::resume::ImportRecord;
+use crate::routing::TypeRoutes;
+use crate::sniff::{self, Anomaly};
+use crate::trim::ClipTrim;
+use crate::undo::{UndoAction, UndoJournal};
+use crate::volume::RemovalLock;
+use crate::{alerts, audio, available_space, fastcopy, health, hooks, humanize, is_video_extension, libraries, lightroom, longpath, max_file_size, mhl, remap, rename, replaced, sidecars, timelapse, trim, volume_info};
+
+/// Snapshot of a running import, for status queries
+#[derive(Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct ImportProgress {
+    pub total_files: usize,
+    pub processed_files: usize,
+    pub total_bytes: u64,
+    pub copied_bytes: u64,
+    pub current_file: Option<String>,
+    pub bytes_per_second: u64,
+    pub eta_seconds: Option<u64>,
+    /// The above as localized text, e.g. "1.2 GB of 3.4 GB, 25.0 MB/s, 1 min left"
+    pub display: String,
+}
+
+/// Window the transfer speed is averaged over
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Rolling average of bytes actually written, so skipped files don't count
+/// as instant transfers
+#[derive(Default)]
+struct RateWindow {
+    transferred: u64,
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl RateWindow {
+    fn add(&mut self, bytes: u64) {
+        self.transferred += bytes;
+        let now = Instant::now();
+        // One sample per 100ms is plenty and keeps the window small
+        if self.samples.back().is_none_or(|(t, _)| now.duration_since(*t) >= Duration::from_millis(100)) {
+            self.samples.push_back((now, self.transferred));
+        }
+        while self.samples.len() > 2 && self.samples.front().is_some_and(|(t, _)| now.duration_since(*t) > RATE_WINDOW) {
+            self.samples.pop_front();
+        }
+    }
+
+    fn bytes_per_second(&self) -> u64 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some((start, from)), Some((end, to))) if end > start => {
+                ((to - from) as f64 / end.duration_since(*start).as_secs_f64()) as u64
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// Sends an import's progress to the UI as `import-progress` events and,
+/// for queued jobs, keeps its `ImportProgress` up to date
+pub struct ProgressReporter<'a> {
+    app: &'a tauri::AppHandle,
+    status: Option<&'a Mutex<ImportProgress>>,
+    rate: Mutex<RateWindow>,
+    session: Mutex<Option<Arc<Session>>>,
+    /// Highest of `MILESTONES` announced so far
+    milestone: AtomicU8,
+    rate_limit: Mutex<Option<RateLimit>>,
+    space_floor: Mutex<Option<SpaceFloor>>,
+    benchmark: Mutex<Option<Benchmark>>,
+    /// Queued job this import runs as, if any
+    job_id: Option<u64>,
+    coalesced: Mutex<Coalesced>,
+    /// Reads from the source that failed, retried or not, for `health`
+    read_errors: AtomicU64,
+}
+
+/// Longest an `import-progress` update is held back; with tens of thousands
+/// of small files, an event per step floods the webview
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress waiting for the next send, latest of each kind
+#[derive(Default)]
+struct Coalesced {
+    last_sent: Option<Instant>,
+    text: Option<String>,
+    bytes: Option<String>,
+    /// Per-file `import-milestone`; percentage milestones aren't held back
+    file: Option<Milestone>,
+}
+
+/// Caps the copy loop's throughput by sleeping once it gets ahead of the
+/// allowed rate, so a background offload doesn't starve the destination disk
+struct RateLimit {
+    bytes_per_second: u64,
+    started: Instant,
+    transferred: u64,
+}
+
+/// Free space to keep on the destination while a file is written; another
+/// app filling the disk pauses the copy instead of failing it partway
+struct SpaceFloor {
+    dir: PathBuf,
+    threshold: u64,
+    /// Bytes of the current file still to be written and not yet reserved
+    remaining: u64,
+    last_checked: Instant,
+    /// Free space was found under the threshold; the copy stops at its next
+    /// write and waits in `wait_for_space`, where it can be cancelled or
+    /// redirected
+    tripped: bool,
+}
+
+impl SpaceFloor {
+    /// Free space once the current file is written, if it's under the threshold
+    fn shortfall(&self) -> Option<u64> {
+        let available = available_space(&self.dir)?.saturating_sub(self.remaining);
+        (available < self.threshold).then_some(available)
+    }
+}
+
+/// Free space kept on the destination unless the import sets its own
+const DEFAULT_LOW_SPACE_THRESHOLD: u64 = 512 * 1024 * 1024;
+/// How often free space is checked while a file is being written
+const SPACE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sent as `import-low-space` when a copy pauses for destination space
+#[derive(Serialize, Clone)]
+struct LowSpace {
+    destination: String,
+    available: u64,
+    threshold: u64,
+}
+
+impl RateLimit {
+    fn add(&mut self, bytes: u64) {
+        self.transferred += bytes;
+        let due = self.started + Duration::from_secs_f64(self.transferred as f64 / self.bytes_per_second as f64);
+        let now = Instant::now();
+        if due > now {
+            std::thread::sleep(due - now);
+        } else if now - due > Duration::from_secs(1) {
+            // Fell behind (e.g. while paused); don't let the backlog turn into a burst
+            self.started = now;
+            self.transferred = 0;
+        }
+    }
+}
+
+/// Overall percentages announced as `import-milestone` events
+const MILESTONES: [u8; 4] = [25, 50, 75, 100];
+
+/// Coarse progress with a readable summary, for screen-reader oriented UIs
+/// that can't announce every byte update
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct Milestone {
+    /// Overall percentage reached, for percentage milestones
+    pub percent: Option<u8>,
+    /// File just copied, for per-file milestones
+    pub file: Option<String>,
+    pub message: String,
+}
+
+impl<'a> ProgressReporter<'a> {
+    pub fn new(app: &'a tauri::AppHandle, status: Option<&'a Mutex<ImportProgress>>) -> Self {
+        ProgressReporter {
+            app,
+            status,
+            rate: Mutex::new(RateWindow::default()),
+            session: Mutex::new(None),
+            milestone: AtomicU8::new(0),
+            rate_limit: Mutex::new(None),
+            space_floor: Mutex::new(None),
+            benchmark: Mutex::new(None),
+            job_id: None,
+            coalesced: Mutex::new(Coalesced::default()),
+            read_errors: AtomicU64::new(0),
+        }
+    }
+
+    /// Send `import-*` events on the job's own channels, e.g.
+    /// `import-progress:3`, so jobs running side by side don't interleave
+    pub fn for_job(self, job_id: u64) -> Self {
+        ProgressReporter { job_id: Some(job_id), ..self }
+    }
+
+    fn send<S: Serialize + Clone>(&self, event: &str, payload: S) -> Result<(), String> {
+        match self.job_id.filter(|_| event.starts_with("import-")) {
+            Some(id) => self.app.emit(&format!("{}:{}", event, id), payload),
+            None => self.app.emit(event, payload),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    /// Send a state change right away, after any progress held back before it
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) -> Result<(), String> {
+        self.flush()?;
+        self.send(event, payload)
+    }
+
+    /// Hold back a progress update, sending everything held at most once per
+    /// `PROGRESS_INTERVAL`; in between, newer updates replace older ones
+    fn coalesce(&self, update: impl FnOnce(&mut Coalesced)) -> Result<(), String> {
+        let due = match self.coalesced.lock() {
+            Ok(mut coalesced) => {
+                update(&mut coalesced);
+                coalesced.last_sent.is_none_or(|t| t.elapsed() >= PROGRESS_INTERVAL)
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        if due {
+            self.flush()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn message(&self, message: &str) -> Result<(), String> {
+        let message = message.to_string();
+        self.coalesce(|coalesced| {
+            if message.starts_with("PROGRESS_BYTES:") {
+                coalesced.bytes = Some(message);
+            } else {
+                coalesced.text = Some(message);
+            }
+        })
+    }
+
+    /// Send held-back progress now
+    fn flush(&self) -> Result<(), String> {
+        let (text, bytes, file) = match self.coalesced.lock() {
+            Ok(mut coalesced) => {
+                coalesced.last_sent = Some(Instant::now());
+                (coalesced.text.take(), coalesced.bytes.take(), coalesced.file.take())
+            }
+            Err(_) => return Ok(()),
+        };
+        for message in [text, bytes].into_iter().flatten() {
+            self.send("import-progress", message)?;
+        }
+        if let Some(file) = file {
+            self.send("import-milestone", file)?;
+        }
+        Ok(())
+    }
+
+    /// Count bytes written by the copy loop towards the transfer speed
+    fn transferred(&self, bytes: u64) {
+        if let Ok(mut rate) = self.rate.lock() {
+            rate.add(bytes);
+        }
+        if let Ok(Some(session)) = self.session.lock().as_deref() {
+            session.add(bytes);
+        }
+        if let Ok(Some(limit)) = self.rate_limit.lock().as_deref_mut() {
+            limit.add(bytes);
+        }
+        if let Ok(Some(floor)) = self.space_floor.lock().as_deref_mut() {
+            floor.remaining = floor.remaining.saturating_sub(bytes);
+            if !floor.tripped && floor.last_checked.elapsed() >= SPACE_CHECK_INTERVAL {
+                floor.last_checked = Instant::now();
+                floor.tripped = floor.shortfall().is_some();
+            }
+        }
+    }
+
+    /// Whether free space fell under the floor while the current file was written
+    fn below_space_floor(&self) -> bool {
+        self.space_floor.lock().is_ok_and(|floor| floor.as_ref().is_some_and(|floor| floor.tripped))
+    }
+
+    /// Watch free space in `dir` while the next file is written; `unreserved`
+    /// is how much of it preallocation hasn't already claimed
+    fn watch_space(&self, dir: &Path, threshold: u64, unreserved: u64) {
+        if let Ok(mut floor) = self.space_floor.lock() {
+            *floor = (threshold > 0).then(|| SpaceFloor {
+                dir: dir.to_path_buf(),
+                threshold,
+                remaining: unreserved,
+                last_checked: Instant::now(),
+                tripped: false,
+            });
+        }
+    }
+
+    fn limit_rate(&self, bytes_per_second: Option<u64>) {
+        if let Ok(mut limit) = self.rate_limit.lock() {
+            *limit = bytes_per_second.filter(|&b| b > 0).map(|bytes_per_second| RateLimit {
+                bytes_per_second,
+                started: Instant::now(),
+                transferred: 0,
+            });
+        }
+    }
+
+    /// Run one stage of a copy, timing it if the import is being benchmarked
+    fn time<T>(&self, stage: Stage, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        if let Ok(Some(benchmark)) = self.benchmark.lock().as_deref_mut() {
+            benchmark.add(stage, started.elapsed());
+        }
+        result
+    }
+
+    /// Report overall progress as `PROGRESS_BYTES:copied:total:speed:eta:text`,
+    /// with speed in bytes per second, the ETA in seconds (empty if unknown)
+    /// and the same as localized text
+    fn bytes(&self, copied: u64, total: u64) -> Result<(), String> {
+        let speed = self.rate.lock().map(|r| r.bytes_per_second()).unwrap_or(0);
+        let eta = (speed > 0).then(|| total.saturating_sub(copied) / speed);
+        let mut display = format!("{} of {}", humanize::bytes(copied), humanize::bytes(total));
+        if speed > 0 {
+            display += &format!(", {}", humanize::speed(speed));
+        }
+        if let Some(eta) = eta.filter(|&e| e > 0) {
+            display += &format!(", {} left", humanize::duration(eta));
+        }
+        self.update(|p| {
+            p.copied_bytes = copied;
+            p.bytes_per_second = speed;
+            p.eta_seconds = eta;
+            p.display = display.clone();
+        });
+        
+        let percent = (copied.saturating_mul(100) / total.max(1)) as u8;
+        let reached = MILESTONES.iter().copied().filter(|&m| m <= percent && total > 0).max();
+        if let Some(reached) = reached.filter(|&m| m > self.milestone.load(Ordering::Relaxed)) {
+            self.milestone.store(reached, Ordering::Relaxed);
+            self.emit(
+                "import-milestone",
+                Milestone { percent: Some(reached), file: None, message: format!("{}% complete, {}", reached, display) },
+            )?;
+        }
+        
+        let eta = eta.map(|e| e.to_string()).unwrap_or_default();
+        self.message(&format!("PROGRESS_BYTES:{}:{}:{}:{}:{}", copied, total, speed, eta, display))
+    }
+
+    /// Announce a finished file as the `done`th of `total`, held back like
+    /// other progress
+    fn file_copied(&self, file_name: &str, size: u64, done: usize, total: usize) -> Result<(), String> {
+        let milestone = Milestone {
+            percent: None,
+            file: Some(file_name.to_string()),
+            message: format!("Copied {} ({}), file {} of {}", file_name, humanize::bytes(size), done, total),
+        };
+        self.coalesce(|coalesced| coalesced.file = Some(milestone))
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ImportProgress)) {
+        if let Some(Ok(mut status)) = self.status.map(|s| s.lock()) {
+            f(&mut status);
+        }
+    }
+}
+
+/// What to do when a file with the same name already exists in the destination
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Skip on any name clash, even when the contents differ
+    Skip,
+    Overwrite,
+    Rename,
+    /// Skip only true duplicates, judged by size and hash, and import a
+    /// different file that happens to share the name (DSC_0001.JPG from
+    /// another card) under a new name
+    #[default]
+    SkipIfIdentical,
+}
+
+/// Optional behaviour for `import_selected_files`; every field has a default
+#[derive(Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct ImportOptions {
+    conflict_strategy: ConflictStrategy,
+    /// Template such as "{date}_{camera}_{seq}" for renaming files on import
+    rename_template: Option<String>,
+    /// Delete each source file after its copy has been verified
+    #[serde(rename = "move")]
+    move_files: bool,
+    /// Delete the sources only once everything is copied and a final pass
+    /// re-verifies every copy, keeping them all if anything failed; replaces
+    /// `move` when both are set
+    verify_then_delete: bool,
+    /// Where to send files too large for the destination file system
+    oversize_target_path: Option<String>,
+    preallocation: Preallocation,
+    /// Copy XMP/THM/LRV/AAE/SRT files and Live Photo clips alongside their
+    /// media
+    pub(crate) include_sidecars: bool,
+    /// Skip files the catalog shows were already imported to any destination
+    skip_cataloged: bool,
+    /// Second destination that every file is also copied to
+    mirror_target_path: Option<String>,
+    /// Skip files already offloaded from the same card in an earlier import
+    new_only: bool,
+    retry: RetryPolicy,
+    /// Cap on copy throughput, for offloading in the background
+    max_bytes_per_second: Option<u64>,
+    /// Import files whose contents disagree with their extension instead of
+    /// setting them aside in the report
+    import_anomalies: bool,
+    /// Unmount the source card once everything copied without failures
+    eject_after_import: bool,
+    /// Write into the configured Lightroom auto-import folder instead of the
+    /// destination
+    lightroom_drop: bool,
+    /// Time each stage of every copy and add a bottleneck report
+    benchmark: bool,
+    /// Unicode form for destination file names
+    name_form: NameForm,
+    /// Free space to keep on the destination, pausing when other writes eat
+    /// into it; None for `DEFAULT_LOW_SPACE_THRESHOLD`, 0 to turn it off
+    low_space_threshold: Option<u64>,
+    /// Assemble an MP4 preview of each time-lapse sequence once imported
+    timelapse_previews: bool,
+    /// In and out points of clips to import only part of, by source path
+    trims: HashMap<String, ClipTrim>,
+    /// Recreate the card's folders (`DCIM/100CANON/...`) under the
+    /// destination instead of copying everything into one folder
+    preserve_structure: bool,
+    /// Add an ASC MHL v2 generation listing the copies to the destination's
+    /// `ascmhl` folder, for checking the chain of custody downstream
+    mhl: bool,
+    /// Put MD5 hashes in the MHL alongside xxh3, reading each copy once more
+    mhl_md5: bool,
+    /// Order to copy the files in
+    ordering: FileOrder,
+    /// Leave the catalog and offload history alone, for `run_untracked_import`
+    #[serde(skip)]
+    untracked: bool,
+}
+
+impl ImportOptions {
+    /// Folders the import writes to besides its target
+    pub(crate) fn other_targets(&self) -> impl Iterator<Item = &String> {
+        self.mirror_target_path.iter().chain(&self.oversize_target_path)
+    }
+
+    /// The same options for files staged by the app itself, such as FTP
+    /// uploads: each is removed once its copy is verified (or at the end
+    /// with `verify_then_delete`), and there's no card to eject
+    pub(crate) fn for_staged_files(self) -> Self {
+        ImportOptions { move_files: !self.verify_then_delete, eject_after_import: false, ..self }
+    }
+}
+
+/// How often a copy is retried after a transient I/O error, such as a card
+/// reader dropping out mid-read; the delay doubles after each attempt
+#[derive(Serialize, Deserialize, Clone, Copy, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Attempts per file, including the first
+    pub attempts: u32,
+    pub initial_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy { attempts: 3, initial_delay_ms: 500 }
+    }
+}
+
+/// Whether to reserve destination space before copying each file
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Preallocation {
+    /// Only on spinning disks, where fragmentation hurts
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Order an import works through its files in. It decides which files are
+/// safe first if the import is cut short, and the `{seq}` numbers they get.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FileOrder {
+    /// As selected
+    #[default]
+    Selection,
+    /// Photos and everything else before videos, which take longest
+    VideosLast,
+    /// Smallest files first, so progress shows early
+    SmallestFirst,
+}
+
+impl FileOrder {
+    fn apply(self, file_paths: &[String]) -> Vec<String> {
+        let mut ordered = file_paths.to_vec();
+        match self {
+            FileOrder::Selection => {}
+            FileOrder::VideosLast => ordered.sort_by_key(|p| {
+                Path::new(p).extension().is_some_and(|ext| is_video_extension(&ext.to_string_lossy().to_lowercase()))
+            }),
+            FileOrder::SmallestFirst => ordered.sort_by_cached_key(|p| fs::metadata(p).map(|m| m.len()).unwrap_or(0)),
+        }
+        ordered
+    }
+}
+
+/// A file that could not be imported
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct FileError {
+    pub path: String,
+    pub error: String,
+    /// The copy broke off partway and its partial output was removed
+    pub partial: bool,
+}
+
+impl FileError {
+    pub fn new(path: &Path, error: impl std::fmt::Display) -> Self {
+        FileError {
+            path: path.to_string_lossy().to_string(),
+            error: error.to_string(),
+            partial: false,
+        }
+    }
+}
+
+/// Outcome at the mirror destination, which is verified on its own
+#[derive(Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct MirrorReport {
+    pub target_path: String,
+    pub copied: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub errors: Vec<FileError>,
+}
+
+impl MirrorReport {
+    fn fail(&mut self, path: &Path, error: impl std::fmt::Display) {
+        self.failed += 1;
+        self.errors.push(FileError::new(path, error));
+    }
+}
+
+/// Summary of a finished import
+#[derive(Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct ImportReport {
+    pub copied: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub errors: Vec<FileError>,
+    pub bytes: u64,
+    pub duration: u64, // milliseconds
+    pub mirror: Option<MirrorReport>,
+    /// For `get_session_metrics`
+    pub session_id: u64,
+    /// Localized one-line summary, e.g. "12 copied, 1 skipped (3.4 GB in 1 min 5 s)"
+    pub summary: String,
+    /// Files left out because their contents don't match their extension;
+    /// also counted as skipped
+    pub anomalies: Vec<Anomaly>,
+    /// Mount point of the card unmounted by `eject_after_import`
+    pub ejected: Option<String>,
+    /// Whether that card was also powered down and can be pulled
+    pub eject_status: Option<EjectStatus>,
+    /// Per-stage timings, when `benchmark` was set
+    pub benchmark: Option<BenchmarkReport>,
+    /// Stopped before every file was processed, as when the app was quit
+    /// mid-import; the rest can be picked up by resuming
+    pub cancelled: bool,
+    /// The final pass and what it deleted, when `verify_then_delete` was set
+    pub deletion: Option<DeletionReport>,
+    /// Every file's outcome, for the log written to the destination
+    #[serde(skip)]
+    pub log: Vec<LogEntry>,
+}
+
+/// A source left on the card by `verify_then_delete`, and why
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct RetainedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+/// How the final pass of `verify_then_delete` went
+#[derive(Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct DeletionReport {
+    /// Copies that still matched their source once everything was copied
+    pub verified: usize,
+    /// Sources removed from the card
+    pub deleted: usize,
+    /// Every source still on the card, with why
+    pub retained: Vec<RetainedFile>,
+}
+
+/// A copied source waiting for the final pass of `verify_then_delete`
+struct StagedSource {
+    source: PathBuf,
+    target: PathBuf,
+    mirror: Option<PathBuf>,
+    /// Hash of the source taken while copying
+    hash: u64,
+    /// Why it stays on the card whatever the pass finds
+    kept: Option<String>,
+}
+
+impl ImportReport {
+    fn skip(&mut self, path: &Path, size: u64, reason: &str) {
+        self.skipped += 1;
+        self.log.push(LogEntry::skipped(path, size, reason));
+    }
+    
+    fn fail(&mut self, path: &Path, error: impl std::fmt::Display) {
+        self.failed += 1;
+        self.errors.push(FileError::new(path, error));
+        self.log.push(LogEntry::failed(path, &self.errors[self.errors.len() - 1].error));
+    }
+    
+    fn fail_copy(&mut self, path: &Path, error: CopyError) {
+        self.failed += 1;
+        self.errors.push(FileError { partial: error.partial, ..FileError::new(path, error) });
+        self.log.push(LogEntry::failed(path, &self.errors[self.errors.len() - 1].error));
+    }
+}
+
+fn copy_failed_message(file_name: &str, error: &CopyError) -> String {
+    if error.partial {
+        format!("Copy of {} broke off partway, partial file removed: {}", file_name, error)
+    } else {
+        format!("Failed to copy {}: {}", file_name, error)
+    }
+}
+
+/// Copy the selected files into `target_path`, reporting progress through
+/// `progress`
+pub fn run_import(
+    file_paths: &[String],
+    target_path: &str,
+    options: ImportOptions,
+    progress: &ProgressReporter,
+    control: &ImportControl,
+    journal: &UndoJournal,
+) -> Result<ImportReport, CamPorterError> {
+    // Released when this returns, whether the import finished, failed or was cancelled
+    let _awake = SleepInhibitor::acquire(&format!("Importing {} files", file_paths.len()));
+    let started = Local::now();
+    // Ordered before the resume record is written, so its checkpoints match.
+    // Queued and resumed imports may name a card by where it used to be.
+    let file_paths = &remap::translate_all(&options.ordering.apply(file_paths));
+    let options = ImportOptions {
+        trims: options.trims.into_iter().map(|(path, trim)| (remap::translate(&path), trim)).collect(),
+        ..options
+    };
+    // Explorer's Eject is refused until the copy is done
+    let removal_lock = RemovalLock::acquire(&source_volumes(file_paths));
+    
+    // Keep a record on disk while the import runs, so it can be resumed if
+    // the app is closed part way through
+    let record = ImportRecord::create(progress.app, file_paths, target_path, &options);
+    if let Err(e) = &record {
+        warn!("Failed to record import for resuming: {}", e);
+    }
+    
+    // Sample throughput for the session graph; the id is announced up front
+    // so the UI can poll while the import runs
+    let session = progress.app.state::<SessionMetrics>().start();
+    if let Ok(mut current) = progress.session.lock() {
+        *current = Some(Arc::clone(&session.session));
+    }
+    let _ = progress.emit("import-session-started", session.id);
+    
+    // Whatever was done before a failure still goes into the undo journal
+    let eject_after_import = options.eject_after_import;
+    // Lightroom moves dropped files on, so there's nothing to list
+    let mhl_md5 = (options.mhl && !options.lightroom_drop).then_some(options.mhl_md5);
+    let mut actions = Vec::new();
+    let mut result = import_files(file_paths, target_path, options, progress, control, record.as_ref().ok(), &mut actions)
+        .map(|report| ImportReport { session_id: session.id, ..report });
+    remember_replaced(progress.app, &actions);
+    journal.record(format!("Import of {} files to {}", file_paths.len(), target_path), actions);
+    
+    // A cancelled import keeps its record, so it can be resumed
+    if let Ok(record) = record {
+        if !result.as_ref().is_ok_and(|r| r.cancelled) {
+            record.remove();
+        }
+    }
+    
+    // Released before the card is ejected below
+    removal_lock.release();
+    
+    if let Ok(report) = &mut result {
+        match auditlog::write(target_path, started, report) {
+            Ok(log_path) => info!("Wrote import log {}", log_path.display()),
+            Err(e) => warn!("Failed to write import log: {}", e),
+        }
+        if let Some(md5) = mhl_md5 {
+            match mhl::write(target_path, report, md5) {
+                Ok(path) => info!("Wrote MHL generation {}", path.display()),
+                Err(e) => warn!("Failed to write MHL: {}", e),
+            }
+        }
+        if eject_after_import {
+            if let Some((mount_point, status)) = eject_source(file_paths, report, progress) {
+                report.ejected = Some(mount_point);
+                report.eject_status = Some(status);
+            }
+        }
+        if !report.cancelled {
+            health::record_import(progress.app, file_paths, progress.read_errors.load(Ordering::Relaxed));
+        }
+        hooks::run_post_import(progress.app, target_path, report.copied);
+        libraries::notify_import(progress.app, target_path, report.copied);
+    }
+    alerts::import_finished(progress.app, target_path, &result);
+    let _ = progress.flush();
+    result
+}
+
+/// The removable drive a file is on, if any
+fn drive_holding<'a>(drives: &'a [RemovableDrive], path: &str) -> Option<&'a RemovableDrive> {
+    drives
+        .iter()
+        .filter(|d| d.mount_point != "/" && Path::new(path).starts_with(&d.mount_point))
+        .max_by_key(|d| d.mount_point.len())
+}
+
+/// The removable drive holding each file, or None if any isn't on one
+fn drives_holding<'a>(drives: &'a [RemovableDrive], file_paths: &[String]) -> Option<Vec<&'a RemovableDrive>> {
+    file_paths.iter().map(|path| drive_holding(drives, path)).collect()
+}
+
+/// Mount points of every removable drive any of the files are on
+fn source_volumes(file_paths: &[String]) -> Vec<String> {
+    let drives = crate::removable_drives();
+    let mut volumes: Vec<String> = Vec::new();
+    for drive in file_paths.iter().filter_map(|path| drive_holding(&drives, path)) {
+        if !volumes.contains(&drive.mount_point) {
+            volumes.push(drive.mount_point.clone());
+        }
+    }
+    volumes
+}
+
+/// Mount point of the removable drive holding every file, if they share one
+pub fn source_drive(file_paths: &[String]) -> Option<String> {
+    let drives = crate::removable_drives();
+    let holding = drives_holding(&drives, file_paths)?;
+    let first = holding.first()?;
+    holding.iter().all(|d| d.mount_point == first.mount_point).then(|| first.mount_point.clone())
+}
+
+/// The card holding every file, when they're all on one: its physical
+/// device, or its mount point where that isn't known, and the mount points
+/// of the partitions the files come from
+pub fn source_card(file_paths: &[String]) -> Option<(String, Vec<String>)> {
+    let drives = crate::removable_drives();
+    let holding = drives_holding(&drives, file_paths)?;
+    let first = *holding.first()?;
+    let same_card = |d: &&RemovableDrive| match &first.physical_device {
+        Some(device) => d.physical_device.as_ref() == Some(device),
+        None => d.mount_point == first.mount_point,
+    };
+    if !holding.iter().all(same_card) {
+        return None;
+    }
+    let mut partitions: Vec<String> = Vec::new();
+    for drive in holding {
+        if !partitions.contains(&drive.mount_point) {
+            partitions.push(drive.mount_point.clone());
+        }
+    }
+    Some((first.physical_device.clone().unwrap_or_else(|| first.mount_point.clone()), partitions))
+}
+
+/// Eject the source card after a clean import and announce it with a
+/// `safe-to-remove` or `unmounted-still-powered` event. Any failure, on the
+/// mirror too, keeps it mounted.
+fn eject_source(file_paths: &[String], report: &ImportReport, progress: &ProgressReporter) -> Option<(String, EjectStatus)> {
+    if report.failed > 0 || report.mirror.as_ref().is_some_and(|m| m.failed > 0) {
+        let _ = progress.message("Card left mounted because some files failed");
+        return None;
+    }
+    let Some((_, partitions)) = source_card(file_paths) else {
+        let _ = progress.message("Card not ejected: files didn't all come from one removable drive");
+        return None;
+    };
+    
+    // Each partition in turn; the last one lets the card power off
+    let mut ejected = None;
+    for mount_point in partitions {
+        // Ejecting one partition can take the card's others with it
+        if !eject::is_mounted(&mount_point) {
+            continue;
+        }
+        match eject::eject(&mount_point, false) {
+            Ok(status) => {
+                info!("Ejected {} after import", mount_point);
+                let event = match status {
+                    EjectStatus::SafeToRemove => "safe-to-remove",
+                    EjectStatus::UnmountedStillPowered => "unmounted-still-powered",
+                };
+                let _ = progress.emit(event, &mount_point);
+                ejected = Some((mount_point, status));
+            }
+            Err(e) => {
+                warn!("Failed to eject {}: {}", mount_point, e);
+                let _ = progress.message(&format!("Failed to eject card: {}", e));
+                return None;
+            }
+        }
+    }
+    ejected
+}
+
+/// Refuse up front to delete sources from a locked or read-only card, which
+/// would otherwise fail for every file after it was copied
+fn refuse_read_only_source(file_paths: &[String]) -> Result<(), CamPorterError> {
+    let drives = crate::removable_drives();
+    let Some(drive) = drives
+        .iter()
+        .filter(|d| d.is_read_only && d.mount_point != "/")
+        .find(|d| file_paths.iter().any(|p| Path::new(p).starts_with(&d.mount_point)))
+    else {
+        return Ok(());
+    };
+    let reason = if drive.is_write_protected { "its lock switch is set" } else { "it is mounted read-only" };
+    Err(CamPorterError::invalid_input(format!(
+        "Files can't be deleted from {} because {}; import without moving, or unlock the card",
+        drive.mount_point, reason
+    )))
+}
+
+/// Copy files through the same pipeline as `run_import`, without recording
+/// them in the catalog, offload history, undo journal or resume records
+pub fn run_untracked_import(
+    file_paths: &[String],
+    target_path: &str,
+    options: ImportOptions,
+    progress: &ProgressReporter,
+    control: &ImportControl,
+) -> Result<ImportReport, CamPorterError> {
+    let file_paths = &options.ordering.apply(file_paths);
+    let mut actions = Vec::new();
+    let result = import_files(file_paths, target_path, ImportOptions { untracked: true, ..options }, progress, control, None, &mut actions);
+    remember_replaced(progress.app, &actions);
+    let _ = progress.flush();
+    result
+}
+
+/// Remember where overwritten files were set aside, so they get purged
+fn remember_replaced(app: &tauri::AppHandle, actions: &[UndoAction]) {
+    let retained: Vec<&Path> = actions
+        .iter()
+        .filter_map(|action| match action {
+            UndoAction::Overwritten { retained, .. } => Some(retained.as_path()),
+            _ => None,
+        })
+        .collect();
+    replaced::remember(app, &retained);
+}
+
+fn import_files(
+    file_paths: &[String],
+    target_path: &str,
+    options: ImportOptions,
+    progress: &ProgressReporter,
+    control: &ImportControl,
+    record: Option<&ImportRecord>,
+    actions: &mut Vec<UndoAction>,
+) -> Result<ImportReport, CamPorterError> {
+    let started = Instant::now();
+    let mut report = ImportReport::default();
+    // In Lightroom drop mode everything goes flat into the auto-import watched
+    // folder, with sidecars, and the catalog records where Lightroom moves it
+    let lightroom_drop = if options.lightroom_drop {
+        Some(lightroom::load(progress.app).ok_or("No Lightroom auto-import folder is configured".to_string())?)
+    } else {
+        None
+    };
+    let dest = match &lightroom_drop {
+        Some(drop) => longpath::extend(Path::new(&drop.watched_folder)),
+        None => longpath::extend(Path::new(target_path)),
+    };
+    let conflict_strategy = options.conflict_strategy;
+    let verify_then_delete = options.verify_then_delete;
+    let move_files = options.move_files && !verify_then_delete;
+    if move_files || verify_then_delete {
+        refuse_read_only_source(file_paths)?;
+    }
+    let mut staged = Vec::new();
+    let template = options
+        .rename_template
+        .as_deref()
+        .filter(|t| !t.is_empty())
+        .map(rename::RenameTemplate::parse)
+        .transpose()?;
+    
+    // Create target directory if it doesn't exist
+    create_destination(&dest)?;
+    replaced::purge_expired(progress.app, &dest);
+    
+    let oversize_dest = options.oversize_target_path.filter(|p| !p.is_empty()).map(|p| longpath::extend(Path::new(&p)));
+    if let Some(oversize_dest) = &oversize_dest {
+        create_destination(oversize_dest)?;
+    }
+    let mirror_dest = options.mirror_target_path.filter(|p| !p.is_empty()).map(|p| longpath::extend(Path::new(&p)));
+    if let Some(mirror_dest) = &mirror_dest {
+        create_destination(mirror_dest)?;
+        report.mirror = Some(MirrorReport {
+            target_path: longpath::display(mirror_dest),
+            ..Default::default()
+        });
+    }
+    let dest_volume = volume_info(&dest);
+    let retry = options.retry;
+    let untracked = options.untracked;
+    let low_space_threshold = options.low_space_threshold.unwrap_or(DEFAULT_LOW_SPACE_THRESHOLD);
+    let name_form = options.name_form;
+    // Lightroom watches a single folder, so drops stay flat
+    let structure_root = if options.preserve_structure && lightroom_drop.is_none() { structure_root(file_paths) } else { None };
+    
+    // Photos and videos can have roots of their own in settings; None keeps a
+    // file in the import's destination
+    let routes = if untracked || lightroom_drop.is_some() { TypeRoutes::default() } else { TypeRoutes::load(progress.app) };
+    let file_roots: Vec<Option<PathBuf>> =
+        file_paths.iter().map(|p| routes.route(Path::new(p)).map(|root| longpath::extend(&root))).collect();
+    let mut root_volumes = HashMap::new();
+    for root in file_roots.iter().flatten() {
+        if !root_volumes.contains_key(root) {
+            create_destination(root)?;
+            root_volumes.insert(root.clone(), volume_info(root));
+        }
+    }
+    let volume_of = |root: Option<&PathBuf>| match root {
+        Some(root) => root_volumes.get(root).and_then(|v| v.as_ref()),
+        None => dest_volume.as_ref(),
+    };
+    let file_system_of = |root: Option<&PathBuf>| volume_of(root).map(|v| v.file_system.clone()).unwrap_or_default();
+    progress.limit_rate(options.max_bytes_per_second);
+    if let Ok(mut benchmark) = progress.benchmark.lock() {
+        *benchmark = options.benchmark.then(Benchmark::default);
+    }
+    let preallocate = match options.preallocation {
+        Preallocation::Always => true,
+        Preallocation::Never => false,
+        Preallocation::Auto => dest_volume.as_ref().is_some_and(|v| v.is_hdd),
+    };
+    
+    // Calculate total size of all files
+    let mut total_size: u64 = 0;
+    let mut file_sizes: Vec<u64> = Vec::new();
+    // Each sidecar as it's reported, and in extended form for reading
+    let mut file_sidecars: Vec<Vec<(PathBuf, PathBuf, u64)>> = Vec::new();
+    let mut claimed_sidecars = std::collections::HashSet::new();
+    let selected: HashSet<String> = file_paths.iter().map(|p| p.to_lowercase()).collect();
+    // Sources are read in extended form, so card folders past MAX_PATH can be
+    // copied from too; the paths as given are kept for reports, the catalog
+    // and the offload history
+    let sources: Vec<PathBuf> = file_paths.iter().map(|p| longpath::extend(Path::new(p))).collect();
+    
+    for source in &sources {
+        if source.exists() {
+            if let Ok(metadata) = source.metadata() {
+                let size = metadata.len();
+                total_size += size;
+                file_sizes.push(size);
+            } else {
+                file_sizes.push(0);
+            }
+        } else {
+            file_sizes.push(0);
+        }
+        
+        // A sidecar shared by e.g. a JPG+RAW pair is only copied once, and a
+        // Live Photo clip selected with its photo goes along as its sidecar
+        let mut sidecars = Vec::new();
+        let with_photo = sidecars::live_photo_of(source).is_some_and(|photo| selected.contains(&longpath::display(&photo).to_lowercase()));
+        if (options.include_sidecars || lightroom_drop.is_some()) && !with_photo {
+            for extended in sidecars::find_sidecars(source) {
+                let sidecar = longpath::strip(&extended);
+                if claimed_sidecars.insert(sidecar.clone()) {
+                    let size = extended.metadata().map(|m| m.len()).unwrap_or(0);
+                    total_size += size;
+                    sidecars.push((sidecar, extended, size));
+                }
+            }
+        }
+        file_sidecars.push(sidecars);
+    }
+    
+    // Refuse to start if any destination can't hold its share of the
+    // selection; files over the file system limit are skipped or rerouted,
+    // so they don't count
+    let mut required_by_root: HashMap<Option<&PathBuf>, u64> = HashMap::new();
+    for (i, &size) in file_sizes.iter().enumerate() {
+        let root = file_roots[i].as_ref();
+        let counted = if max_file_size(&file_system_of(root)).is_none_or(|max| size <= max) { size } else { 0 };
+        let sidecar_size: u64 = file_sidecars[i].iter().map(|(_, _, size)| size).sum();
+        *required_by_root.entry(root).or_default() += counted + sidecar_size;
+    }
+    for (root, required) in required_by_root {
+        let Some(available) = volume_of(root).map(|v| v.available_space) else {
+            continue;
+        };
+        if required > available {
+            let path = root.map(|r| r.display().to_string()).unwrap_or_else(|| target_path.to_string());
+            warn!("Insufficient space in {}: {} bytes required, {} available", path, required, available);
+            return Err(CamPorterError::InsufficientSpace {
+                required,
+                available,
+                missing: required - available,
+            });
+        }
+    }
+    
+    let mut copied_size: u64 = 0;
+    let mut seq: u32 = 1;
+    let catalog = progress.app.state::<Catalog>();
+    // Copies to look for time-lapse sequences and recordings among afterwards
+    let mut imported = Vec::new();
+    let mut offloaded = OffloadTracker::new(progress.app);
+    // Destinations the user redirected while paused, by where files were routed
+    let mut redirected: HashMap<PathBuf, PathBuf> = HashMap::new();
+    
+    progress.update(|p| {
+        p.total_files = file_paths.len();
+        p.total_bytes = total_size;
+    });
+    
+    for (i, file_path) in file_paths.iter().enumerate() {
+        if control.is_cancelled() {
+            info!("Import cancelled with {} of {} files processed", i, file_paths.len());
+            progress.message("Cancelled")?;
+            report.cancelled = true;
+            break;
+        }
+        progress.update(|p| {
+            p.processed_files = i;
+            p.current_file = Some(file_path.clone());
+        });
+        let src_file = Path::new(file_path);
+        let source = &sources[i];
+        let file_size = file_sizes[i];
+        let sidecars = &file_sidecars[i];
+        let sidecar_size: u64 = sidecars.iter().map(|(_, _, size)| size).sum();
+        
+        if !source.exists() {
+            progress.message(&format!("Skipped: {} (file not found)", file_path))?;
+            report.skip(src_file, file_size, "file not found");
+            continue;
+        }
+        
+        let file_name = src_file.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
+        
+        if claimed_sidecars.contains(src_file) {
+            progress.message(&format!("Importing {} with its photo", file_name))?;
+            copied_size += file_size;
+            progress.bytes(copied_size, total_size)?;
+            continue;
+        }
+        
+        if options.new_only && offloaded.is_offloaded(src_file) {
+            progress.message(&format!("Skipped: {} (already offloaded from this card)", file_name))?;
+            report.skip(src_file, file_size, "already offloaded from this card");
+            copied_size += file_size + sidecar_size;
+            progress.bytes(copied_size, total_size)?;
+            continue;
+        }
+        
+        if !options.import_anomalies {
+            if let Some(anomaly) = sniff::check(source) {
+                let detected = anomaly.detected.as_deref().unwrap_or("unrecognised data");
+                let reason = format!("contents look like {}, not .{}", detected, anomaly.extension);
+                progress.message(&format!("Skipped: {} ({})", file_name, reason))?;
+                report.anomalies.push(anomaly);
+                report.skip(src_file, file_size, &reason);
+                copied_size += file_size + sidecar_size;
+                progress.bytes(copied_size, total_size)?;
+                continue;
+            }
+        }
+        
+        // Warn about (or skip) content already imported anywhere, not just here
+        if let Some(entry) = catalog.find(progress.app, file_size, || hash_file(source).ok()) {
+            let folder = Path::new(&entry.destination).parent().unwrap_or_else(|| Path::new("")).display().to_string();
+            if options.skip_cataloged {
+                progress.message(&format!("Skipped: {} (already imported to {})", file_name, folder))?;
+                report.skip(src_file, file_size, &format!("already imported to {}", folder));
+                copied_size += file_size + sidecar_size;
+                progress.bytes(copied_size, total_size)?;
+                continue;
+            }
+            progress.message(&format!("Already imported: {} (to {})", file_name, folder))?;
+        }
+        
+        // Files too large for the destination filesystem go to the oversize
+        // destination if one was given, otherwise they're skipped up front
+        let root = file_roots[i].as_ref();
+        let file_system = file_system_of(root);
+        let routed = if max_file_size(&file_system).is_some_and(|max| file_size > max) {
+            match &oversize_dest {
+                Some(oversize_dest) => {
+                    progress.message(&format!("Rerouting: {} (too large for {})", file_name, file_system))?;
+                    oversize_dest.clone()
+                }
+                None => {
+                    progress.message(&format!("Skipped: {} (too large for {})", file_name, file_system))?;
+                    report.skip(src_file, file_size, &format!("too large for {}", file_system));
+                    copied_size += file_size + sidecar_size;
+                    progress.bytes(copied_size, total_size)?;
+                    continue;
+                }
+            }
+        } else {
+            root.cloned().unwrap_or_else(|| dest.clone())
+        };
+        // Once the user sends a paused destination's files elsewhere, the rest
+        // of the files routed there follow
+        let file_dest = redirected.get(&routed).cloned().unwrap_or_else(|| routed.clone());
+        
+        // The file's folders below the card root, recreated in each destination
+        let subfolder = structure_root.as_deref().and_then(|root| relative_folder(src_file, root, name_form));
+        let nest = |dir: &Path| match &subfolder {
+            Some(subfolder) => dir.join(subfolder),
+            None => dir.to_path_buf(),
+        };
+        let mut file_dest = nest(&file_dest);
+        if let Err(e) = create_destination(&file_dest) {
+            progress.message(&format!("Failed to create folder for {}: {}", file_name, e))?;
+            report.fail(src_file, e);
+            copied_size += file_size + sidecar_size;
+            progress.bytes(copied_size, total_size)?;
+            continue;
+        }
+        
+        let mut target_file = match &template {
+            Some(template) => {
+                let info = template.capture_info(source);
+                template.next_free(&mut seq, |seq| file_dest.join(name_form.apply(&template.render(source, info.as_ref(), seq))))
+            }
+            None => file_dest.join(name_form.apply(file_name)),
+        };
+        
+        // Resolve name collisions according to the chosen strategy
+        if target_file.exists() {
+            let skip_reason = match conflict_strategy {
+                ConflictStrategy::Skip => Some("already exists"),
+                ConflictStrategy::Overwrite => match set_aside_existing(&target_file, actions) {
+                    Ok(()) => {
+                        progress.message(&format!("Overwriting: {}", file_name))?;
+                        None
+                    }
+                    Err(e) => {
+                        progress.message(&format!("Failed to set aside {}: {}", file_name, e))?;
+                        Some("existing file could not be set aside")
+                    }
+                },
+                ConflictStrategy::Rename => {
+                    target_file = next_available_path(&target_file);
+                    None
+                }
+                ConflictStrategy::SkipIfIdentical => match files_identical(source, &target_file) {
+                    Ok(true) => Some("identical file exists"),
+                    Ok(false) => {
+                        target_file = next_available_path(&target_file);
+                        None
+                    }
+                    Err(e) => {
+                        progress.message(&format!("Failed to compare {}: {}", file_name, e))?;
+                        Some("comparison failed")
+                    }
+                },
+            };
+            
+            if let Some(reason) = skip_reason {
+                progress.message(&format!("Skipped: {} ({})", file_name, reason))?;
+                report.skip(src_file, file_size, reason);
+                copied_size += file_size + sidecar_size; // Count as "copied" for progress calculation
+                progress.bytes(copied_size, total_size)?;
+                continue;
+            }
+            
+            if conflict_strategy != ConflictStrategy::Overwrite {
+                let new_name = target_file.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
+                progress.message(&format!("Renamed: {} -> {}", file_name, new_name))?;
+            }
+        }
+        
+        // The mirror copy keeps the same name; a clash there is resolved on its
+        // own. An identical copy already there counts as mirrored.
+        let (mirror_target, mirror_present) = match &mirror_dest {
+            Some(mirror_dest) => {
+                let mirror_dir = nest(mirror_dest);
+                if let Err(e) = create_destination(&mirror_dir) {
+                    warn!("Failed to create {} on mirror: {}", mirror_dir.display(), e);
+                }
+                let candidate = mirror_dir.join(target_file.file_name().unwrap_or_default());
+                let (target, present, reason) = match mirror_target(candidate, source, conflict_strategy, actions) {
+                    MirrorTarget::Write(target) => (Some(target), None, None),
+                    MirrorTarget::Present(present) => (None, Some(present), Some("identical file exists")),
+                    MirrorTarget::Skipped => (None, None, Some("already exists")),
+                };
+                if let Some(reason) = reason {
+                    progress.message(&format!("Skipped on mirror: {} ({})", file_name, reason))?;
+                    if let Some(mirror) = &mut report.mirror {
+                        mirror.skipped += 1;
+                    }
+                }
+                (target, present)
+            }
+            None => (None, None),
+        };
+        
+        // Trimmed clips are cut by ffmpeg rather than copied, without their
+        // sidecars. Their sources are kept since only part was imported, and
+        // they stay out of the catalog, which matches on the card's contents,
+        // and out of the offload history, so `new_only` still lists them and
+        // the card isn't cleared for formatting with the rest of the footage
+        // still on it.
+        if let Some(&clip_trim) = options.trims.get(file_path) {
+            progress.message(&format!("Trimming: {}", file_name))?;
+            match progress.time(Stage::Write, || trim::extract(source, &target_file, clip_trim)) {
+                Ok(size) => {
+                    let hash = hash_file(&target_file);
+                    // Copied from the cut clip, then read back like any other mirror copy
+                    if let Some(mirror_target) = &mirror_target {
+                        let mirror_error = match &hash {
+                            Ok(_) => fs::copy(&target_file, mirror_target).err(),
+                            Err(e) => Some(std::io::Error::new(e.kind(), e.to_string())),
+                        };
+                        record_mirror(&mut report, mirror_target, hash.as_ref().copied().unwrap_or_default(), mirror_error, file_name, progress)?;
+                    }
+                    report.copied += 1;
+                    report.bytes += size;
+                    report.log.push(LogEntry::copied(src_file, &target_file, size, hash.ok()));
+                    progress.message(&format!(
+                        "Trimmed: {} ({:.1}-{:.1} s)",
+                        file_name, clip_trim.start_seconds, clip_trim.end_seconds
+                    ))?;
+                    progress.file_copied(file_name, size, i + 1, file_paths.len())?;
+                    if verify_then_delete {
+                        staged.push(StagedSource {
+                            source: source.clone(),
+                            target: target_file.clone(),
+                            mirror: None,
+                            hash: 0,
+                            kept: Some("only part of the clip was imported".to_string()),
+                        });
+                    }
+                }
+                Err(e) => {
+                    progress.message(&format!("Failed to trim {}: {}", file_name, e))?;
+                    report.fail(src_file, e);
+                }
+            }
+            copied_size += file_size + sidecar_size;
+            progress.bytes(copied_size, total_size)?;
+            continue;
+        }
+        
+        // Hold off starting a file that would leave less free space than the
+        // floor; as with a full destination, the user can redirect from here
+        if low_space_threshold > 0 && available_space(&file_dest).is_some_and(|a| a < file_size + low_space_threshold) {
+            let remaining = total_size - copied_size + low_space_threshold;
+            let needed = file_size + low_space_threshold;
+            if let Some(new_dest) = wait_for_space(file_name, &file_dest, needed, remaining, Some(low_space_threshold), control, progress)? {
+                target_file = redirect_target(&nest(&new_dest), &target_file)?;
+                file_dest = nest(&new_dest);
+                redirected.insert(routed.clone(), new_dest);
+            }
+            if control.is_cancelled() {
+                continue;
+            }
+        }
+        progress.watch_space(target_file.parent().unwrap_or(&dest), low_space_threshold, if preallocate { 0 } else { file_size });
+        
+        progress.message(&format!("Copying: {}", file_name))?;
+        if let Some(record) = record {
+            record.checkpoint(i, &target_file);
+        }
+        
+        // Copy file with progress tracking for large files, pausing instead of
+        // failing when the destination runs out of space
+        let result = loop {
+            let copy = || copy_file_with_progress(source, &target_file, mirror_target.as_deref(), copied_size, total_size, preallocate, progress);
+            match with_retries(retry, file_name, progress, copy) {
+                // The destination filled up, or its free space fell under the floor partway
+                Err(e) if e.error.kind() == std::io::ErrorKind::StorageFull && !control.is_cancelled() => {
+                    let floor = progress.below_space_floor().then_some(low_space_threshold);
+                    let reserve = floor.unwrap_or(0);
+                    let remaining = total_size - copied_size + reserve;
+                    if let Some(new_dest) = wait_for_space(file_name, &file_dest, file_size + reserve, remaining, floor, control, progress)? {
+                        target_file = redirect_target(&nest(&new_dest), &target_file)?;
+                        file_dest = nest(&new_dest);
+                        redirected.insert(routed.clone(), new_dest);
+                    }
+                    if control.is_cancelled() {
+                        break Err(e);
+                    }
+                    progress.watch_space(target_file.parent().unwrap_or(&dest), low_space_threshold, if preallocate { 0 } else { file_size });
+                }
+                other => break other,
+            }
+        };
+        // Sidecars are small enough to go without the floor
+        progress.watch_space(&dest, 0, 0);
+        if result.is_err() && control.is_cancelled() {
+            continue;
+        }
+        
+        match result {
+            Ok((hash, mirror_error)) => {
+                // With a mirror, each destination is read back and checked separately
+                if mirror_dest.is_some() {
+                    if let Err(e) = progress.time(Stage::Hash, || verify_copy(&target_file, hash)) {
+                        progress.message(&format!("Verification failed for {}: {}", file_name, e))?;
+                        report.fail(src_file, e);
+                        copied_size += file_size + sidecar_size;
+                        progress.bytes(copied_size, total_size)?;
+                        continue;
+                    }
+                }
+                let mirrored = match &mirror_target {
+                    Some(mirror_target) => record_mirror(&mut report, mirror_target, hash, mirror_error, file_name, progress)?,
+                    None => mirror_dest.is_none() || mirror_present.is_some(),
+                };
+                
+                if !untracked {
+                    let cataloged = match &lightroom_drop {
+                        Some(drop) => drop.final_location(&target_file),
+                        None => longpath::strip(&target_file),
+                    };
+                    catalog.record(progress.app, hash, file_size, src_file, offloaded.card_id(src_file), &cataloged);
+                    offloaded.mark(src_file);
+                }
+                // Lightroom moves dropped files away, so those aren't processed
+                if lightroom_drop.is_none() {
+                    imported.push(longpath::display(&target_file));
+                }
+                copied_size += file_size;
+                report.copied += 1;
+                report.bytes += file_size;
+                report.log.push(LogEntry::copied(src_file, &target_file, file_size, Some(hash)));
+                progress.message(&format!("Copied: {}", file_name))?;
+                progress.file_copied(file_name, file_size, i + 1, file_paths.len())?;
+                
+                let original = target_file.with_file_name(name_form.apply(file_name));
+                if original != target_file && !original.exists() {
+                    actions.push(UndoAction::Renamed { original, renamed: target_file.clone() });
+                }
+                
+                // In move mode, only remove the source once the synced copy (and
+                // the mirror's, if any) verifies
+                if move_files && mirrored {
+                    progress.message(&remove_verified_source(source, &target_file, actions))?;
+                } else if move_files {
+                    progress.message(&format!("Mirror copy of {} missing, kept on card", file_name))?;
+                }
+                if verify_then_delete {
+                    staged.push(StagedSource {
+                        source: source.clone(),
+                        target: target_file.clone(),
+                        mirror: mirror_target.clone().or_else(|| mirror_present.clone()),
+                        hash,
+                        kept: (!mirrored).then(|| "mirror copy missing".to_string()),
+                    });
+                }
+                
+                for (sidecar, extended, size) in sidecars {
+                    let sidecar_name = sidecar.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
+                    let sidecar_target = target_file.with_file_name(sidecars::target_name(src_file, sidecar, &target_file));
+                    let sidecar_candidate = mirror_target.as_ref().or(mirror_present.as_ref()).map(|mirror_target| {
+                        mirror_target.with_file_name(sidecars::target_name(src_file, sidecar, mirror_target))
+                    });
+                    let sidecar_present = sidecar_candidate.clone().filter(|candidate| files_identical(extended, candidate).unwrap_or(false));
+                    let sidecar_mirror = sidecar_candidate
+                        .filter(|_| sidecar_present.is_none())
+                        .and_then(|candidate| mirror_target_for_sidecar(candidate, conflict_strategy, actions));
+                    
+                    if sidecar_target.exists() && conflict_strategy != ConflictStrategy::Overwrite {
+                        progress.message(&format!("Skipped: {} (already exists)", sidecar_name))?;
+                        report.log.push(LogEntry::skipped(sidecar, *size, "already exists"));
+                    } else if let Err(e) = set_aside_existing(&sidecar_target, actions) {
+                        progress.message(&format!("Failed to set aside {}: {}", sidecar_name, e))?;
+                    } else {
+                        let copy = || copy_file_with_progress(extended, &sidecar_target, sidecar_mirror.as_deref(), copied_size, total_size, false, progress);
+                        match with_retries(retry, sidecar_name, progress, copy) {
+                            Ok((hash, mirror_error)) => {
+                                report.bytes += size;
+                                report.log.push(LogEntry::copied(sidecar, &sidecar_target, *size, Some(hash)));
+                                progress.message(&format!("Copied: {}", sidecar_name))?;
+                                // So format clearance, which checks every file, sees it was imported
+                                if !untracked {
+                                    offloaded.mark(sidecar);
+                                }
+                                let mirrored = match &sidecar_mirror {
+                                    Some(sidecar_mirror) => record_mirror(&mut report, sidecar_mirror, hash, mirror_error, sidecar_name, progress)?,
+                                    None => mirror_dest.is_none() || sidecar_present.is_some(),
+                                };
+                                if move_files && mirrored {
+                                    progress.message(&remove_verified_source(extended, &sidecar_target, actions))?;
+                                }
+                                if verify_then_delete {
+                                    staged.push(StagedSource {
+                                        source: extended.clone(),
+                                        target: sidecar_target.clone(),
+                                        mirror: sidecar_mirror.clone().or_else(|| sidecar_present.clone()),
+                                        hash,
+                                        kept: (!mirrored).then(|| "mirror copy missing".to_string()),
+                                    });
+                                }
+                            }
+                            Err(e) => {
+                                progress.message(&copy_failed_message(sidecar_name, &e))?;
+                                report.fail_copy(sidecar, e);
+                            }
+                        }
+                    }
+                    copied_size += size;
+                }
+            }
+            Err(e) => {
+                progress.message(&copy_failed_message(file_name, &e))?;
+                report.fail_copy(src_file, e);
+            }
+        }
+        if let Ok(Some(benchmark)) = progress.benchmark.lock().as_deref_mut() {
+            benchmark.finish_file(file_path, file_size + sidecar_size);
+        }
+        
+        // Report final progress for this file
+        progress.bytes(copied_size, total_size)?;
+    }
+    
+    if verify_then_delete {
+        report.deletion = Some(delete_verified_sources(&staged, &report, progress, actions)?);
+    }
+    
+    offloaded.save();
+    audio::process_imported(progress.app, &imported);
+    if options.timelapse_previews {
+        timelapse::assemble_imported(progress.app, imported);
+    }
+    progress.update(|p| {
+        p.processed_files = file_paths.len();
+        p.current_file = None;
+    });
+    report.duration = started.elapsed().as_millis() as u64;
+    report.benchmark = progress.benchmark.lock().ok().and_then(|mut b| b.take()).map(|b| b.report(COPY_BUFFER_SIZE));
+    report.summary = format!("{} copied, {} skipped", report.copied, report.skipped);
+    if report.failed > 0 {
+        report.summary += &format!(", {} failed", report.failed);
+    }
+    if let Some(deletion) = &report.deletion {
+        report.summary += &format!(", {} deleted from card, {} kept", deletion.deleted, deletion.retained.len());
+    }
+    report.summary += &format!(" ({} in {})", humanize::bytes(report.bytes), humanize::duration(report.duration / 1000));
+    if report.cancelled {
+        report.summary = format!("Cancelled: {}", report.summary);
+    }
+    Ok(report)
+}
+
+/// Shared state that lets the UI steer a running import
+#[derive(Default)]
+pub struct ImportControl {
+    paused: AtomicBool,
+    redirect: Mutex<Option<PathBuf>>,
+    cancelled: AtomicBool,
+}
+
+#[derive(Serialize, Clone)]
+struct ImportPaused {
+    file: String,
+    destination: String,
+    bytes_needed: u64,
+    /// The free-space floor reached, when paused for low space rather than
+    /// a full destination
+    low_space_threshold: Option<u64>,
+}
+
+/// Block until the destination has `needed` bytes free, or the user points
+/// the remaining files at another destination (which is then returned), or
+/// the import is cancelled
+fn wait_for_space(
+    file_name: &str,
+    dest: &Path,
+    needed: u64,
+    remaining: u64,
+    low_space_threshold: Option<u64>,
+    control: &ImportControl,
+    progress: &ProgressReporter,
+) -> Result<Option<PathBuf>, String> {
+    let available = available_space(dest).unwrap_or(0);
+    let paused = ImportPaused {
+        file: file_name.to_string(),
+        destination: longpath::display(dest),
+        bytes_needed: remaining.saturating_sub(available),
+        low_space_threshold,
+    };
+    match low_space_threshold {
+        Some(threshold) => {
+            warn!("Destination space low, pausing import: {} bytes left", available);
+            let low = LowSpace { destination: paused.destination.clone(), available, threshold };
+            progress.emit("import-low-space", &low)?;
+            progress.message(&format!("Paused: destination space low ({} left)", humanize::bytes(available)))?;
+        }
+        None => {
+            warn!("Destination full, pausing import: {} bytes needed", paused.bytes_needed);
+            progress.message(&format!("Paused: destination is full ({} more bytes needed)", paused.bytes_needed))?;
+        }
+    }
+    progress.emit("import-paused", &paused)?;
+    
+    control.paused.store(true, Ordering::SeqCst);
+    let new_dest = loop {
+        std::thread::sleep(Duration::from_secs(2));
+        
+        if let Some(new_dest) = control.redirect.lock().map_err(|e| e.to_string())?.take() {
+            break Some(new_dest);
+        }
+        if available_space(dest).is_some_and(|available| available >= needed) || control.is_cancelled() {
+            break None;
+        }
+    };
+    control.paused.store(false, Ordering::SeqCst);
+    
+    info!("Resuming import");
+    progress.emit("import-resumed", ())?;
+    progress.message("Resumed")?;
+    Ok(new_dest)
+}
+
+/// Where the selection's folder structure starts: the card holding it, or
+/// else the deepest folder the selected files share
+fn structure_root(file_paths: &[String]) -> Option<PathBuf> {
+    if let Some(mount_point) = source_drive(file_paths) {
+        return Some(PathBuf::from(mount_point));
+    }
+    let mut parents = file_paths.iter().filter_map(|p| Path::new(p).parent());
+    let mut common = parents.next()?.to_path_buf();
+    for parent in parents {
+        while !parent.starts_with(&common) {
+            if !common.pop() {
+                return None;
+            }
+        }
+    }
+    Some(common)
+}
+
+/// The folders between `root` and `src`, such as `DCIM/100CANON`, named in
+/// `name_form` like the files; None when `src` sits directly in `root`
+fn relative_folder(src: &Path, root: &Path, name_form: NameForm) -> Option<PathBuf> {
+    let relative = src.parent()?.strip_prefix(root).ok()?;
+    let folder: PathBuf = relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(name_form.apply(&part.to_string_lossy())),
+            _ => None,
+        })
+        .collect();
+    (!folder.as_os_str().is_empty()).then_some(folder)
+}
+
+/// Create a destination folder, keeping why it couldn't be (a missing drive,
+/// no permission, a read-only card) for the UI to act on
+fn create_destination(dir: &Path) -> Result<(), CamPorterError> {
+    fs::create_dir_all(dir).map_err(|e| CamPorterError::io(format!("Failed to create {}", longpath::display(dir)), e))
+}
+
+/// Where a file goes after the user redirected a paused import to `new_dest`
+fn redirect_target(new_dest: &Path, target_file: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(new_dest).map_err(|e| e.to_string())?;
+    let target = new_dest.join(target_file.file_name().unwrap_or_default());
+    Ok(if target.exists() { next_available_path(&target) } else { target })
+}
+
+impl ImportControl {
+    /// Stop the import after the file being copied
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Whether the import is waiting for space
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Send the remaining files of the paused import to another destination
+    pub fn redirect(&self, destination: String) -> Result<(), String> {
+        if !self.paused.load(Ordering::SeqCst) {
+            return Err("No import is paused".to_string());
+        }
+        *self.redirect.lock().map_err(|e| e.to_string())? = Some(longpath::extend(Path::new(&destination)));
+        Ok(())
+    }
+}
+
+/// Whether an I/O error may clear up on another attempt
+fn is_transient(error: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    !matches!(
+        error.kind(),
+        ErrorKind::StorageFull
+            | ErrorKind::NotFound
+            | ErrorKind::PermissionDenied
+            | ErrorKind::InvalidInput
+            | ErrorKind::AlreadyExists
+            | ErrorKind::Unsupported
+    )
+}
+
+/// Run a copy, starting over (which reopens source and destination) after
+/// transient errors until the policy's attempts are used up
+fn with_retries<T>(
+    policy: RetryPolicy,
+    file_name: &str,
+    progress: &ProgressReporter,
+    mut copy: impl FnMut() -> Result<T, CopyError>,
+) -> Result<T, CopyError> {
+    let mut delay = Duration::from_millis(policy.initial_delay_ms);
+    let mut attempt = 1;
+    loop {
+        match copy() {
+            Err(e) if attempt < policy.attempts && is_transient(&e.error) => {
+                warn!("Copy of {} failed (attempt {} of {}): {}", file_name, attempt, policy.attempts, e);
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+                let _ = progress.message(&format!("Retrying {} (attempt {} of {})", file_name, attempt, policy.attempts));
+            }
+            result => return result,
+        }
+    }
+}
+
+/// A copy that failed, noting whether it broke off after data was written
+pub struct CopyError {
+    pub error: std::io::Error,
+    pub partial: bool,
+}
+
+impl From<std::io::Error> for CopyError {
+    fn from(error: std::io::Error) -> Self {
+        CopyError { error, partial: false }
+    }
+}
+
+impl std::fmt::Display for CopyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+/// Read size for copying and hashing
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Where a file is written until it's complete, so an interrupted copy is
+/// never mistaken for an existing file
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+/// Copy `src` to `dest`, and to `mirror` from the same read buffer if given.
+/// Returns the xxh3 hash of the data written, plus the error that stopped the
+/// mirror copy if it failed; a failing mirror doesn't abort the primary copy.
+/// Output goes to `.part` files that are renamed into place once synced, and
+/// removed if the copy fails.
+fn copy_file_with_progress(
+    src: &Path,
+    dest: &Path,
+    mirror: Option<&Path>,
+    initial_copied: u64,
+    total_size: u64,
+    preallocate: bool,
+    progress: &ProgressReporter,
+) -> Result<(u64, Option<std::io::Error>), CopyError> {
+    let dest_part = part_path(dest);
+    let mirror_part = mirror.map(part_path);
+    let remove_parts = || {
+        let _ = fs::remove_file(&dest_part);
+        if let Some(mirror_part) = &mirror_part {
+            let _ = fs::remove_file(mirror_part);
+        }
+    };
+    
+    let (hash, mut mirror_error) =
+        match write_parts(src, &dest_part, mirror_part.as_deref(), initial_copied, total_size, preallocate, progress) {
+            Ok(written) => written,
+            Err(e) => {
+                remove_parts();
+                return Err(e);
+            }
+        };
+    if let Err(error) = progress.time(Stage::Metadata, || fs::rename(&dest_part, dest)) {
+        remove_parts();
+        return Err(CopyError { error, partial: true });
+    }
+    if let (Some(mirror), Some(mirror_part)) = (mirror, &mirror_part) {
+        if mirror_error.is_none() {
+            mirror_error = progress.time(Stage::Metadata, || fs::rename(mirror_part, mirror)).err();
+        }
+        if mirror_error.is_some() {
+            let _ = fs::remove_file(mirror_part);
+        }
+    }
+    Ok((hash, mirror_error))
+}
+
+fn write_parts(
+    src: &Path,
+    dest: &Path,
+    mirror: Option<&Path>,
+    initial_copied: u64,
+    total_size: u64,
+    preallocate: bool,
+    progress: &ProgressReporter,
+) -> Result<(u64, Option<std::io::Error>), CopyError> {
+    use std::io::{Read, Write};
+    
+    let mut src_file = fs::File::open(src)?;
+    let src_metadata = src_file.metadata()?;
+    let file_size = src_metadata.len();
+    
+    // Let the kernel or file system copy (or clone) the data when there's no
+    // mirror to feed and no throttle to honour; the hash then comes from
+    // reading the finished copy back
+    let throttled = progress.rate_limit.lock().map(|l| l.is_some()).unwrap_or(false);
+    if mirror.is_none() && !throttled {
+        let mut copied = 0;
+        let fast = progress.time(Stage::Read, || {
+            fastcopy::copy(&src_file, src, dest, file_size, |bytes| {
+                progress.transferred(bytes);
+                copied += bytes;
+                let _ = progress.bytes(initial_copied + copied, total_size);
+            })
+        })
+        .map_err(|error| CopyError { error, partial: true })?;
+        if fast {
+            let dest_file = fs::OpenOptions::new().write(true).open(dest)?;
+            finish_output(&dest_file, copied, file_size, false, &src_metadata, progress)
+                .map_err(|error| CopyError { error, partial: true })?;
+            let hash = progress.time(Stage::Hash, || hash_file(dest)).map_err(|error| CopyError { error, partial: true })?;
+            return Ok((hash, None));
+        }
+    }
+    
+    let mut dest_file = create_output(dest, file_size, preallocate)?;
+    let mut mirror_file = mirror.map(|mirror| create_output(mirror, file_size, preallocate));
+    
+    let mut buffer = [0; COPY_BUFFER_SIZE];
+    let mut hasher = Xxh3::new();
+    let mut copied_this_file = 0u64;
+    let mut last_progress_report = 0u64;
+    
+    loop {
+        let partial = copied_this_file > 0;
+        let bytes_read = progress.time(Stage::Read, || src_file.read(&mut buffer)).map_err(|error| {
+            progress.read_errors.fetch_add(1, Ordering::Relaxed);
+            CopyError { error, partial }
+        })?;
+        if bytes_read == 0 {
+            break;
+        }
+        
+        progress.time(Stage::Write, || {
+            dest_file.write_all(&buffer[..bytes_read])?;
+            if let Some(Ok(file)) = &mut mirror_file {
+                if let Err(e) = file.write_all(&buffer[..bytes_read]) {
+                    mirror_file = Some(Err(e));
+                }
+            }
+            Ok(())
+        })
+        .map_err(|error| CopyError { error, partial })?;
+        progress.time(Stage::Hash, || hasher.update(&buffer[..bytes_read]));
+        progress.transferred(bytes_read as u64);
+        copied_this_file += bytes_read as u64;
+        if progress.below_space_floor() {
+            let error = std::io::Error::new(std::io::ErrorKind::StorageFull, "Destination free space fell below the floor");
+            return Err(CopyError { error, partial: true });
+        }
+        
+        // Report progress every 1MB or 10% of file, whichever is smaller
+        let progress_interval = std::cmp::min(1024 * 1024, file_size / 10).max(64 * 1024);
+        
+        if copied_this_file - last_progress_report >= progress_interval || copied_this_file == file_size {
+            let total_copied = initial_copied + copied_this_file;
+            // Continue even if progress reporting fails
+            let _ = progress.bytes(total_copied, total_size);
+            last_progress_report = copied_this_file;
+        }
+    }
+    
+    finish_output(&dest_file, copied_this_file, file_size, preallocate, &src_metadata, progress)
+        .map_err(|error| CopyError { error, partial: true })?;
+    let mirror_error = match mirror_file {
+        Some(Ok(file)) => finish_output(&file, copied_this_file, file_size, preallocate, &src_metadata, progress).err(),
+        Some(Err(e)) => Some(e),
+        None => None,
+    };
+    Ok((hasher.digest(), mirror_error))
+}
+
+fn create_output(path: &Path, file_size: u64, preallocate: bool) -> Result<fs::File, std::io::Error> {
+    let file = fs::File::create(path)?;
+    
+    // Reserve the whole file up front to limit fragmentation and to fail
+    // fast when the destination can't fit it
+    if preallocate && file_size > 0 {
+        preallocate_file(&file, file_size)?;
+    }
+    Ok(file)
+}
+
+fn finish_output(
+    file: &fs::File,
+    copied: u64,
+    file_size: u64,
+    preallocate: bool,
+    src_metadata: &fs::Metadata,
+    progress: &ProgressReporter,
+) -> Result<(), std::io::Error> {
+    progress.time(Stage::Metadata, || {
+        // Drop any preallocated tail if the source turned out shorter
+        if preallocate && copied < file_size {
+            file.set_len(copied)?;
+        }
+        
+        // Keep the source's timestamps (and permissions on Unix) so date-sorted
+        // libraries see the capture time rather than the import time
+        let mut times = fs::FileTimes::new().set_modified(src_metadata.modified()?);
+        if let Ok(accessed) = src_metadata.accessed() {
+            times = times.set_accessed(accessed);
+        }
+        file.set_times(times)?;
+        #[cfg(unix)]
+        file.set_permissions(src_metadata.permissions())?;
+        Ok::<(), std::io::Error>(())
+    })?;
+    
+    progress.time(Stage::Fsync, || file.sync_all())
+}
+
+#[cfg(target_os = "linux")]
+fn preallocate_file(file: &fs::File, len: u64) -> Result<(), std::io::Error> {
+    use std::os::unix::io::AsRawFd;
+    
+    // posix_fallocate returns the error code rather than setting errno
+    match unsafe { libc::posix_fallocate(file.as_raw_fd(), 0, len as libc::off_t) } {
+        0 => Ok(()),
+        code => Err(std::io::Error::from_raw_os_error(code)),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn preallocate_file(file: &fs::File, len: u64) -> Result<(), std::io::Error> {
+    use std::os::unix::io::AsRawFd;
+    
+    let mut store = libc::fstore_t {
+        fst_flags: libc::F_ALLOCATECONTIG,
+        fst_posmode: libc::F_PEOFPOSMODE,
+        fst_offset: 0,
+        fst_length: len as libc::off_t,
+        fst_bytesalloc: 0,
+    };
+    // Fall back to a non-contiguous allocation if no contiguous run is free
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &store) } == -1 {
+        store.fst_flags = libc::F_ALLOCATEALL;
+        if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &store) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    file.set_len(len)
+}
+
+/// On Windows set_len maps to SetEndOfFile, which reserves the clusters
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn preallocate_file(file: &fs::File, len: u64) -> Result<(), std::io::Error> {
+    file.set_len(len)
+}
+
+/// Where a file's mirror copy goes after applying the conflict strategy
+enum MirrorTarget {
+    Write(PathBuf),
+    /// An identical copy is already on the mirror
+    Present(PathBuf),
+    /// Another file holds the name and the strategy keeps it
+    Skipped,
+}
+
+fn mirror_target(candidate: PathBuf, src: &Path, strategy: ConflictStrategy, actions: &mut Vec<UndoAction>) -> MirrorTarget {
+    if !candidate.exists() {
+        return MirrorTarget::Write(candidate);
+    }
+    match strategy {
+        ConflictStrategy::Skip => MirrorTarget::Skipped,
+        ConflictStrategy::Overwrite => match set_aside_existing(&candidate, actions) {
+            Ok(()) => MirrorTarget::Write(candidate),
+            Err(_) => MirrorTarget::Skipped,
+        },
+        ConflictStrategy::Rename => MirrorTarget::Write(next_available_path(&candidate)),
+        ConflictStrategy::SkipIfIdentical => match files_identical(src, &candidate) {
+            Ok(true) => MirrorTarget::Present(candidate),
+            _ => MirrorTarget::Write(next_available_path(&candidate)),
+        },
+    }
+}
+
+/// Sidecars follow their media's mirror name, so a clash is only resolved by
+/// overwriting
+fn mirror_target_for_sidecar(candidate: PathBuf, strategy: ConflictStrategy, actions: &mut Vec<UndoAction>) -> Option<PathBuf> {
+    if !candidate.exists() {
+        return Some(candidate);
+    }
+    match strategy {
+        ConflictStrategy::Overwrite => set_aside_existing(&candidate, actions).ok().map(|_| candidate),
+        _ => None,
+    }
+}
+
+/// Verify a mirror copy and add the outcome to the report; returns whether
+/// the mirror holds a good copy
+fn record_mirror(
+    report: &mut ImportReport,
+    mirror_target: &Path,
+    hash: u64,
+    mirror_error: Option<std::io::Error>,
+    file_name: &str,
+    progress: &ProgressReporter,
+) -> Result<bool, String> {
+    let outcome = match mirror_error {
+        Some(e) => Err(e.to_string()),
+        None => progress.time(Stage::Hash, || verify_copy(mirror_target, hash)),
+    };
+    let Some(mirror) = &mut report.mirror else {
+        return Ok(false);
+    };
+    match outcome {
+        Ok(()) => {
+            mirror.copied += 1;
+            progress.message(&format!("Mirrored: {}", file_name))?;
+            Ok(true)
+        }
+        Err(e) => {
+            progress.message(&format!("Failed to mirror {}: {}", file_name, e))?;
+            mirror.fail(mirror_target, e);
+            Ok(false)
+        }
+    }
+}
+
+/// Read a written file back and compare it with the hash of what was sent
+fn verify_copy(path: &Path, expected: u64) -> Result<(), String> {
+    match hash_file(path) {
+        Ok(hash) if hash == expected => Ok(()),
+        Ok(_) => Err("contents differ from source".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Move an existing destination file out of the way of an overwrite into
+/// `_replaced/`, keeping it for undo
+fn set_aside_existing(path: &Path, actions: &mut Vec<UndoAction>) -> Result<(), std::io::Error> {
+    if path.exists() {
+        let retained = replaced::retain(path)?;
+        actions.push(UndoAction::Overwritten { path: path.to_path_buf(), retained });
+    }
+    Ok(())
+}
+
+/// Delete a source file after checking its copy matches, for move mode.
+/// Returns the progress message describing the outcome.
+fn remove_verified_source(src: &Path, target: &Path, actions: &mut Vec<UndoAction>) -> String {
+    let file_name = src.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
+    match files_identical(src, target) {
+        Ok(true) => match fs::remove_file(src) {
+            Ok(_) => {
+                actions.push(UndoAction::Deleted { path: src.to_path_buf(), copy: target.to_path_buf() });
+                format!("Moved: {}", file_name)
+            }
+            Err(e) => format!("Failed to delete {} from card: {}", file_name, e),
+        },
+        Ok(false) => format!("Verification failed for {}, kept on card", file_name),
+        Err(e) => format!("Failed to verify {}, kept on card: {}", file_name, e),
+    }
+}
+
+/// The final pass of `verify_then_delete`: re-read every staged source and
+/// its copies, then delete the sources, but only if all of them still match
+/// and nothing in the import failed or was cancelled. Whatever stays on the
+/// card is listed with why, skipped and failed files included.
+fn delete_verified_sources(
+    staged: &[StagedSource],
+    report: &ImportReport,
+    progress: &ProgressReporter,
+    actions: &mut Vec<UndoAction>,
+) -> Result<DeletionReport, String> {
+    let mut deletion = DeletionReport::default();
+    let retain = |deletion: &mut DeletionReport, path: &Path, reason: String| {
+        deletion.retained.push(RetainedFile { path: longpath::display(path), reason });
+    };
+
+    let mut verified = Vec::new();
+    let mut mismatched = 0;
+    for (i, file) in staged.iter().enumerate() {
+        if let Some(kept) = &file.kept {
+            retain(&mut deletion, &file.source, kept.clone());
+            continue;
+        }
+        if report.cancelled {
+            retain(&mut deletion, &file.source, "kept because the import was cancelled".to_string());
+            continue;
+        }
+        progress.message(&format!("Verifying {} of {}", i + 1, staged.len()))?;
+        let check = |path: &Path| match progress.time(Stage::Hash, || hash_file(path)) {
+            Ok(hash) if hash == file.hash => Ok(()),
+            Ok(_) => Err(format!("{} no longer matches", longpath::display(path))),
+            Err(e) => Err(format!("Failed to read {}: {}", longpath::display(path), e)),
+        };
+        match [Some(&file.source), Some(&file.target), file.mirror.as_ref()].into_iter().flatten().try_for_each(|p| check(p)) {
+            Ok(()) => verified.push(file),
+            Err(e) => {
+                mismatched += 1;
+                retain(&mut deletion, &file.source, e);
+            }
+        }
+    }
+    deletion.verified = verified.len();
+
+    let failed = report.failed + report.mirror.as_ref().map_or(0, |m| m.failed);
+    let blocker = if report.cancelled {
+        Some("the import was cancelled".to_string())
+    } else if mismatched > 0 {
+        Some(format!("{} files failed the final verification", mismatched))
+    } else if failed > 0 {
+        Some(format!("{} files failed to copy", failed))
+    } else {
+        None
+    };
+    for file in verified {
+        if let Some(blocker) = &blocker {
+            retain(&mut deletion, &file.source, format!("verified, but kept because {}", blocker));
+            continue;
+        }
+        match fs::remove_file(&file.source) {
+            Ok(()) => {
+                actions.push(UndoAction::Deleted { path: file.source.clone(), copy: file.target.clone() });
+                deletion.deleted += 1;
+            }
+            Err(e) => retain(&mut deletion, &file.source, format!("Failed to delete: {}", e)),
+        }
+    }
+
+    for entry in report.log.iter().filter(|e| !matches!(e.outcome, Outcome::Copied)) {
+        let reason = entry.reason.clone().unwrap_or_default();
+        let reason = match entry.outcome {
+            Outcome::Failed => format!("failed to copy: {}", reason),
+            _ => format!("not copied: {}", reason),
+        };
+        retain(&mut deletion, Path::new(&entry.source), reason);
+    }
+
+    match &blocker {
+        Some(blocker) => progress.message(&format!("Nothing deleted from card: {}", blocker))?,
+        None => progress.message(&format!("Verified and deleted {} files from card", deletion.deleted))?,
+    }
+    Ok(deletion)
+}
+
+/// Find the first free `name_N.ext` variant of a destination path
+pub fn next_available_path(target: &Path) -> PathBuf {
+    let parent = target.parent().unwrap_or_else(|| Path::new(""));
+    let stem = target.file_stem().and_then(|s| s.to_str()).unwrap_or("unknown");
+    let extension = target.extension().and_then(|e| e.to_str());
+    
+    let mut counter = 1;
+    loop {
+        let candidate_name = match extension {
+            Some(ext) => format!("{}_{}.{}", stem, counter, ext),
+            None => format!("{}_{}", stem, counter),
+        };
+        let candidate = parent.join(candidate_name);
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Compare two files by size first, then by content hash
+pub fn files_identical(a: &Path, b: &Path) -> Result<bool, std::io::Error> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+    Ok(hash_file(a)? == hash_file(b)?)
+}
+
+/// Hash a file's contents with xxh3
+pub fn hash_file(path: &Path) -> Result<u64, std::io::Error> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Xxh3::new();
+    let mut buffer = [0; COPY_BUFFER_SIZE];
+    
+    loop {
+        let bytes_read = file.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+    
+    Ok(hasher.digest())
+}
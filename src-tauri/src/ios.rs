@@ -0,0 +1,165 @@
+// iPhones and iPads, whose Camera Roll is only reachable through Apple's
+// AFC service. libimobiledevice's tools list the devices (`idevice_id`,
+// `ideviceinfo`) and ifuse mounts each one's media, after which DCIM is read
+// like a card's. Mounts live outside the temp workspace, so removing that
+// can never reach into a phone, and are undone when the app exits. The
+// device must be unlocked and have trusted this computer.
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::UNIX_EPOCH;
+use which::which;
+
+use crate::devices::DeviceObject;
+use crate::watchdog;
+
+/// Where each device's media is mounted, by UDID
+fn mount_point(udid: &str) -> PathBuf {
+    std::env::temp_dir().join("camporter-ios").join(udid)
+}
+
+/// Devices mounted by this session
+fn mounted() -> &'static Mutex<HashSet<String>> {
+    static MOUNTED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    MOUNTED.get_or_init(Default::default)
+}
+
+fn run(command: &mut Command, timeout: std::time::Duration) -> Result<String, String> {
+    let output = watchdog::output(command, timeout)?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if output.status.success() {
+        Ok(stdout)
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(stderr.lines().map(str::trim).rfind(|line| !line.is_empty()).unwrap_or(stdout.trim()).to_string())
+    }
+}
+
+/// UDID and name of each device connected over USB
+pub fn list_devices() -> Result<Vec<(String, String)>, String> {
+    if cfg!(target_os = "windows") || which("idevice_id").is_err() {
+        return Ok(Vec::new());
+    }
+    let output = run(Command::new("idevice_id").arg("-l"), watchdog::PROBE_TIMEOUT)?;
+    Ok(output
+        .lines()
+        .map(str::trim)
+        .filter(|udid| !udid.is_empty())
+        .map(|udid| {
+            // Locked devices that haven't trusted this computer don't give their name
+            let name = run(Command::new("ideviceinfo").args(["-u", udid, "-k", "DeviceName"]), watchdog::PROBE_TIMEOUT)
+                .ok()
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| "iPhone".to_string());
+            (udid.to_string(), name)
+        })
+        .collect())
+}
+
+/// Whether something other than the empty folder is at the mount point; a
+/// stale mount left by an unplugged device fails to stat at all
+#[cfg(unix)]
+fn is_mounted(dir: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (fs::metadata(dir), dir.parent().map(fs::metadata)) {
+        (Ok(dir), Some(Ok(parent))) => dir.dev() != parent.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_mounted(_dir: &Path) -> bool {
+    false
+}
+
+fn unmount(dir: &Path) -> Result<String, String> {
+    if cfg!(target_os = "linux") {
+        // Lazily, so a stale mount of an unplugged device comes off too
+        run(Command::new("fusermount").arg("-uz").arg(dir), watchdog::PROBE_TIMEOUT)
+    } else {
+        run(Command::new("umount").arg(dir), watchdog::PROBE_TIMEOUT)
+    }
+}
+
+/// The root of the device's media, mounting it first if need be
+fn mount(udid: &str) -> Result<PathBuf, String> {
+    let dir = mount_point(udid);
+    if !is_mounted(&dir) {
+        if which("ifuse").is_err() {
+            return Err("ifuse is not installed".to_string());
+        }
+        let _ = unmount(&dir);
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        run(Command::new("ifuse").arg(&dir).args(["-u", udid]), watchdog::DEVICE_LIST_TIMEOUT)?;
+        if !is_mounted(&dir) {
+            return Err("The device's media could not be mounted; unlock it and trust this computer".to_string());
+        }
+    }
+    // Including a mount left by a session that didn't exit cleanly
+    if let Ok(mut mounted) = mounted().lock() {
+        mounted.insert(udid.to_string());
+    }
+    Ok(dir)
+}
+
+/// Unmount every device this session mounted; called when the app exits
+pub fn unmount_all() {
+    let Ok(mut mounted) = mounted().lock() else {
+        return;
+    };
+    for udid in mounted.drain() {
+        let dir = mount_point(&udid);
+        match unmount(&dir) {
+            // Not remove_dir_all: if the unmount didn't take, that would delete photos
+            Ok(_) => {
+                let _ = fs::remove_dir(&dir);
+            }
+            Err(e) => log::warn!("Failed to unmount {}: {}", dir.display(), e),
+        }
+    }
+}
+
+/// Every file in the device's DCIM folders (100APPLE, 101APPLE, ...), each
+/// with its folder, such as "DCIM/100APPLE", as its object
+pub fn list_media(udid: &str) -> Result<Vec<DeviceObject>, String> {
+    let root = mount(udid)?;
+    let dcim = fs::read_dir(root.join("DCIM")).map_err(|e| format!("Failed to read DCIM: {}", e))?;
+    let mut objects = Vec::new();
+    for folder in dcim.filter_map(|e| e.ok()).filter(|e| e.path().is_dir()) {
+        let object = format!("DCIM/{}", folder.file_name().to_string_lossy());
+        let Ok(files) = fs::read_dir(folder.path()) else {
+            continue;
+        };
+        for file in files.filter_map(|e| e.ok()) {
+            let Some(metadata) = file.metadata().ok().filter(|m| m.is_file()) else {
+                continue;
+            };
+            let modified = metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok());
+            objects.push(DeviceObject {
+                object: object.clone(),
+                name: file.file_name().to_string_lossy().to_string(),
+                size: metadata.len(),
+                modified: modified.map_or(0, |d| d.as_secs()),
+            });
+        }
+    }
+    Ok(objects)
+}
+
+/// The Live Photo clip (IMG_0001.MOV) next to a photo, if it has one
+pub fn motion_clip(udid: &str, folder: &str, name: &str) -> Option<String> {
+    let stem = Path::new(name).file_stem()?.to_str()?;
+    let dir = mount(udid).ok()?.join(folder);
+    ["MOV", "mov"].iter().map(|ext| format!("{}.{}", stem, ext)).find(|clip| clip != name && dir.join(clip).is_file())
+}
+
+/// Copy `name` from `folder` on the device to `target`
+pub fn download(udid: &str, folder: &str, name: &str, target: &Path) -> Result<(), String> {
+    let source = mount(udid)?.join(folder).join(name);
+    fs::copy(&source, target).map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,456 @@
+// Queue of import jobs run in the background, so the UI can start several
+// transfers and poll each one by id. Jobs from different cards run side by
+// side, each emitting its own `import-*:<id>` events; jobs reading the same
+// card wait their turn.
+use log::{error, info};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+use crate::error::CamPorterError;
+use crate::import::{self, ImportControl, ImportOptions, ImportProgress, ImportReport, ProgressReporter};
+use crate::{humanize, notify, remap};
+use crate::undo::UndoJournal;
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    /// Stopped before finishing, or never started
+    Cancelled,
+}
+
+/// What `get_job_status` and `list_jobs` report for a job
+#[derive(Serialize, Clone)]
+pub struct JobStatus {
+    pub id: u64,
+    pub state: JobState,
+    /// Mount point of the card the job reads from, if all its files share one
+    pub source: Option<String>,
+    pub target_path: String,
+    pub progress: ImportProgress,
+    pub report: Option<ImportReport>,
+    pub error: Option<String>,
+    pub created: u64, // Unix timestamp
+    pub created_display: String,
+}
+
+struct Job {
+    id: u64,
+    state: JobState,
+    source: Option<String>,
+    /// The card it reads from, across all its partitions
+    card: Option<String>,
+    file_paths: Vec<String>,
+    target_path: String,
+    options: ImportOptions,
+    progress: Arc<Mutex<ImportProgress>>,
+    control: Arc<ImportControl>,
+    report: Option<ImportReport>,
+    error: Option<String>,
+    created: u64,
+}
+
+impl Job {
+    fn status(&self) -> JobStatus {
+        JobStatus {
+            id: self.id,
+            state: self.state,
+            source: self.source.clone(),
+            target_path: self.target_path.clone(),
+            progress: self.progress.lock().map(|p| p.clone()).unwrap_or_default(),
+            report: self.report.clone(),
+            error: self.error.clone(),
+            created: self.created,
+            created_display: humanize::date_time(self.created as i64),
+        }
+    }
+}
+
+/// A job handed to the worker thread
+struct QueuedJob {
+    id: u64,
+    file_paths: Vec<String>,
+    target_path: String,
+    options: ImportOptions,
+    progress: Arc<Mutex<ImportProgress>>,
+    control: Arc<ImportControl>,
+}
+
+#[derive(Default)]
+pub struct JobManager {
+    state: Mutex<JobsState>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct JobsState {
+    jobs: Vec<Job>,
+    next_id: u64,
+    /// Imports run straight from `import_selected_files` rather than queued,
+    /// numbered from the same ids as queued jobs
+    direct: Vec<DirectJob>,
+    /// Set once the window was closed, to quit when the last job is done
+    quitting: bool,
+}
+
+impl JobsState {
+    fn pending(&self) -> usize {
+        self.direct.len() + self.jobs.iter().filter(|j| matches!(j.state, JobState::Queued | JobState::Running)).count()
+    }
+}
+
+/// An import run outside the queue, with the paths it reads from and
+/// writes to and the control that steers it
+struct DirectJob {
+    id: u64,
+    paths: Vec<String>,
+    control: Arc<ImportControl>,
+}
+
+/// Counts an import run outside the queue as pending until dropped
+pub struct DirectImport<'a> {
+    manager: &'a JobManager,
+    id: u64,
+    control: Arc<ImportControl>,
+}
+
+impl DirectImport<'_> {
+    /// The import's own control, which `redirect_paused_job`, `cancel_job`
+    /// and quitting reach by its id
+    pub fn control(&self) -> &ImportControl {
+        &self.control
+    }
+
+    /// Announce the import's id with `job-started`, as queued jobs do, and
+    /// report its progress on that job's `import-*:<id>` channels
+    pub fn reporter<'a>(&self, app: &'a tauri::AppHandle) -> ProgressReporter<'a> {
+        let _ = app.emit("job-started", self.id);
+        ProgressReporter::new(app, None).for_job(self.id)
+    }
+}
+
+impl Drop for DirectImport<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.manager.state.lock() {
+            state.direct.retain(|direct| direct.id != self.id);
+        }
+        self.manager.changed.notify_all();
+    }
+}
+
+impl JobManager {
+    pub fn enqueue(&self, file_paths: Vec<String>, target_path: String, options: ImportOptions) -> Result<u64, String> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        state.next_id += 1;
+        let id = state.next_id;
+        let progress = ImportProgress { total_files: file_paths.len(), ..Default::default() };
+        state.jobs.push(Job {
+            id,
+            state: JobState::Queued,
+            source: import::source_drive(&file_paths),
+            card: import::source_card(&file_paths).map(|(card, _)| card),
+            file_paths,
+            target_path,
+            options,
+            progress: Arc::new(Mutex::new(progress)),
+            control: Arc::new(ImportControl::default()),
+            report: None,
+            error: None,
+            created: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        });
+        self.changed.notify_all();
+        Ok(id)
+    }
+
+    fn status(&self, id: u64) -> Result<JobStatus, String> {
+        let state = self.state.lock().map_err(|e| e.to_string())?;
+        state
+            .jobs
+            .iter()
+            .find(|j| j.id == id)
+            .map(Job::status)
+            .ok_or(format!("No job with id {}", id))
+    }
+
+    fn list(&self) -> Result<Vec<JobStatus>, String> {
+        Ok(self.state.lock().map_err(|e| e.to_string())?.jobs.iter().map(Job::status).collect())
+    }
+
+    /// Block until a queued job can start, then mark it as running. A job
+    /// waits while another is reading from the same card, even from another
+    /// of its partitions; jobs without a single source card wait for each
+    /// other too.
+    fn next_queued(&self) -> Option<QueuedJob> {
+        let mut state = self.state.lock().ok()?;
+        loop {
+            let busy: HashSet<Option<String>> =
+                state.jobs.iter().filter(|j| j.state == JobState::Running).map(|j| j.card.clone()).collect();
+            if let Some(job) = state.jobs.iter_mut().find(|j| j.state == JobState::Queued && !busy.contains(&j.card)) {
+                job.state = JobState::Running;
+                return Some(QueuedJob {
+                    id: job.id,
+                    file_paths: job.file_paths.clone(),
+                    target_path: job.target_path.clone(),
+                    options: job.options.clone(),
+                    progress: job.progress.clone(),
+                    control: job.control.clone(),
+                });
+            }
+            state = self.changed.wait(state).ok()?;
+        }
+    }
+
+    fn finish(&self, id: u64, result: Result<ImportReport, String>) {
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(job) = state.jobs.iter_mut().find(|j| j.id == id) {
+                match result {
+                    Ok(report) => {
+                        job.state = if report.cancelled { JobState::Cancelled } else { JobState::Completed };
+                        job.report = Some(report);
+                    }
+                    Err(e) => {
+                        job.state = JobState::Failed;
+                        job.error = Some(e);
+                    }
+                }
+            }
+        }
+        self.changed.notify_all();
+    }
+
+    fn control(&self, id: u64) -> Result<Arc<ImportControl>, String> {
+        let state = self.state.lock().map_err(|e| e.to_string())?;
+        let queued = state.jobs.iter().find(|j| j.id == id).map(|j| j.control.clone());
+        queued
+            .or_else(|| state.direct.iter().find(|d| d.id == id).map(|d| d.control.clone()))
+            .ok_or(format!("No job with id {}", id))
+    }
+
+    /// Cancel a queued job, or stop a running one or a direct import after
+    /// its current file
+    fn cancel(&self, id: u64) -> Result<(), String> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        if let Some(job) = state.jobs.iter_mut().find(|j| j.id == id) {
+            match job.state {
+                JobState::Queued => job.state = JobState::Cancelled,
+                JobState::Running => job.control.cancel(),
+                JobState::Completed | JobState::Failed | JobState::Cancelled => {}
+            }
+        } else {
+            state.direct.iter().find(|d| d.id == id).ok_or(format!("No job with id {}", id))?.control.cancel();
+        }
+        drop(state);
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    /// The one direct import paused for space, for callers that don't know its id
+    fn paused_direct(&self) -> Result<Arc<ImportControl>, String> {
+        let state = self.state.lock().map_err(|e| e.to_string())?;
+        let mut paused = state.direct.iter().filter(|d| d.control.is_paused());
+        match (paused.next(), paused.next()) {
+            (Some(direct), None) => Ok(direct.control.clone()),
+            (None, _) => Err("No import is paused".to_string()),
+            (Some(_), Some(_)) => Err("More than one import is paused; redirect one by its job id".to_string()),
+        }
+    }
+
+    /// Block until a job has completed or failed
+    pub fn wait(&self, id: u64) -> Result<(), String> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        loop {
+            let job = state.jobs.iter().find(|j| j.id == id).ok_or(format!("No job with id {}", id))?;
+            match job.state {
+                JobState::Completed => return Ok(()),
+                JobState::Failed => return Err(job.error.clone().unwrap_or_default()),
+                JobState::Cancelled => return Err(format!("Job {} was cancelled", id)),
+                JobState::Queued | JobState::Running => {}
+            }
+            state = self.changed.wait(state).map_err(|e| e.to_string())?;
+        }
+    }
+
+    /// Count an import run outside the queue, such as `import_selected_files`,
+    /// as pending while the returned guard lives
+    pub fn track_direct(&self, file_paths: &[String], target_path: &str, options: &ImportOptions) -> DirectImport<'_> {
+        let paths = file_paths.iter().chain([&target_path.to_string()]).chain(options.other_targets()).cloned().collect();
+        let control = Arc::new(ImportControl::default());
+        let mut id = 0;
+        if let Ok(mut state) = self.state.lock() {
+            state.next_id += 1;
+            id = state.next_id;
+            info!("Starting direct import {}", id);
+            state.direct.push(DirectJob { id, paths, control: control.clone() });
+        }
+        DirectImport { manager: self, id, control }
+    }
+
+    /// How many queued or running imports read from or write to the volume
+    /// mounted at `mount_point`
+    pub fn using(&self, mount_point: &str) -> usize {
+        let Ok(state) = self.state.lock() else {
+            return 0;
+        };
+        let on_volume = |path: &String| Path::new(path).starts_with(mount_point);
+        let direct = state.direct.iter().filter(|d| d.paths.iter().any(on_volume)).count();
+        let queued = state
+            .jobs
+            .iter()
+            .filter(|j| matches!(j.state, JobState::Queued | JobState::Running))
+            .filter(|j| j.file_paths.iter().chain([&j.target_path]).chain(j.options.other_targets()).any(on_volume))
+            .count();
+        direct + queued
+    }
+
+    /// Cancel queued jobs and stop running ones, direct imports included,
+    /// after their current file
+    fn cancel_all(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            for direct in &state.direct {
+                direct.control.cancel();
+            }
+            for job in state.jobs.iter_mut() {
+                match job.state {
+                    JobState::Queued => job.state = JobState::Cancelled,
+                    JobState::Running => job.control.cancel(),
+                    JobState::Completed | JobState::Failed | JobState::Cancelled => {}
+                }
+            }
+        }
+        self.changed.notify_all();
+    }
+
+    /// Block until no import is queued or running
+    fn wait_idle(&self) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        while state.pending() > 0 {
+            match self.changed.wait(state) {
+                Ok(next) => state = next,
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+fn run_job(app: &tauri::AppHandle, job: QueuedJob) {
+    info!("Starting import job {}", job.id);
+    let _ = app.emit("job-started", job.id);
+
+    let journal = app.state::<UndoJournal>();
+    let started = Instant::now();
+    let reporter = ProgressReporter::new(app, Some(&*job.progress)).for_job(job.id);
+    let result = import::run_import(&job.file_paths, &job.target_path, job.options, &reporter, &job.control, &journal)
+        .map_err(|e| e.to_string());
+
+    if let Err(e) = &result {
+        error!("Import job {} failed: {}", job.id, e);
+    }
+    notify::job_finished(app, &result, started.elapsed());
+    app.state::<JobManager>().finish(job.id, result);
+    let _ = app.emit("job-finished", job.id);
+}
+
+/// Whether closing the window (or quitting) has to wait for imports. The
+/// first time, the UI gets `close-blocked` with the number pending to ask
+/// whether to finish them in the background or cancel them.
+pub fn intercept_close(app: &tauri::AppHandle) -> bool {
+    let jobs = app.state::<JobManager>();
+    let Ok(state) = jobs.state.lock() else {
+        return false;
+    };
+    let pending = state.pending();
+    if pending == 0 || state.quitting {
+        return pending > 0;
+    }
+    drop(state);
+    info!("Close requested with {} imports pending", pending);
+    let _ = app.emit("close-blocked", pending);
+    true
+}
+
+/// Start the thread that hands queued jobs to workers as their cards free up
+pub fn start(app: tauri::AppHandle) {
+    thread::spawn(move || {
+        let jobs = app.state::<JobManager>();
+        while let Some(job) = jobs.next_queued() {
+            let app = app.clone();
+            thread::spawn(move || run_job(&app, job));
+        }
+    });
+}
+
+/// Queue an import and return its job id without waiting for it
+#[tauri::command]
+pub fn start_import_job(
+    file_paths: Vec<String>,
+    target_path: String,
+    options: Option<ImportOptions>,
+    jobs: tauri::State<'_, JobManager>,
+) -> Result<u64, CamPorterError> {
+    if file_paths.is_empty() {
+        return Err(CamPorterError::invalid_input("No files selected"));
+    }
+    Ok(jobs.enqueue(remap::translate_all(&file_paths), target_path, options.unwrap_or_default())?)
+}
+
+#[tauri::command]
+pub fn get_job_status(job_id: u64, jobs: tauri::State<'_, JobManager>) -> Result<JobStatus, CamPorterError> {
+    Ok(jobs.status(job_id)?)
+}
+
+#[tauri::command]
+pub fn list_jobs(jobs: tauri::State<'_, JobManager>) -> Result<Vec<JobStatus>, CamPorterError> {
+    Ok(jobs.list()?)
+}
+
+/// Hide the window and quit once every import has finished, after cancelling
+/// them first when `cancel` is set
+#[tauri::command]
+pub fn close_when_idle(cancel: bool, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    let jobs = app.state::<JobManager>();
+    jobs.state.lock().map_err(|e| e.to_string())?.quitting = true;
+    if cancel {
+        info!("Cancelling imports to quit");
+        jobs.cancel_all();
+    }
+    for window in app.webview_windows().values() {
+        let _ = window.hide();
+    }
+    thread::spawn(move || {
+        app.state::<JobManager>().wait_idle();
+        info!("Imports done, quitting");
+        app.exit(0);
+    });
+    Ok(())
+}
+
+/// Cancel a queued job, or stop a running job or direct import after its
+/// current file
+#[tauri::command]
+pub fn cancel_job(job_id: u64, jobs: tauri::State<'_, JobManager>) -> Result<(), CamPorterError> {
+    Ok(jobs.cancel(job_id)?)
+}
+
+/// Send the remaining files of the direct import paused for lack of space to
+/// another destination
+#[tauri::command]
+pub fn redirect_paused_import(destination: String, jobs: tauri::State<'_, JobManager>) -> Result<(), CamPorterError> {
+    Ok(jobs.paused_direct()?.redirect(destination)?)
+}
+
+/// Send the remaining files of a job paused for lack of space to another
+/// destination; direct imports are reached by their id too
+#[tauri::command]
+pub fn redirect_paused_job(job_id: u64, destination: String, jobs: tauri::State<'_, JobManager>) -> Result<(), CamPorterError> {
+    Ok(jobs.control(job_id)?.redirect(destination)?)
+}
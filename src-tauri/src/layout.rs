@@ -0,0 +1,175 @@
+// Recognising how a card is laid out from the folder names cameras write,
+// so scans can go straight to where the media lives. Vendors and layouts are
+// reported as ids rather than display names, for the UI to translate.
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+use crate::error::CamPorterError;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Vendor {
+    Nikon,
+    Canon,
+    Fujifilm,
+    Olympus,
+    Gopro,
+    Dji,
+    Sony,
+    Unknown,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutType {
+    /// Everything under DCIM in DCF-numbered folders (100CANON, 101_FUJI, ...)
+    Dcf,
+    /// DCF stills plus XAVC clips under PRIVATE/M4ROOT, as on Sony cameras
+    DcfWithXavc,
+    /// DCF stills plus AVCHD streams under PRIVATE/AVCHD
+    DcfWithAvchd,
+    /// No DCIM folder; the whole card has to be scanned
+    Unstructured,
+}
+
+#[derive(Serialize, Clone)]
+pub struct CardLayout {
+    pub vendor: Vendor,
+    pub layout: LayoutType,
+    /// Folders holding the card's media, most important first
+    pub scan_roots: Vec<String>,
+}
+
+/// The five characters after a DCF folder's number, matched as suffixes
+/// (100NIKON) or prefixes (100EOS5D, 100ND750)
+const DCF_SUFFIXES: &[(&str, Vendor)] = &[
+    ("NIKON", Vendor::Nikon),
+    ("CANON", Vendor::Canon),
+    ("_FUJI", Vendor::Fujifilm),
+    ("OLYMP", Vendor::Olympus),
+    ("OMSYS", Vendor::Olympus),
+    ("GOPRO", Vendor::Gopro),
+    ("MEDIA", Vendor::Dji),
+    ("MSDCF", Vendor::Sony),
+];
+const DCF_PREFIXES: &[(&str, Vendor)] = &[
+    ("EOS", Vendor::Canon),
+    ("ND", Vendor::Nikon),
+    ("NZ", Vendor::Nikon),
+    ("NCD", Vendor::Nikon),
+];
+
+/// Video folders outside DCIM, relative to the card root
+const XAVC_ROOT: &[&str] = &["PRIVATE", "M4ROOT", "CLIP"];
+const AVCHD_ROOT: &[&str] = &["PRIVATE", "AVCHD", "BDMV", "STREAM"];
+/// MP4 clips on older Sony cameras
+const MP_ROOT: &[&str] = &["MP_ROOT"];
+/// DPOF print orders (AUTPRINT.MRK), which only cameras write
+const MISC: &str = "MISC";
+
+/// The vendor a DCIM subfolder name points to; None if it isn't one a
+/// camera would have written
+fn vendor_of(folder: &str) -> Option<Vendor> {
+    let folder = folder.to_uppercase();
+    // Newer DJI drones write DCIM/DJI_001 instead of a DCF name
+    if folder.starts_with("DJI_") {
+        return Some(Vendor::Dji);
+    }
+
+    let (number, tag) = folder.split_at_checked(3)?;
+    if !number.bytes().all(|b| b.is_ascii_digit()) || tag.len() != 5 {
+        return None;
+    }
+    if let Some((_, vendor)) = DCF_SUFFIXES.iter().find(|(suffix, _)| tag == *suffix) {
+        return Some(*vendor);
+    }
+    if let Some((_, vendor)) = DCF_PREFIXES.iter().find(|(prefix, _)| tag.starts_with(prefix)) {
+        return Some(*vendor);
+    }
+    Some(Vendor::Unknown)
+}
+
+/// A child folder by name, ignoring case; FAT and exFAT keep whatever case
+/// the camera used
+fn child(dir: &Path, name: &str) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .find(|e| e.file_name().to_string_lossy().eq_ignore_ascii_case(name) && e.path().is_dir())
+        .map(|e| e.path())
+}
+
+fn descend(root: &Path, components: &[&str]) -> Option<PathBuf> {
+    components.iter().try_fold(root.to_path_buf(), |dir, name| child(&dir, name))
+}
+
+/// Work out the vendor and layout of the card mounted at `root`
+pub fn detect(root: &Path) -> CardLayout {
+    let dcim = child(root, "DCIM");
+
+    // Go by whichever vendor most DCIM folders point to; a card moved between
+    // cameras can hold several
+    let mut votes: HashMap<Vendor, usize> = HashMap::new();
+    if let Some(entries) = dcim.as_ref().and_then(|dcim| fs::read_dir(dcim).ok()) {
+        for entry in entries.filter_map(|e| e.ok()).filter(|e| e.path().is_dir()) {
+            if let Some(vendor) = vendor_of(&entry.file_name().to_string_lossy()) {
+                *votes.entry(vendor).or_default() += 1;
+            }
+        }
+    }
+    let mut vendor = votes
+        .iter()
+        .filter(|(v, _)| **v != Vendor::Unknown)
+        .max_by_key(|(_, count)| **count)
+        .map(|(v, _)| *v)
+        .unwrap_or(Vendor::Unknown);
+
+    let xavc = descend(root, XAVC_ROOT);
+    let avchd = descend(root, AVCHD_ROOT);
+    let mp_root = descend(root, MP_ROOT);
+    if vendor == Vendor::Unknown && (xavc.is_some() || mp_root.is_some()) {
+        vendor = Vendor::Sony;
+    }
+
+    let layout = match (&dcim, &xavc, &avchd) {
+        (None, None, None) => LayoutType::Unstructured,
+        (_, Some(_), _) => LayoutType::DcfWithXavc,
+        (_, None, Some(_)) => LayoutType::DcfWithAvchd,
+        (Some(_), None, None) => LayoutType::Dcf,
+    };
+
+    let scan_roots: Vec<String> = match layout {
+        LayoutType::Unstructured => vec![root.to_path_buf()],
+        _ => [dcim, xavc, avchd, mp_root].into_iter().flatten().collect(),
+    }
+    .iter()
+    .map(|p| p.to_string_lossy().to_string())
+    .collect();
+
+    CardLayout { vendor, layout, scan_roots }
+}
+
+/// Whether the card at `root` came out of a camera, going by the folders
+/// cameras write, and the vendor they point to if one does. A USB stick with
+/// a copied DCIM folder passes too, but random sticks are kept apart.
+pub fn camera_card(root: &Path) -> (bool, Option<Vendor>) {
+    let card = detect(root);
+    let print_order = child(root, MISC).and_then(|misc| fs::read_dir(misc).ok()).is_some_and(|entries| {
+        entries.filter_map(|e| e.ok()).any(|e| e.file_name().to_string_lossy().to_uppercase().ends_with(".MRK"))
+    });
+    let is_camera_card = card.layout != LayoutType::Unstructured || card.vendor != Vendor::Unknown || print_order;
+    (is_camera_card, Some(card.vendor).filter(|v| *v != Vendor::Unknown))
+}
+
+#[tauri::command]
+pub fn detect_card_layout(drive_path: String) -> Result<CardLayout, CamPorterError> {
+    let root = Path::new(&drive_path);
+    if !root.is_dir() {
+        return Err(CamPorterError::not_found("Drive path does not exist"));
+    }
+    Ok(detect(root))
+}
@@ -1,6 +1,5 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-use sysinfo::Disks;
-use serde::Serialize;
+use sysinfo::{DiskKind, Disks};
 use std::process::Command;
 use std::fs;
 use std::path::Path;
@@ -8,260 +7,459 @@ use walkdir::WalkDir;
 use tauri::{Emitter, Manager};
 use base64::{engine::general_purpose, Engine as _};
 use image::{ImageFormat};
-use tempfile::tempdir;
-use which::which;
-use tauri_plugin_log;
 use std::io::Read;
 
 #[cfg(not(target_os = "windows"))]
 use thumbnails::Thumbnailer;
 
+mod alerts;
+mod audio;
+mod auditlog;
+mod benchmark;
+mod catalog;
+mod cloud;
+mod config;
+mod devices;
+#[cfg(target_os = "macos")]
+mod diskarb;
+pub mod dto;
+mod drivelists;
+mod eject;
+mod error;
+mod estimate;
+mod fastcopy;
+mod ffmpeg;
+mod format;
+mod ftp;
+#[cfg(feature = "gphoto2")]
+mod gphoto;
+#[cfg(target_os = "linux")]
+mod gvfs;
+mod health;
+mod hooks;
+mod hotplug;
+mod humanize;
+mod import;
+mod ios;
+mod jobs;
+mod layout;
+mod libraries;
+mod lightroom;
+mod longpath;
+mod metadata;
+mod metrics;
+mod mhl;
+mod mtp;
+mod normalize;
+mod notify;
+mod offload;
+mod partitions;
+mod power;
+mod profiles;
+mod protected;
+mod proxy;
+mod raw;
+mod remap;
+mod rename;
+mod replaced;
+mod resume;
+mod routing;
+mod scan;
+mod scheduler;
+mod selftest;
+mod sidecars;
+mod sniff;
+mod tiles;
+mod timelapse;
+mod trim;
+#[cfg(target_os = "linux")]
+mod udisks;
+mod ui_state;
+mod undo;
+mod upload;
+mod verify;
+mod volume;
+mod watchdog;
+mod workflow;
+mod workspace;
+
+use dto::{Confidence, DestinationLimits, MediaFile, RemovableDrive, SelectionStats};
+use error::CamPorterError;
 use log::{info, warn, error};
 
-#[derive(Serialize)]
-struct RemovableDrive {
-    name: String,
-    mount_point: String,
-    device_id: String,
+const MEDIA_EXTENSIONS: [&str; 11] = ["mp4", "jpg", "jpeg", "png", "mov", "heic", "mp3", "wav", "avi", "mkv", "gif"];
+const IMAGE_EXTENSIONS: [&str; 5] = ["jpg", "jpeg", "png", "heic", "gif"];
+const VIDEO_EXTENSIONS: [&str; 4] = ["mp4", "mov", "avi", "mkv"];
+const AUDIO_EXTENSIONS: [&str; 2] = ["mp3", "wav"];
+
+/// Windows knows which drive letters are removable media, where a disk's
+/// name is only its label
+#[cfg(target_os = "windows")]
+fn looks_removable(disk: &sysinfo::Disk) -> bool {
+    volume::is_removable_media(disk.mount_point())
 }
 
-#[derive(Serialize)]
-struct MediaFile {
-    name: String,
-    path: String,
-    size: u64,
-    modified: u64, // Unix timestamp
-    extension: String,
-    is_image: bool,
-    is_video: bool,
+#[cfg(not(target_os = "windows"))]
+fn looks_removable(disk: &sysinfo::Disk) -> bool {
+    let mount_point = disk.mount_point().to_string_lossy();
+    let name = disk.name().to_string_lossy();
+    
+    // On Linux, removable drives often mount under /media, /mnt, or /run/media
+    // Also check for common USB drive characteristics
+    disk.is_removable() ||
+    mount_point.starts_with("/media/") ||
+    mount_point.starts_with("/mnt/") ||
+    mount_point.starts_with("/run/media/") ||
+    name.contains("mmcblk") ||  // SD cards
+    name.contains("usb") || // USB drives
+    name.contains("removable")
+}
+
+/// Whether a disk is listed, and how sure that it's removable: the bus the
+/// OS reports decides where it's known, so internal disks mounted under
+/// /mnt are left out, and the disk's name and mount point only when it isn't
+fn removability(disk: &sysinfo::Disk, bus: Option<volume::Bus>) -> Option<Confidence> {
+    match bus {
+        Some(bus) if bus.is_external() => Some(Confidence::Definite),
+        Some(_) => None,
+        None => looks_removable(disk).then_some(Confidence::Maybe),
+    }
 }
 
 #[tauri::command]
-fn list_removable_drives() -> Vec<RemovableDrive> {
+fn list_removable_drives(app: tauri::AppHandle) -> Vec<RemovableDrive> {
+    visible_drives(&app)
+}
+
+/// Removable drives as the UI shows them: without the volumes the user hid,
+/// and with each card's profile and whether it was the last one used
+fn visible_drives(app: &tauri::AppHandle) -> Vec<RemovableDrive> {
+    let drives = drivelists::DriveLists::load(app).apply(profiles::attach(app, removable_drives()));
+    ui_state::mark_last_used(app, drives)
+}
+
+/// Removable drives as they are now, without their profiles, noting where
+/// each volume is mounted for `remap`
+fn removable_drives() -> Vec<RemovableDrive> {
     let disks = Disks::new_with_refreshed_list();
     
-    disks.iter()
-        .filter(|disk| {
-            let mount_point = disk.mount_point().to_string_lossy();
-            let name = disk.name().to_string_lossy();
-            
-            // On Linux, removable drives often mount under /media, /mnt, or /run/media
-            // Also check for common USB drive characteristics
-            disk.is_removable() ||
-            mount_point.starts_with("/media/") ||
-            mount_point.starts_with("/mnt/") ||
-            mount_point.starts_with("/run/media/") ||
-            name.contains("sd") ||  // SD cards
-            name.contains("usb") || // USB drives
-            name.contains("removable")
+    let drives: Vec<RemovableDrive> = disks.iter()
+        .filter_map(|disk| {
+            let id = volume::identify(&disk.name().to_string_lossy(), disk.mount_point(), disk.total_space());
+            let confidence = removability(disk, id.bus)?;
+            let (is_camera_card, camera_hint) = layout::camera_card(disk.mount_point());
+            let is_write_protected = volume::is_write_protected(&disk.name().to_string_lossy(), disk.mount_point());
+            Some(RemovableDrive {
+                name: format!("{} ({})",
+                    disk.name().to_string_lossy(),
+                    if disk.is_removable() { "removable" } else { "mounted" }
+                ),
+                mount_point: disk.mount_point().to_string_lossy().to_string(),
+                device_id: disk.name().to_string_lossy().to_string(),
+                mounted_by: protected::mounted_by_other_user(&disk.mount_point().to_string_lossy()),
+                total_bytes: disk.total_space(),
+                available_bytes: disk.available_space(),
+                file_system: disk.file_system().to_string_lossy().to_string(),
+                is_read_only: is_write_protected || volume::is_read_only(disk.mount_point()),
+                is_write_protected,
+                label: id.label,
+                serial: id.serial,
+                bus: id.bus,
+                physical_device: id.disk,
+                confidence,
+                is_camera_card,
+                camera_hint,
+                profile: None,
+                auto_import_allowed: true,
+                last_used: false,
+            })
         })
-        .map(|disk| RemovableDrive {
-            name: format!("{} ({})",
-                disk.name().to_string_lossy(),
-                if disk.is_removable() { "removable" } else { "mounted" }
-            ),
-            mount_point: disk.mount_point().to_string_lossy().to_string(),
-            device_id: disk.name().to_string_lossy().to_string(),
+        .chain(camera_mounts())
+        .collect();
+    let drives = group_by_card(drives);
+    remap::observe(&drives);
+    drives
+}
+
+/// Put the partitions of each physical card next to each other, where the
+/// first of them was
+fn group_by_card(drives: Vec<RemovableDrive>) -> Vec<RemovableDrive> {
+    let first_of_card: Vec<usize> = drives
+        .iter()
+        .enumerate()
+        .map(|(index, drive)| match &drive.physical_device {
+            Some(device) => drives.iter().position(|d| d.physical_device.as_ref() == Some(device)).unwrap_or(index),
+            None => index,
         })
-        .collect()
+        .collect();
+    let mut drives: Vec<(usize, RemovableDrive)> = first_of_card.into_iter().zip(drives).collect();
+    drives.sort_by_key(|(first, _)| *first);
+    drives.into_iter().map(|(_, drive)| drive).collect()
 }
 
-#[tauri::command]
-fn list_media_files(drive_path: String) -> Result<Vec<MediaFile>, String> {
-    let src = Path::new(&drive_path);
-    
-    if !src.exists() {
-        return Err("Drive path does not exist".to_string());
+/// Cameras mounted as folders rather than disks, which sysinfo doesn't list
+#[cfg(target_os = "linux")]
+fn camera_mounts() -> Vec<RemovableDrive> {
+    gvfs::mounts()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn camera_mounts() -> Vec<RemovableDrive> {
+    Vec::new()
+}
+
+/// Identify a card independently of where it's mounted or which reader it's
+/// in: its volume serial, as profiles and drive lists use
+fn card_fingerprint(mount_point: &str) -> Option<String> {
+    let disks = Disks::new_with_refreshed_list();
+    let disk = disks.iter().find(|disk| disk.mount_point() == Path::new(mount_point))?;
+    Some(disk_fingerprint(disk))
+}
+
+/// The volume serial, or for volumes without one a hash of the device name,
+/// capacity and file system
+fn disk_fingerprint(disk: &sysinfo::Disk) -> String {
+    let name = disk.name().to_string_lossy();
+    if let Some(serial) = volume::identify(&name, disk.mount_point(), disk.total_space()).serial {
+        return serial;
     }
-    
-    let media_extensions = ["mp4", "jpg", "jpeg", "png", "mov", "heic", "mp3", "wav", "avi", "mkv", "gif"];
-    let image_extensions = ["jpg", "jpeg", "png", "heic", "gif"];
-    let video_extensions = ["mp4", "mov", "avi", "mkv"];
-    
-    let mut media_files = Vec::new();
-    
-    for entry in WalkDir::new(src).into_iter().filter_map(|e| e.ok()) {
-        if entry.file_type().is_file() {
-            if let Some(ext) = entry.path().extension().and_then(|e| e.to_str()) {
-                let ext_lower = ext.to_lowercase();
-                if media_extensions.contains(&ext_lower.as_str()) {
-                    if let Ok(metadata) = entry.metadata() {
-                        let modified = metadata
-                            .modified()
-                            .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
-                            .duration_since(std::time::SystemTime::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs();
-                        
-                        let file_name = entry.file_name().to_string_lossy().to_string();
-                        let is_image = image_extensions.contains(&ext_lower.as_str());
-                        let is_video = video_extensions.contains(&ext_lower.as_str());
-                        
-                        media_files.push(MediaFile {
-                            name: file_name,
-                            path: entry.path().to_string_lossy().to_string(),
-                            size: metadata.len(),
-                            modified,
-                            extension: ext_lower,
-                            is_image,
-                            is_video,
-                        });
-                    }
-                }
-            }
-        }
+    let identity = format!(
+        "{}|{}|{}",
+        name,
+        disk.total_space(),
+        disk.file_system().to_string_lossy()
+    );
+    format!("{:016x}", xxhash_rust::xxh3::xxh3_64(identity.as_bytes()))
+}
+
+/// Start listing the media on a drive; the files come in `scan-result`
+/// events under the returned scan id
+#[tauri::command]
+fn list_media_files(drive_path: String, new_only: Option<bool>, app: tauri::AppHandle) -> Result<u64, CamPorterError> {
+    let drive_path = scan::scan_root(&drive_path)?;
+    Ok(scan::start(&app, vec![drive_path], new_only.unwrap_or(false)))
+}
+
+/// List the media on every partition of a physical card together, for
+/// cameras and phones that split one card into several volumes, as one scan
+#[tauri::command]
+fn list_card_media(physical_device: String, new_only: Option<bool>, app: tauri::AppHandle) -> Result<u64, CamPorterError> {
+    let partitions = visible_drives(&app)
+        .into_iter()
+        .filter(|d| d.physical_device.as_ref() == Some(&physical_device))
+        .map(|d| scan::scan_root(&d.mount_point))
+        .collect::<Result<Vec<String>, CamPorterError>>()?;
+    if partitions.is_empty() {
+        return Err(CamPorterError::not_found(format!("No partitions of {} are mounted", physical_device)));
     }
-    
-    // Sort by modification time (newest first)
-    media_files.sort_by(|a, b| b.modified.cmp(&a.modified));
-    
-    Ok(media_files)
+    Ok(scan::start(&app, partitions, new_only.unwrap_or(false)))
 }
 
+/// List the media in any folder, such as a phone-synced folder or a network
+/// share, through the same scanner used for cards
 #[tauri::command]
-async fn unmount_drive(mount_point: String) -> Result<(), String> {
-    let mut command = if cfg!(target_os = "linux") {
-        let mut cmd = Command::new("udisksctl");
-        cmd.args(["unmount", "-p", &mount_point]);
-        cmd
-    } else if cfg!(target_os = "macos") {
-        let mut cmd = Command::new("diskutil");
-        cmd.args(["unmount", &mount_point]);
-        cmd
-    } else if cfg!(target_os = "windows") {
-        let mut cmd = Command::new("powershell");
-        cmd.args([
-            "-Command",
-            &format!("(New-Object -comObject Shell.Application).Namespace(17).ParseName('{}').InvokeVerb('Eject')", mount_point),
-        ]);
-        cmd
-    } else {
-        return Err("Unsupported platform".to_string());
-    };
+fn browse_folder_media(path: String, app: tauri::AppHandle) -> Result<u64, CamPorterError> {
+    if !Path::new(&path).is_dir() {
+        return Err(CamPorterError::invalid_input("Not a folder"));
+    }
+    list_media_files(path, None, app)
+}
 
-    let output = command.output().map_err(|e| e.to_string())?;
+/// Photos, RAW included, by lowercase extension
+fn is_image_extension(ext: &str) -> bool {
+    IMAGE_EXTENSIONS.contains(&ext) || raw::RAW_EXTENSIONS.contains(&ext)
+}
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        Err(format!("Unmount failed: {} {}", stderr, stdout))
+fn is_video_extension(ext: &str) -> bool {
+    VIDEO_EXTENSIONS.contains(&ext)
+}
+
+fn is_audio_extension(ext: &str) -> bool {
+    AUDIO_EXTENSIONS.contains(&ext)
+}
+
+/// Basic listing entry for a walked file, or None if it isn't media
+fn media_file(entry: &walkdir::DirEntry) -> Option<MediaFile> {
+    if !entry.file_type().is_file() {
+        return None;
     }
+    let ext_lower = entry.path().extension().and_then(|e| e.to_str())?.to_lowercase();
+    if !MEDIA_EXTENSIONS.contains(&ext_lower.as_str()) && !raw::RAW_EXTENSIONS.contains(&ext_lower.as_str()) {
+        return None;
+    }
+    let metadata = entry.metadata().ok()?;
+    let modified = metadata
+        .modified()
+        .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    
+    let is_image = is_image_extension(&ext_lower);
+    let is_video = is_video_extension(&ext_lower);
+    
+    Some(MediaFile {
+        name: entry.file_name().to_string_lossy().to_string(),
+        path: longpath::display(entry.path()),
+        size: metadata.len(),
+        modified,
+        extension: ext_lower,
+        is_image,
+        is_video,
+        captured: None,
+        duration: None,
+    })
 }
 
+/// Summarize an arbitrary selection for the UI footer
 #[tauri::command]
-async fn import_selected_files(
-    file_paths: Vec<String>,
-    target_path: String,
-    window: tauri::Window,
-) -> Result<(), String> {
-    let dest = Path::new(&target_path);
-    
-    // Create target directory if it doesn't exist
-    fs::create_dir_all(dest).map_err(|e| e.to_string())?;
-    
-    // Calculate total size of all files
-    let mut total_size: u64 = 0;
-    let mut file_sizes: Vec<u64> = Vec::new();
-    
-    for file_path in &file_paths {
-        let src_file = Path::new(file_path);
-        if src_file.exists() {
-            if let Ok(metadata) = src_file.metadata() {
-                let size = metadata.len();
-                total_size += size;
-                file_sizes.push(size);
-            } else {
-                file_sizes.push(0);
+fn get_selection_stats(paths: Vec<String>) -> SelectionStats {
+    let mut stats = SelectionStats::default();
+    
+    for path in &paths {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                stats.missing += 1;
+                continue;
             }
-        } else {
-            file_sizes.push(0);
-        }
-    }
-    
-    let mut copied_size: u64 = 0;
-    
-    for (i, file_path) in file_paths.iter().enumerate() {
-        let src_file = Path::new(file_path);
-        let file_size = file_sizes[i];
-        
-        if !src_file.exists() {
-            window.emit("import-progress", &format!("Skipped: {} (file not found)", file_path)).map_err(|e| e.to_string())?;
-            continue;
-        }
+        };
+        let size = metadata.len();
         
-        let file_name = src_file.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
-        let target_file = dest.join(file_name);
-        
-        // Skip if file already exists
-        if target_file.exists() {
-            window.emit("import-progress", &format!("Skipped: {} (already exists)", file_name)).map_err(|e| e.to_string())?;
-            copied_size += file_size; // Count as "copied" for progress calculation
-            window.emit("import-progress", &format!("PROGRESS_BYTES:{}:{}", copied_size, total_size)).map_err(|e| e.to_string())?;
-            continue;
-        }
+        let ext = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        let type_stats = if raw::RAW_EXTENSIONS.contains(&ext.as_str()) {
+            &mut stats.raw
+        } else if IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+            &mut stats.images
+        } else if VIDEO_EXTENSIONS.contains(&ext.as_str()) {
+            &mut stats.videos
+        } else if is_audio_extension(&ext) {
+            &mut stats.audio
+        } else {
+            &mut stats.other
+        };
+        type_stats.count += 1;
+        type_stats.bytes += size;
         
-        window.emit("import-progress", &format!("Copying: {}", file_name)).map_err(|e| e.to_string())?;
+        stats.count += 1;
+        stats.total_bytes += size;
         
-        // Copy file with progress tracking for large files
-        match copy_file_with_progress(src_file, &target_file, file_size, copied_size, total_size, &window) {
-            Ok(_) => {
-                copied_size += file_size;
-                window.emit("import-progress", &format!("Copied: {}", file_name)).map_err(|e| e.to_string())?;
-            }
-            Err(e) => {
-                window.emit("import-progress", &format!("Failed to copy {}: {}", file_name, e)).map_err(|e| e.to_string())?;
-            }
+        if let Ok(modified) = metadata.modified() {
+            let modified = modified
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            stats.earliest = Some(stats.earliest.map_or(modified, |e| e.min(modified)));
+            stats.latest = Some(stats.latest.map_or(modified, |l| l.max(modified)));
         }
-        
-        // Report final progress for this file
-        window.emit("import-progress", &format!("PROGRESS_BYTES:{}:{}", copied_size, total_size)).map_err(|e| e.to_string())?;
     }
     
-    Ok(())
+    stats
+}
+
+/// Unmount a card and, where the platform allows, power it down, reporting
+/// whether it's actually safe to pull out. A card an import is using fails
+/// with `busy`, as does one with files open on it unless `force` is set.
+#[tauri::command]
+async fn unmount_drive(
+    mount_point: String,
+    force: Option<bool>,
+    jobs: tauri::State<'_, jobs::JobManager>,
+) -> Result<eject::EjectStatus, CamPorterError> {
+    match jobs.using(&mount_point) {
+        0 => eject::eject(&mount_point, force.unwrap_or(false)),
+        1 => Err(CamPorterError::busy("An import is still reading from or writing to this drive")),
+        n => Err(CamPorterError::busy(format!("{} imports are still reading from or writing to this drive", n))),
+    }
 }
 
-fn copy_file_with_progress(
-    src: &Path,
-    dest: &Path,
-    file_size: u64,
-    initial_copied: u64,
-    total_size: u64,
-    window: &tauri::Window,
-) -> Result<u64, std::io::Error> {
-    use std::io::{Read, Write};
+/// Mount a removable partition that wasn't mounted automatically, such as
+/// "/dev/sdb1", and return where it was mounted
+#[tauri::command]
+async fn mount_drive(device_id: String) -> Result<String, CamPorterError> {
+    partitions::mount(&device_id)
+}
+
+#[tauri::command]
+async fn import_selected_files(
+    file_paths: Vec<String>,
+    target_path: String,
+    options: Option<import::ImportOptions>,
+    window: tauri::Window,
+    jobs: tauri::State<'_, jobs::JobManager>,
+    journal: tauri::State<'_, undo::UndoJournal>,
+) -> Result<import::ImportReport, CamPorterError> {
+    let options = options.unwrap_or_default();
+    let file_paths = remap::translate_all(&file_paths);
+    // Keeps the app from quitting mid-copy, or the card being ejected
+    let pending = jobs.track_direct(&file_paths, &target_path, &options);
+    let progress = pending.reporter(window.app_handle());
+    import::run_import(&file_paths, &target_path, options, &progress, pending.control(), &journal)
+}
+
+struct VolumeInfo {
+    file_system: String,
+    available_space: u64,
+    is_hdd: bool,
+}
+
+/// Details of the volume holding `path`, found by longest mount point match
+fn volume_info(path: &Path) -> Option<VolumeInfo> {
+    let path = longpath::strip(&fs::canonicalize(path).ok()?);
+    let disks = Disks::new_with_refreshed_list();
     
-    let mut src_file = fs::File::open(src)?;
-    let mut dest_file = fs::File::create(dest)?;
+    disks.iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| VolumeInfo {
+            file_system: disk.file_system().to_string_lossy().to_string(),
+            available_space: disk.available_space(),
+            is_hdd: disk.kind() == DiskKind::HDD,
+        })
+}
+
+fn available_space(path: &Path) -> Option<u64> {
+    volume_info(path).map(|v| v.available_space)
+}
+
+/// Largest single file a file system can store, if it has a notable limit
+fn max_file_size(file_system: &str) -> Option<u64> {
+    match file_system.to_lowercase().as_str() {
+        // FAT32 as reported by Linux, macOS and Windows respectively
+        "vfat" | "msdos" | "fat32" | "fat" => Some(u32::MAX as u64),
+        "fat16" => Some(2 * 1024 * 1024 * 1024 - 1),
+        _ => None,
+    }
+}
+
+/// Dry-run check for files that the destination file system can't hold
+#[tauri::command]
+fn check_destination_file_limits(file_paths: Vec<String>, target_path: String) -> Result<DestinationLimits, CamPorterError> {
+    let dest = Path::new(&target_path);
+    if !dest.exists() {
+        return Err(CamPorterError::not_found("Destination folder does not exist"));
+    }
     
-    let mut buffer = [0; 64 * 1024]; // 64KB buffer
-    let mut copied_this_file = 0u64;
-    let mut last_progress_report = 0u64;
+    let file_system = volume_info(dest).map(|v| v.file_system).unwrap_or_default();
+    let max_size = max_file_size(&file_system);
     
-    loop {
-        let bytes_read = src_file.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
-        
-        dest_file.write_all(&buffer[..bytes_read])?;
-        copied_this_file += bytes_read as u64;
-        
-        // Report progress every 1MB or 10% of file, whichever is smaller
-        let progress_interval = std::cmp::min(1024 * 1024, file_size / 10).max(64 * 1024);
-        
-        if copied_this_file - last_progress_report >= progress_interval || copied_this_file == file_size {
-            let total_copied = initial_copied + copied_this_file;
-            if let Err(_) = window.emit("import-progress", &format!("PROGRESS_BYTES:{}:{}", total_copied, total_size)) {
-                // Continue even if progress reporting fails
-            }
-            last_progress_report = copied_this_file;
-        }
-    }
+    let oversized_files = match max_size {
+        Some(max) => file_paths
+            .into_iter()
+            .filter(|p| fs::metadata(p).map(|m| m.len() > max).unwrap_or(false))
+            .collect(),
+        None => Vec::new(),
+    };
     
-    dest_file.sync_all()?;
-    Ok(copied_this_file)
+    Ok(DestinationLimits {
+        file_system,
+        max_file_size: max_size,
+        oversized_files,
+    })
 }
 
 #[tauri::command]
@@ -269,7 +467,7 @@ async fn import_media(
     source_path: String,
     target_path: String,
     window: tauri::Window,
-) -> Result<(), String> {
+) -> Result<(), CamPorterError> {
     let src = Path::new(&source_path);
     let dest = Path::new(&target_path);
     
@@ -313,14 +511,14 @@ async fn import_media(
 
 /// Check if FFmpeg is installed on the system
 fn is_ffmpeg_available() -> bool {
-    // Try to find ffmpeg in PATH
+    // Try the bundled, managed and PATH copies of ffmpeg
     info!("Checking if FFmpeg is available...");
-    let result = which("ffmpeg");
+    let result = ffmpeg::ffmpeg_path();
     match &result {
-        Ok(path) => info!("FFmpeg found at: {}", path.display()),
-        Err(e) => warn!("FFmpeg not found: {}", e),
+        Some(path) => info!("FFmpeg found at: {}", path.display()),
+        None => warn!("FFmpeg not found"),
     }
-    result.is_ok()
+    result.is_some()
 }
 
 /// Generate a video thumbnail using FFmpeg
@@ -328,8 +526,8 @@ fn generate_video_thumbnail(video_path: &Path) -> Result<String, String> {
     info!("Generating thumbnail for video: {}", video_path.display());
     
     // Create a temporary directory for the thumbnail
-    let temp_dir = tempdir().map_err(|e| format!("Failed to create temp dir: {}", e))?;
-    let output_path = temp_dir.path().join("thumbnail.png");
+    let scratch = workspace::scratch()?;
+    let output_path = scratch.path().join("thumbnail.png");
     
     info!("Temp output path: {}", output_path.display());
     
@@ -340,13 +538,9 @@ fn generate_video_thumbnail(video_path: &Path) -> Result<String, String> {
     info!("Video absolute path: {}", video_absolute_path.display());
     
     // Build FFmpeg command to extract a frame from the video
-    let ffmpeg_cmd = if cfg!(target_os = "windows") {
-        "ffmpeg.exe"
-    } else {
-        "ffmpeg"
-    };
+    let ffmpeg_cmd = ffmpeg::ffmpeg_path().ok_or("FFmpeg not found")?;
     
-    info!("Using FFmpeg command: {}", ffmpeg_cmd);
+    info!("Using FFmpeg command: {}", ffmpeg_cmd.display());
     
     let mut command = Command::new(ffmpeg_cmd);
     
@@ -364,7 +558,8 @@ fn generate_video_thumbnail(video_path: &Path) -> Result<String, String> {
     info!("FFmpeg command: {}", cmd_str);
     
     // Execute FFmpeg command
-    let output = command.output().map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
+    // A damaged file can hang ffmpeg; the caller falls back to another thumbnail
+    let output = watchdog::output(&mut command, watchdog::THUMBNAIL_TIMEOUT)?;
     
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -399,14 +594,15 @@ fn generate_video_thumbnail(video_path: &Path) -> Result<String, String> {
 }
 
 #[tauri::command]
-fn get_file_thumbnail(file_path: String) -> Result<String, String> {
+fn get_file_thumbnail(file_path: String) -> Result<String, CamPorterError> {
+    let file_path = remap::translate(&file_path);
     info!("Getting thumbnail for file: {}", file_path);
     
     let path = Path::new(&file_path);
     
     if !path.exists() {
         error!("File does not exist: {}", file_path);
-        return Err("File does not exist".to_string());
+        return Err(CamPorterError::not_found("File does not exist"));
     }
     
     let is_video = match path.extension().and_then(|e| e.to_str()) {
@@ -430,93 +626,27 @@ fn get_file_thumbnail(file_path: String) -> Result<String, String> {
             match generate_video_thumbnail(path) {
                 Ok(data_url) => {
                     info!("Successfully generated thumbnail with FFmpeg");
-                    return Ok(data_url);
+                    Ok(data_url)
                 },
                 Err(e) => {
                     error!("Failed to generate thumbnail with FFmpeg: {}", e);
                     warn!("Falling back to alternative methods");
                     
                     // Fall back to platform-specific methods if FFmpeg fails
-                    #[cfg(not(target_os = "windows"))]
-                    {
-                        println!("Using Thumbnailer on non-Windows platform");
-                        let thumbnailer = Thumbnailer::new(250, 250);
-                        match thumbnailer.get(path) {
-                            Ok(img) => {
-                                println!("Thumbnailer succeeded, converting to base64");
-                                let mut buf = Vec::new();
-                                let mut cursor = std::io::Cursor::new(&mut buf);
-                                
-                                if let Err(e) = img.write_to(&mut cursor, ImageFormat::Png) {
-                                    println!("Failed to write thumbnail to buffer: {}", e);
-                                    return generate_fallback_thumbnail(path);
-                                }
-                                
-                                let res_base64 = general_purpose::STANDARD.encode(&buf);
-                                let mime_type = get_mime_type(path);
-                                
-                                println!("Successfully generated thumbnail with Thumbnailer");
-                                return Ok(format!("data:{};base64,{}", mime_type, res_base64));
-                            },
-                            Err(e) => {
-                                println!("Thumbnailer failed: {}", e);
-                                println!("Using fallback thumbnail");
-                                return generate_fallback_thumbnail(path);
-                            },
-                        }
-                    }
-                    
-                    #[cfg(target_os = "windows")]
-                    {
-                        warn!("On Windows, using fallback thumbnail");
-                        return generate_fallback_thumbnail(path);
-                    }
+                    Ok(generate_platform_thumbnail(path)?)
                 }
             }
         } else {
             warn!("FFmpeg is not available");
             
             // If FFmpeg is not available, fall back to platform-specific methods
-            #[cfg(not(target_os = "windows"))]
-            {
-                println!("Using Thumbnailer on non-Windows platform");
-                let thumbnailer = Thumbnailer::new(250, 250);
-                match thumbnailer.get(path) {
-                    Ok(img) => {
-                        println!("Thumbnailer succeeded, converting to base64");
-                        let mut buf = Vec::new();
-                        let mut cursor = std::io::Cursor::new(&mut buf);
-                        
-                        if let Err(e) = img.write_to(&mut cursor, ImageFormat::Png) {
-                            println!("Failed to write thumbnail to buffer: {}", e);
-                            return generate_fallback_thumbnail(path);
-                        }
-                        
-                        let res_base64 = general_purpose::STANDARD.encode(&buf);
-                        let mime_type = get_mime_type(path);
-                        
-                        println!("Successfully generated thumbnail with Thumbnailer");
-                        return Ok(format!("data:{};base64,{}", mime_type, res_base64));
-                    },
-                    Err(e) => {
-                        println!("Thumbnailer failed: {}", e);
-                        println!("Using fallback thumbnail");
-                        return generate_fallback_thumbnail(path);
-                    },
-                }
-            }
-            
-            #[cfg(target_os = "windows")]
-            {
-                warn!("On Windows, using fallback thumbnail");
-                return generate_fallback_thumbnail(path);
-            }
+            Ok(generate_platform_thumbnail(path)?)
         }
     } else {
         info!("File is an image, using image crate");
         
         // For images, use the image crate directly
-        match image::open(path) {
+        match raw::open_image(path) {
             Ok(img) => {
                 info!("Successfully opened image, creating thumbnail");
                 let thumbnail = img.thumbnail(250, 250);
@@ -526,24 +656,62 @@ fn get_file_thumbnail(file_path: String) -> Result<String, String> {
                 
                 if let Err(e) = thumbnail.write_to(&mut cursor, ImageFormat::Png) {
                     error!("Failed to write thumbnail to buffer: {}", e);
-                    return generate_fallback_thumbnail(path);
+                    return Ok(generate_fallback_thumbnail(path)?);
                 }
                 
                 let res_base64 = general_purpose::STANDARD.encode(&buf);
-                let mime_type = get_mime_type(path);
+                // RAW extensions have no image MIME type of their own
+                let mime_type = if raw::is_raw_file(path) { "image/png" } else { get_mime_type(path) };
                 
                 info!("Successfully generated thumbnail for image");
-                return Ok(format!("data:{};base64,{}", mime_type, res_base64));
+                Ok(format!("data:{};base64,{}", mime_type, res_base64))
             },
             Err(e) => {
                 error!("Failed to open image: {}", e);
                 warn!("Using fallback thumbnail");
-                return generate_fallback_thumbnail(path);
+                Ok(generate_fallback_thumbnail(path)?)
             },
         }
     }
 }
 
+/// Generate a video thumbnail with the thumbnails crate
+#[cfg(not(target_os = "windows"))]
+fn generate_platform_thumbnail(path: &Path) -> Result<String, String> {
+    log::debug!("Using Thumbnailer on non-Windows platform");
+    let thumbnailer = Thumbnailer::new(250, 250);
+    match thumbnailer.get(path) {
+        Ok(img) => {
+            log::debug!("Thumbnailer succeeded, converting to base64");
+            let mut buf = Vec::new();
+            let mut cursor = std::io::Cursor::new(&mut buf);
+            
+            if let Err(e) = img.write_to(&mut cursor, ImageFormat::Png) {
+                warn!("Failed to write thumbnail to buffer: {}", e);
+                return generate_fallback_thumbnail(path);
+            }
+            
+            let res_base64 = general_purpose::STANDARD.encode(&buf);
+            let mime_type = get_mime_type(path);
+            
+            log::debug!("Successfully generated thumbnail with Thumbnailer");
+            Ok(format!("data:{};base64,{}", mime_type, res_base64))
+        },
+        Err(e) => {
+            warn!("Thumbnailer failed: {}", e);
+            warn!("Using fallback thumbnail");
+            generate_fallback_thumbnail(path)
+        },
+    }
+}
+
+/// The thumbnails crate isn't available on Windows, so use the fallback thumbnail
+#[cfg(target_os = "windows")]
+fn generate_platform_thumbnail(path: &Path) -> Result<String, String> {
+    warn!("On Windows, using fallback thumbnail");
+    generate_fallback_thumbnail(path)
+}
+
 fn generate_fallback_thumbnail(path: &Path) -> Result<String, String> {
     info!("Generating fallback thumbnail for: {}", path.display());
     
@@ -606,12 +774,11 @@ fn generate_fallback_thumbnail(path: &Path) -> Result<String, String> {
                 if distance.sqrt() < radius as f32 - 10.0 {
                     // Simple right-pointing triangle
                     if x > center_x - 15 && x < center_x + 15 &&
-                       y > center_y - 15 && y < center_y + 15 {
-                        if x > center_x - 10 &&
-                           y > center_y - 10 - (x - center_x) / 2 &&
-                           y < center_y + 10 - (x - center_x) / 2 {
-                            img.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
-                        }
+                       y > center_y - 15 && y < center_y + 15 &&
+                       x > center_x - 10 &&
+                       y > center_y - 10 - (x - center_x) / 2 &&
+                       y < center_y + 10 - (x - center_x) / 2 {
+                        img.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
                     }
                 }
             }
@@ -656,44 +823,21 @@ fn get_mime_type(path: &Path) -> &'static str {
 }
 
 #[tauri::command]
-async fn save_destination_path(path: String, app: tauri::AppHandle) -> Result<(), String> {
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-    
-    // Create app data directory if it doesn't exist
-    if !app_data_dir.exists() {
-        fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
-    }
-    
-    let config_file = app_data_dir.join("config.json");
-    let config = serde_json::json!({
-        "destination_path": path
-    });
-    
-    fs::write(config_file, config.to_string()).map_err(|e| e.to_string())?;
-    Ok(())
+async fn save_destination_path(path: String, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    Ok(config::set(&app, "destination_path", &path)?)
 }
 
 #[tauri::command]
-async fn load_destination_path(app: tauri::AppHandle) -> Result<String, String> {
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-    let config_file = app_data_dir.join("config.json");
-    
-    if !config_file.exists() {
-        return Ok(String::new());
-    }
-    
-    let config_content = fs::read_to_string(config_file).map_err(|e| e.to_string())?;
-    let config: serde_json::Value = serde_json::from_str(&config_content).map_err(|e| e.to_string())?;
-    
-    Ok(config["destination_path"].as_str().unwrap_or("").to_string())
+async fn load_destination_path(app: tauri::AppHandle) -> Result<String, CamPorterError> {
+    Ok(config::get::<String>(&app, "destination_path")?.unwrap_or_default())
 }
 
 #[tauri::command]
-async fn open_destination_folder(path: String) -> Result<(), String> {
+async fn open_destination_folder(path: String) -> Result<(), CamPorterError> {
     let dest_path = Path::new(&path);
     
     if !dest_path.exists() {
-        return Err("Destination folder does not exist".to_string());
+        return Err(CamPorterError::not_found("Destination folder does not exist"));
     }
     
     let mut command = if cfg!(target_os = "linux") {
@@ -709,26 +853,35 @@ async fn open_destination_folder(path: String) -> Result<(), String> {
         cmd.arg(&path);
         cmd
     } else {
-        return Err("Unsupported platform".to_string());
+        return Err("Unsupported platform".into());
     };
 
-    command.output().map_err(|e| e.to_string())?;
+    watchdog::output(&mut command, watchdog::OPEN_TIMEOUT)?;
     Ok(())
 }
 
+/// Whether each file already has an identical copy in the destination; a
+/// different file under the same name doesn't count, as it'll be renamed.
+/// Names are looked up in `name_form` as the import would write them, and
+/// as they are on the card for copies made before normalization.
 #[tauri::command]
 fn check_files_exist_in_destination(
     file_paths: Vec<String>,
     destination_path: String,
-) -> Result<Vec<bool>, String> {
-    let dest = Path::new(&destination_path);
+    name_form: Option<normalize::NameForm>,
+) -> Result<Vec<bool>, CamPorterError> {
+    let dest = longpath::extend(Path::new(&destination_path));
+    let name_form = name_form.unwrap_or_default();
     let mut results = Vec::new();
     
     for file_path in file_paths {
-        let src_file = Path::new(&file_path);
+        let src_file = longpath::extend(Path::new(&file_path));
         let file_name = src_file.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
-        let target_file = dest.join(file_name);
-        results.push(target_file.exists());
+        let candidates = [dest.join(name_form.apply(file_name)), dest.join(file_name)];
+        let exists = candidates
+            .iter()
+            .any(|target_file| target_file.exists() && import::files_identical(&src_file, target_file).unwrap_or(false));
+        results.push(exists);
     }
     
     Ok(results)
@@ -748,82 +901,252 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_log::Builder::default().build())
+        .plugin(tauri_plugin_notification::init())
+        .manage(tiles::TileCache::default())
+        .manage(catalog::Catalog::default())
+        .manage(scan::ScanCache::default())
+        .manage(scan::ScanJobs::default())
+        .manage(protected::ProtectedFolders::default())
+        .manage(metrics::SessionMetrics::default())
+        .manage(jobs::JobManager::default())
+        .manage(format::FormatClearances::default())
+        .manage(ftp::FtpReceiver::default())
+        .manage(scheduler::Scheduler::default())
+        .manage(undo::UndoJournal::default())
+        .setup(|app| {
+            ffmpeg::init(app.handle());
+            workspace::init();
+            jobs::start(app.handle().clone());
+            hotplug::start(app.handle().clone());
+            scheduler::start(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             list_removable_drives,
             list_media_files,
+            list_card_media,
+            browse_folder_media,
+            scan::list_media_files_enriched,
+            scan::rescan_drive,
+            scan::cancel_scan,
+            layout::detect_card_layout,
+            protected::get_protected_folders,
+            protected::remount_drive,
+            protected::unlock_protected_folders,
+            cloud::set_cloud_source,
+            cloud::get_cloud_source,
+            cloud::check_files_in_cloud,
+            selftest::run_self_test,
+            sniff::check_file_types,
+            timelapse::detect_timelapses,
+            timelapse::assemble_timelapse_preview,
+            hooks::get_post_import_hook,
+            hooks::set_post_import_hook,
+            alerts::get_completion_alerts,
+            alerts::set_completion_alerts,
+            audio::get_audio_processing,
+            audio::set_audio_processing,
+            libraries::get_library_hints,
+            libraries::set_library_hints,
+            lightroom::get_lightroom_drop,
+            lightroom::set_lightroom_drop,
+            routing::get_type_destinations,
+            routing::set_type_destinations,
+            get_selection_stats,
             get_file_thumbnail,
+            catalog::query_import_history,
+            catalog::was_card_imported,
+            ffmpeg::get_ffmpeg_status,
+            ffmpeg::install_ffmpeg,
             unmount_drive,
+            mount_drive,
+            partitions::list_unmounted_partitions,
+            format::request_format_clearance,
+            format::format_drive,
             import_selected_files,
+            jobs::redirect_paused_import,
+            estimate::estimate_import,
+            estimate::benchmark_drive,
+            jobs::start_import_job,
+            jobs::get_job_status,
+            jobs::list_jobs,
+            jobs::redirect_paused_job,
+            jobs::cancel_job,
+            jobs::close_when_idle,
+            metrics::get_session_metrics,
             import_media,
             save_destination_path,
             load_destination_path,
             open_destination_folder,
             check_files_exist_in_destination,
+            check_destination_file_limits,
             read_log_file,
-            copy_to_temp
+            copy_to_temp,
+            get_raw_preview,
+            get_image_tile_info,
+            get_image_tile,
+            scheduler::schedule_import,
+            scheduler::schedule_verify,
+            scheduler::schedule_proxies,
+            scheduler::schedule_upload,
+            scheduler::list_scheduled_jobs,
+            scheduler::run_scheduled_job,
+            scheduler::cancel_scheduled_job,
+            scheduler::get_schedule_windows,
+            scheduler::set_schedule_windows,
+            ui_state::save_drive_ui_state,
+            ui_state::load_drive_ui_state,
+            ui_state::set_last_used_drive,
+            replaced::get_replaced_retention_days,
+            replaced::set_replaced_retention_days,
+            resume::list_interrupted_imports,
+            resume::resume_interrupted_import,
+            resume::discard_interrupted_import,
+            undo::undo_last_operation,
+            devices::list_devices,
+            devices::list_device_media,
+            devices::import_device_files,
+            ftp::get_ftp_settings,
+            ftp::start_ftp_receiver,
+            ftp::stop_ftp_receiver,
+            ftp::get_ftp_receiver_status,
+            health::get_card_health,
+            verify::verify_import,
+            drivelists::get_drive_lists,
+            drivelists::set_drive_lists,
+            drivelists::hide_drive,
+            profiles::list_drive_profiles,
+            profiles::save_drive_profile,
+            profiles::delete_drive_profile,
+            workflow::list_import_presets,
+            workflow::save_import_preset,
+            workflow::delete_import_preset,
+            workflow::export_workflow_pack,
+            workflow::import_workflow_pack
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if jobs::intercept_close(window.app_handle()) {
+                    api.prevent_close();
+                }
+            }
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Quitting from the menu or dock rather than closing the window;
+            // `app.exit` from `close_when_idle` comes with a code
+            tauri::RunEvent::ExitRequested { code: None, api, .. } if jobs::intercept_close(app) => api.prevent_exit(),
+            tauri::RunEvent::Exit => {
+                ios::unmount_all();
+                workspace::cleanup();
+            }
+            _ => {}
+        });
 }
 #[tauri::command]
-fn copy_to_temp(file_path: String, app: tauri::AppHandle) -> Result<String, String> {
+fn copy_to_temp(file_path: String) -> Result<String, CamPorterError> {
     use std::fs;
     use std::path::Path;
 
+    let file_path = remap::translate(&file_path);
     log::info!("copy_to_temp called with file_path: {}", file_path);
 
     let src = Path::new(&file_path);
     if !src.exists() {
         log::error!("Source file does not exist: {}", file_path);
-        return Err("Source file does not exist".to_string());
+        return Err(CamPorterError::not_found("Source file does not exist"));
     }
 
-    let temp_dir = match app.path().temp_dir() {
-        Ok(dir) => dir,
-        Err(e) => {
-            log::error!("Failed to get temp dir: {}", e);
-            return Err(e.to_string());
-        }
-    };
     let file_name = match src.file_name().and_then(|n| n.to_str()) {
         Some(name) => name,
         None => {
             log::error!("Invalid file name for: {}", file_path);
-            return Err("Invalid file name".to_string());
+            return Err(CamPorterError::invalid_input("Invalid file name"));
         }
     };
-    let dest_path = temp_dir.join(file_name);
-
-    // If file already exists in temp, remove it first
-    if dest_path.exists() {
-        match fs::remove_file(&dest_path) {
-            Ok(_) => log::info!("Removed existing file in temp: {}", dest_path.display()),
-            Err(e) => {
-                log::error!("Failed to remove existing file in temp: {}: {}", dest_path.display(), e);
-                return Err(e.to_string());
-            }
-        }
-    }
+    // Replaces any earlier copy of the same name
+    let size = fs::metadata(src).map(|m| m.len()).unwrap_or(0);
+    let dest_path = workspace::file(file_name, size)?;
 
     // Copy the file
-    match fs::copy(&src, &dest_path) {
+    match fs::copy(src, &dest_path) {
         Ok(_) => log::info!("Copied file to temp: {}", dest_path.display()),
         Err(e) => {
             log::error!("Failed to copy file to temp: {}: {}", dest_path.display(), e);
-            return Err(e.to_string());
+            return Err(e.into());
         }
     }
 
     Ok(dest_path.to_string_lossy().to_string())
 }
 
+/// Extract the full-size embedded JPEG of a RAW file into the temp dir and
+/// return its path, so the preview can load it through the asset protocol.
+#[tauri::command]
+fn get_raw_preview(path: String) -> Result<String, CamPorterError> {
+    let path = remap::translate(&path);
+    info!("get_raw_preview called with path: {}", path);
+
+    let src = Path::new(&path);
+    if !src.exists() {
+        error!("Source file does not exist: {}", path);
+        return Err(CamPorterError::not_found("Source file does not exist"));
+    }
+    if !raw::is_raw_file(src) {
+        return Err(CamPorterError::invalid_input("Not a RAW file"));
+    }
+
+    let data = fs::read(src).map_err(|e| e.to_string())?;
+    let jpeg = match raw::extract_largest_embedded_jpeg(&data) {
+        Some(jpeg) => jpeg,
+        None => {
+            warn!("No embedded JPEG found in: {}", path);
+            return Err(CamPorterError::not_found("No embedded preview found"));
+        }
+    };
+
+    let stem = src.file_stem().and_then(|s| s.to_str()).ok_or("Invalid file name")?;
+    let dest_path = workspace::file(&format!("{}_preview.jpg", stem), jpeg.len() as u64)?;
+
+    fs::write(&dest_path, jpeg).map_err(|e| e.to_string())?;
+    info!("Wrote {} byte RAW preview to: {}", jpeg.len(), dest_path.display());
+
+    Ok(dest_path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn get_image_tile_info(path: String) -> Result<tiles::TileInfo, CamPorterError> {
+    Ok(tiles::tile_info(Path::new(&remap::translate(&path)))?)
+}
+
+/// Serve one JPEG tile of the deep-zoom pyramid for an image
+#[tauri::command]
+fn get_image_tile(
+    path: String,
+    level: u32,
+    col: u32,
+    row: u32,
+    cache: tauri::State<'_, tiles::TileCache>,
+) -> Result<String, CamPorterError> {
+    let tile = cache.tile(&remap::translate(&path), level, col, row)?;
+    
+    let mut buf = Vec::new();
+    let mut cursor = std::io::Cursor::new(&mut buf);
+    image::DynamicImage::ImageRgb8(tile.to_rgb8())
+        .write_to(&mut cursor, ImageFormat::Jpeg)
+        .map_err(|e| format!("Error writing JPEG: {e}"))?;
+    
+    Ok(format!("data:image/jpeg;base64,{}", general_purpose::STANDARD.encode(&buf)))
+}
+
 #[tauri::command]
-fn read_log_file(app: tauri::AppHandle) -> Result<String, String> {
+fn read_log_file(app: tauri::AppHandle) -> Result<String, CamPorterError> {
     let log_dir = app.path().app_log_dir().map_err(|e| e.to_string())?;
     let log_file = log_dir.join("app.log");
     if !log_file.exists() {
-        return Err("Log file does not exist".to_string());
+        return Err(CamPorterError::not_found("Log file does not exist"));
     }
     let mut file = std::fs::File::open(log_file).map_err(|e| e.to_string())?;
     let mut contents = String::new();
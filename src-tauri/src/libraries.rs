@@ -0,0 +1,69 @@
+// Nudging photo managers to pick up an import straight away instead of at
+// their next startup scan: darktable is handed the folder to import, and
+// digiKam (along with KDE file managers) hears about it through KDirNotify
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::error::CamPorterError;
+use crate::{config, watchdog};
+
+const HINTS_KEY: &str = "library_hints";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub struct LibraryHints {
+    /// Open the destination in darktable, which imports it as a film roll;
+    /// a running darktable takes the folder over D-Bus
+    pub darktable: bool,
+    /// Announce the new files with a KDirNotify FilesAdded signal, which
+    /// digiKam watches for changes to its collections. Linux only.
+    pub digikam: bool,
+}
+
+fn notify_darktable(destination: &str) {
+    // Without a running instance this starts darktable, so don't wait for it
+    match Command::new("darktable").arg(destination).stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+        Ok(mut child) => {
+            info!("Handed {} to darktable", destination);
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => warn!("Failed to start darktable: {}", e),
+    }
+}
+
+fn notify_digikam(destination: &str) {
+    let url = format!("string:file://{}", destination);
+    let mut command = Command::new("dbus-send");
+    command.args(["--session", "--type=signal", "/", "org.kde.KDirNotify.FilesAdded", &url]);
+    match watchdog::output(&mut command, watchdog::OPEN_TIMEOUT) {
+        Ok(output) if output.status.success() => info!("Announced {} to digiKam", destination),
+        Ok(output) => warn!("Failed to notify digiKam: {}", String::from_utf8_lossy(&output.stderr).trim()),
+        Err(e) => warn!("Failed to notify digiKam: {}", e),
+    }
+}
+
+/// Tell the enabled photo managers about files newly copied to `destination`
+pub fn notify_import(app: &tauri::AppHandle, destination: &str, copied: usize) {
+    if copied == 0 {
+        return;
+    }
+    let hints: LibraryHints = config::get(app, HINTS_KEY).ok().flatten().unwrap_or_default();
+    if hints.darktable {
+        notify_darktable(destination);
+    }
+    if hints.digikam && cfg!(target_os = "linux") {
+        notify_digikam(destination);
+    }
+}
+
+#[tauri::command]
+pub fn get_library_hints(app: tauri::AppHandle) -> Result<LibraryHints, CamPorterError> {
+    Ok(config::get(&app, HINTS_KEY)?.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn set_library_hints(hints: LibraryHints, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    Ok(config::set(&app, HINTS_KEY, &hints)?)
+}
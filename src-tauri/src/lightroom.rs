@@ -0,0 +1,44 @@
+// Handing imports to Lightroom Classic's auto-import: files are written flat
+// into its watched folder, which Lightroom empties into its own destination,
+// so that destination is what gets cataloged. Copies are written as .part
+// files and renamed once complete, so Lightroom never picks up half a file.
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::error::CamPorterError;
+
+const DROP_KEY: &str = "lightroom_drop";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LightroomDrop {
+    /// The watched folder set in Lightroom's Auto Import Settings
+    pub watched_folder: String,
+    /// Where Lightroom moves files from the watched folder, subfolder included
+    pub destination_folder: String,
+}
+
+impl LightroomDrop {
+    /// Where Lightroom will have moved a file dropped at `dropped`
+    pub fn final_location(&self, dropped: &Path) -> PathBuf {
+        Path::new(&self.destination_folder).join(dropped.file_name().unwrap_or_default())
+    }
+}
+
+pub fn load(app: &tauri::AppHandle) -> Option<LightroomDrop> {
+    config::get::<LightroomDrop>(app, DROP_KEY)
+        .ok()
+        .flatten()
+        .filter(|d| !d.watched_folder.is_empty())
+}
+
+#[tauri::command]
+pub fn get_lightroom_drop(app: tauri::AppHandle) -> Option<LightroomDrop> {
+    load(&app)
+}
+
+/// Configure the auto-import folders, or clear them with None
+#[tauri::command]
+pub fn set_lightroom_drop(drop: Option<LightroomDrop>, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    Ok(config::set(&app, DROP_KEY, &drop)?)
+}
@@ -0,0 +1,70 @@
+// Windows limits ordinary paths to 260 characters (MAX_PATH), which a deeply
+// nested destination plus a long camera file name can pass. Paths with the
+// `\\?\` extended-length prefix skip that limit, so the copy engine works on
+// those and strips the prefix again for anything shown to the user. The app
+// manifest opts in to long paths too, for machines where they're enabled.
+// Elsewhere paths pass through unchanged.
+use std::path::{Path, PathBuf};
+
+/// `path` made absolute in extended-length form. The prefix turns off the
+/// system's own parsing, so `.`, `..` and forward slashes are resolved first.
+#[cfg(target_os = "windows")]
+pub fn extend(path: &Path) -> PathBuf {
+    use std::path::{Component, Prefix};
+
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    let mut components = absolute.components();
+    let mut extended = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(letter) => PathBuf::from(format!(r"\\?\{}:\", letter as char)),
+            Prefix::UNC(server, share) => {
+                PathBuf::from(format!(r"\\?\UNC\{}\{}\", server.to_string_lossy(), share.to_string_lossy()))
+            }
+            // Already extended, or a device path
+            _ => return absolute,
+        },
+        _ => return absolute,
+    };
+    for component in components {
+        match component {
+            Component::Normal(part) => extended.push(part),
+            Component::ParentDir => {
+                extended.pop();
+            }
+            _ => {}
+        }
+    }
+    extended
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn extend(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// `path` without an extended-length prefix, for comparing with mount points
+#[cfg(target_os = "windows")]
+pub fn strip(path: &Path) -> PathBuf {
+    let Some(text) = path.to_str() else {
+        return path.to_path_buf();
+    };
+    if let Some(rest) = text.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{}", rest));
+    }
+    match text.strip_prefix(r"\\?\") {
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => PathBuf::from(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn strip(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// `path` as shown to the user, without an extended-length prefix
+pub fn display(path: &Path) -> String {
+    strip(path).to_string_lossy().to_string()
+}
@@ -0,0 +1,77 @@
+// Capture metadata lookup (EXIF for stills, file times as a fallback)
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use exif::{In, Tag, Value};
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+
+pub struct CaptureInfo {
+    /// When the shot was taken, in camera-local time
+    pub captured: NaiveDateTime,
+    /// Camera model without the vendor prefix, e.g. "D850"
+    pub camera: Option<String>,
+    /// EXIF ExposureProgram, e.g. 1 for manual or 3 for aperture priority
+    pub exposure_program: Option<u16>,
+}
+
+/// Read capture date, camera model and exposure program from EXIF, falling
+/// back to the file's modification time when the file has no usable EXIF
+/// data (e.g. videos).
+pub fn read_capture_info(path: &Path) -> CaptureInfo {
+    let exif = fs::File::open(path)
+        .ok()
+        .and_then(|file| exif::Reader::new().read_from_container(&mut BufReader::new(file)).ok());
+
+    let captured = exif
+        .as_ref()
+        .and_then(|exif| exif_date_time(exif, Tag::DateTimeOriginal).or_else(|| exif_date_time(exif, Tag::DateTime)))
+        .unwrap_or_else(|| modified_local(path));
+
+    let camera = exif.as_ref().and_then(|exif| {
+        let model = exif_string(exif, Tag::Model)?;
+        let make = exif_string(exif, Tag::Make).unwrap_or_default();
+        Some(strip_make_prefix(&model, &make))
+    });
+
+    let exposure_program = exif.as_ref().and_then(|exif| match &exif.get_field(Tag::ExposureProgram, In::PRIMARY)?.value {
+        Value::Short(values) => values.first().copied(),
+        _ => None,
+    });
+
+    CaptureInfo { captured, camera, exposure_program }
+}
+
+fn exif_string(exif: &exif::Exif, tag: Tag) -> Option<String> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => {
+            let value = String::from_utf8_lossy(values.first()?).trim().to_string();
+            if value.is_empty() { None } else { Some(value) }
+        }
+        _ => None,
+    }
+}
+
+fn exif_date_time(exif: &exif::Exif, tag: Tag) -> Option<NaiveDateTime> {
+    let Value::Ascii(values) = &exif.get_field(tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    let dt = exif::DateTime::from_ascii(values.first()?).ok()?;
+    NaiveDate::from_ymd_opt(dt.year as i32, dt.month as u32, dt.day as u32)?
+        .and_hms_opt(dt.hour as u32, dt.minute as u32, dt.second as u32)
+}
+
+fn modified_local(path: &Path) -> NaiveDateTime {
+    let modified = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+    DateTime::<Local>::from(modified).naive_local()
+}
+
+/// Models usually repeat the vendor ("NIKON D850", "Canon EOS R5"); drop it
+fn strip_make_prefix(model: &str, make: &str) -> String {
+    let vendor = make.split_whitespace().next().unwrap_or("");
+    match model.split_once(' ') {
+        Some((first, rest)) if !vendor.is_empty() && first.eq_ignore_ascii_case(vendor) => rest.trim().to_string(),
+        _ => model.to_string(),
+    }
+}
@@ -0,0 +1,108 @@
+// Throughput sampled once a second for each import session, so the UI can
+// draw a live speed graph and spot stalls
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::CamPorterError;
+
+/// Sessions kept for querying; older ones are dropped
+const MAX_SESSIONS: usize = 20;
+
+#[derive(Serialize, Clone)]
+pub struct Sample {
+    pub elapsed_ms: u64,
+    pub bytes_per_second: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SessionMetricsReport {
+    pub session_id: u64,
+    pub finished: bool,
+    pub samples: Vec<Sample>,
+}
+
+pub struct Session {
+    transferred: AtomicU64,
+    finished: AtomicBool,
+    samples: Mutex<Vec<Sample>>,
+}
+
+impl Session {
+    pub fn add(&self, bytes: u64) {
+        self.transferred.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Marks its session finished when the import ends, however it ends
+pub struct SessionGuard {
+    pub id: u64,
+    pub session: Arc<Session>,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.session.finished.store(true, Ordering::SeqCst);
+    }
+}
+
+#[derive(Default)]
+pub struct SessionMetrics {
+    sessions: Mutex<BTreeMap<u64, Arc<Session>>>,
+    next_id: AtomicU64,
+}
+
+impl SessionMetrics {
+    /// Register a session and start sampling it in the background
+    pub fn start(&self) -> SessionGuard {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let session = Arc::new(Session {
+            transferred: AtomicU64::new(0),
+            finished: AtomicBool::new(false),
+            samples: Mutex::new(Vec::new()),
+        });
+
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.insert(id, Arc::clone(&session));
+            while sessions.len() > MAX_SESSIONS {
+                sessions.pop_first();
+            }
+        }
+
+        let sampled = Arc::clone(&session);
+        thread::spawn(move || {
+            let started = Instant::now();
+            let mut last = 0;
+            while !sampled.finished.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_secs(1));
+                let total = sampled.transferred.load(Ordering::Relaxed);
+                if let Ok(mut samples) = sampled.samples.lock() {
+                    samples.push(Sample {
+                        elapsed_ms: started.elapsed().as_millis() as u64,
+                        bytes_per_second: total - last,
+                        total_bytes: total,
+                    });
+                }
+                last = total;
+            }
+        });
+
+        SessionGuard { id, session }
+    }
+}
+
+#[tauri::command]
+pub fn get_session_metrics(session_id: u64, metrics: tauri::State<'_, SessionMetrics>) -> Result<SessionMetricsReport, CamPorterError> {
+    let sessions = metrics.sessions.lock().map_err(|e| e.to_string())?;
+    let session = sessions.get(&session_id).ok_or(format!("No session with id {}", session_id))?;
+    let samples = session.samples.lock().map_err(|e| e.to_string())?.clone();
+    Ok(SessionMetricsReport {
+        session_id,
+        finished: session.finished.load(Ordering::SeqCst),
+        samples,
+    })
+}
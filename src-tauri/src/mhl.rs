@@ -0,0 +1,258 @@
+// ASC Media Hash List (MHL v2) manifests for professional video work. Each
+// import adds a numbered generation to the destination's `ascmhl` folder,
+// listing every file it copied with the xxh3 hash from the copy (and MD5 on
+// request), and records the generation in `ascmhl_chain.xml` so DITs and
+// post houses can check the media's chain of custody with standard tools.
+use chrono::{DateTime, SecondsFormat, Utc};
+use md5::{Digest, Md5};
+use sha2::Sha512;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use sysinfo::System;
+
+use crate::auditlog::Outcome;
+use crate::import::{hash_file, ImportReport};
+
+const MHL_FOLDER: &str = "ascmhl";
+const CHAIN_FILE: &str = "ascmhl_chain.xml";
+/// What verifying tools should skip in the destination, including the import
+/// logs, which change with every import
+const IGNORED: [&str; 5] = [".DS_Store", "ascmhl", "ascmhl_chain.xml", "import-*.log", "import-*.json"];
+
+const BASE58: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+/// `target` relative to `root` with forward slashes, as MHL paths are written
+fn relative_path(target: &Path, root: &Path) -> Option<String> {
+    let relative = target.strip_prefix(root).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn md5_file(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Md5::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// C4 ID (SHA-512 in base58, 90 characters), which the chain uses to catch
+/// edits to a generation
+fn c4_id(data: &[u8]) -> String {
+    let digest = Sha512::digest(data);
+    // Repeated division of the big-endian digest by 58
+    let mut number: Vec<u8> = digest.to_vec();
+    let mut digits = Vec::new();
+    while number.iter().any(|&b| b != 0) {
+        let mut remainder = 0u32;
+        for byte in number.iter_mut() {
+            let value = (remainder << 8) | *byte as u32;
+            *byte = (value / 58) as u8;
+            remainder = value % 58;
+        }
+        digits.push(BASE58[remainder as usize]);
+    }
+    digits.resize(88, b'1');
+    digits.reverse();
+    format!("c4{}", String::from_utf8_lossy(&digits))
+}
+
+/// Sequence number for the next generation, after those already in `folder`
+fn next_generation(folder: &Path) -> u32 {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return 1;
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.ends_with(".mhl").then(|| name.split('_').next()?.parse::<u32>().ok()).flatten()
+        })
+        .max()
+        .map_or(1, |last| last + 1)
+}
+
+fn hash_entries(root: &Path, report: &ImportReport, md5: bool, now: &str) -> String {
+    let mut hashes = String::new();
+    for entry in report.log.iter().filter(|e| matches!(e.outcome, Outcome::Copied)) {
+        let Some(target) = entry.target.as_deref().map(Path::new) else {
+            continue;
+        };
+        // Type routes and the oversize destination can put copies elsewhere
+        let Some(path) = relative_path(target, root) else {
+            continue;
+        };
+        let Some(xxh3) = entry.checksum.clone().or_else(|| hash_file(target).ok().map(|h| format!("{:016x}", h))) else {
+            continue;
+        };
+        let modified = fs::metadata(target).and_then(|m| m.modified()).map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now());
+
+        hashes += "    <hash>\n";
+        hashes += &format!(
+            "      <path size=\"{}\" lastmodificationdate=\"{}\">{}</path>\n",
+            entry.size.unwrap_or(0),
+            timestamp(modified),
+            escape(&path)
+        );
+        hashes += &format!("      <xxh3 action=\"original\" hashdate=\"{}\">{}</xxh3>\n", now, xxh3);
+        if md5 {
+            if let Ok(md5) = md5_file(target) {
+                hashes += &format!("      <md5 action=\"original\" hashdate=\"{}\">{}</md5>\n", now, md5);
+            }
+        }
+        hashes += "    </hash>\n";
+    }
+    hashes
+}
+
+/// Add this import's generation to the MHL history in `destination`,
+/// returning the new manifest's path
+pub fn write(destination: &str, report: &ImportReport, md5: bool) -> Result<PathBuf, String> {
+    let root = Path::new(destination);
+    let folder = root.join(MHL_FOLDER);
+    fs::create_dir_all(&folder).map_err(|e| format!("Failed to create {}: {}", folder.display(), e))?;
+
+    let created = Utc::now();
+    let now = timestamp(created);
+    let sequence = next_generation(&folder);
+    let root_name = root.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "root".to_string());
+    let file_name = format!("{:04}_{}_{}.mhl", sequence, root_name, created.format("%Y-%m-%d_%H%M%SZ"));
+
+    let mut manifest = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    manifest += "<hashlist version=\"2.0\" xmlns=\"urn:ASC:MHL:v2.0\">\n";
+    manifest += "  <creatorinfo>\n";
+    manifest += &format!("    <creationdate>{}</creationdate>\n", now);
+    manifest += &format!("    <hostname>{}</hostname>\n", escape(&System::host_name().unwrap_or_default()));
+    manifest += &format!("    <tool version=\"{}\">CamPorter</tool>\n", env!("CARGO_PKG_VERSION"));
+    manifest += "  </creatorinfo>\n";
+    manifest += "  <processinfo>\n";
+    manifest += "    <process>transfer</process>\n";
+    manifest += "    <ignore>\n";
+    for pattern in IGNORED {
+        manifest += &format!("      <pattern>{}</pattern>\n", escape(pattern));
+    }
+    manifest += "    </ignore>\n";
+    manifest += "  </processinfo>\n";
+    manifest += "  <hashes>\n";
+    manifest += &hash_entries(root, report, md5, &now);
+    manifest += "  </hashes>\n";
+    manifest += "</hashlist>\n";
+    let manifest_path = folder.join(&file_name);
+    fs::write(&manifest_path, &manifest).map_err(|e| format!("Failed to write {}: {}", manifest_path.display(), e))?;
+
+    // The chain lists every generation with its C4 ID, oldest first
+    let chain_path = folder.join(CHAIN_FILE);
+    let generation = format!(
+        "  <hashlist sequencenr=\"{}\">\n    <path>{}</path>\n    <c4>{}</c4>\n  </hashlist>\n",
+        sequence,
+        escape(&file_name),
+        c4_id(manifest.as_bytes())
+    );
+    let existing = fs::read_to_string(&chain_path).unwrap_or_default();
+    let chain = match existing.rfind("</ascmhldirectory>") {
+        Some(end) => format!("{}{}</ascmhldirectory>\n", &existing[..end], generation),
+        None => format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ascmhldirectory xmlns=\"urn:ASC:MHL:DIRECTORY:v2.0\">\n{}</ascmhldirectory>\n",
+            generation
+        ),
+    };
+    fs::write(&chain_path, chain).map_err(|e| format!("Failed to write {}: {}", chain_path.display(), e))?;
+    Ok(manifest_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auditlog::LogEntry;
+
+    #[test]
+    fn escapes_xml_text() {
+        assert_eq!(escape("A&B <\"C\">"), "A&amp;B &lt;&quot;C&quot;&gt;");
+    }
+
+    #[test]
+    fn writes_paths_relative_to_the_root() {
+        let root = Path::new("/media/shoot");
+        assert_eq!(relative_path(&root.join("A001").join("C001.MXF"), root).as_deref(), Some("A001/C001.MXF"));
+        assert_eq!(relative_path(root, root), None);
+        assert_eq!(relative_path(Path::new("/media/other/C001.MXF"), root), None);
+    }
+
+    #[test]
+    fn hashes_md5() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc.txt");
+        fs::write(&path, b"abc").unwrap();
+        assert_eq!(md5_file(&path).unwrap(), "900150983cd24fb0d6963f7d28e17f72");
+    }
+
+    #[test]
+    fn makes_fixed_length_c4_ids() {
+        for data in [&b""[..], b"abc", &[0xff; 1000]] {
+            let id = c4_id(data);
+            assert_eq!(id.len(), 90);
+            assert!(id.starts_with("c4"));
+            assert!(id[2..].bytes().all(|b| BASE58.contains(&b)));
+        }
+        assert_eq!(c4_id(b"abc"), c4_id(b"abc"));
+        assert_ne!(c4_id(b"abc"), c4_id(b"abd"));
+    }
+
+    #[test]
+    fn numbers_generations_after_existing_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(next_generation(&dir.path().join("missing")), 1);
+        assert_eq!(next_generation(dir.path()), 1);
+        fs::write(dir.path().join("0001_shoot_2024-06-01_120000Z.mhl"), "").unwrap();
+        fs::write(dir.path().join("0007_shoot_2024-06-02_120000Z.mhl"), "").unwrap();
+        fs::write(dir.path().join(CHAIN_FILE), "").unwrap();
+        assert_eq!(next_generation(dir.path()), 8);
+    }
+
+    #[test]
+    fn adds_a_generation_per_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("shoot");
+        fs::create_dir_all(&root).unwrap();
+        let clip = root.join("C001.MXF");
+        fs::write(&clip, b"abc").unwrap();
+        let elsewhere = dir.path().join("C002.MXF");
+        fs::write(&elsewhere, b"abc").unwrap();
+        let report = ImportReport {
+            log: vec![
+                LogEntry::copied(Path::new("/card/C001.MXF"), &clip, 3, Some(0x1234)),
+                LogEntry::copied(Path::new("/card/C002.MXF"), &elsewhere, 3, Some(0x5678)),
+            ],
+            ..Default::default()
+        };
+
+        let first = write(root.to_str().unwrap(), &report, true).unwrap();
+        let manifest = fs::read_to_string(&first).unwrap();
+        assert!(first.file_name().unwrap().to_string_lossy().starts_with("0001_shoot_"));
+        assert!(manifest.contains(">C001.MXF</path>"));
+        assert!(manifest.contains(">0000000000001234</xxh3>"));
+        assert!(manifest.contains(">900150983cd24fb0d6963f7d28e17f72</md5>"));
+        assert!(!manifest.contains("C002.MXF"));
+
+        let second = write(root.to_str().unwrap(), &report, false).unwrap();
+        assert!(second.file_name().unwrap().to_string_lossy().starts_with("0002_shoot_"));
+        assert!(!fs::read_to_string(&second).unwrap().contains("<md5"));
+        let chain = fs::read_to_string(root.join(MHL_FOLDER).join(CHAIN_FILE)).unwrap();
+        assert!(chain.contains("sequencenr=\"1\"") && chain.contains("sequencenr=\"2\""));
+        assert_eq!(chain.matches("</ascmhldirectory>").count(), 1);
+    }
+}
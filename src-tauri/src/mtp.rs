@@ -0,0 +1,145 @@
+// Android phones and cameras that offer their media over MTP rather than as
+// a drive, through libmtp's command-line tools (mtp-detect, mtp-folders,
+// mtp-files, mtp-getfile). The tools always open the first device libmtp
+// finds, so one MTP device is offered at a time. Windows speaks MTP through
+// WPD instead, which isn't supported yet.
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use which::which;
+
+use crate::devices::DeviceObject;
+use crate::watchdog;
+
+/// The only device the tools can open
+const FIRST_DEVICE: &str = "0";
+
+fn run(command: &mut Command, timeout: std::time::Duration) -> Result<String, String> {
+    let output = watchdog::output(command, timeout)?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if output.status.success() {
+        Ok(stdout)
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("{} {}", stderr.trim(), stdout.lines().last().unwrap_or_default()).trim().to_string())
+    }
+}
+
+/// The connected device's id and name, from the "Device 0 (VID=04e8 and
+/// PID=6860) is a Samsung Galaxy models (MTP)." line libmtp prints
+pub fn list_devices() -> Result<Vec<(String, String)>, String> {
+    if cfg!(target_os = "windows") || which("mtp-detect").is_err() {
+        return Ok(Vec::new());
+    }
+    let output = run(&mut Command::new("mtp-detect"), watchdog::PROBE_TIMEOUT)?;
+    Ok(output
+        .lines()
+        .find_map(|line| {
+            let line = line.trim();
+            line.starts_with("Device 0 (").then(|| line.split_once(" is a ").map(|(_, name)| name))?
+        })
+        .map(|name| vec![(FIRST_DEVICE.to_string(), name.trim_end_matches('.').to_string())])
+        .unwrap_or_default())
+}
+
+/// Folders by id, with their name and parent, from `mtp-folders`, which
+/// prints "<id>\t<name>" indented two spaces per level
+fn parse_folders(output: &str) -> HashMap<String, (String, Option<String>)> {
+    let mut folders = HashMap::new();
+    let mut ancestors: Vec<String> = Vec::new();
+    for line in output.lines() {
+        let Some((id, name)) = line.split_once('\t') else {
+            continue;
+        };
+        let id = id.trim();
+        if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        let depth = (name.len() - name.trim_start().len()) / 2;
+        ancestors.truncate(depth);
+        folders.insert(id.to_string(), (name.trim().to_string(), ancestors.last().cloned()));
+        ancestors.push(id.to_string());
+    }
+    folders
+}
+
+/// One file from `mtp-files`, which prints a "File ID:" line and then
+/// indented "Key: value" lines for it
+#[derive(Default)]
+struct ListedFile {
+    id: String,
+    name: String,
+    size: u64,
+    parent: String,
+    modified: u64,
+}
+
+fn parse_files(output: &str) -> Vec<ListedFile> {
+    let mut files: Vec<ListedFile> = Vec::new();
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(id) = line.strip_prefix("File ID:") {
+            files.push(ListedFile { id: id.trim().to_string(), ..Default::default() });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if let Some(size) = line.strip_prefix("File size ") {
+            file.size = size.split_whitespace().next().and_then(|s| s.parse().ok()).unwrap_or(0);
+        } else if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            match key {
+                "Filename" => file.name = value.to_string(),
+                "Parent ID" => file.parent = value.to_string(),
+                // Written with ctime(), e.g. "Sat Jun  1 14:03:22 2024"
+                key if key.to_lowercase().contains("date") => {
+                    if let Ok(date) = NaiveDateTime::parse_from_str(value, "%a %b %e %H:%M:%S %Y") {
+                        file.modified = date.and_utc().timestamp().max(0) as u64;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    files
+}
+
+/// Whether `folder` is a DCIM folder or inside one
+fn in_dcim(folders: &HashMap<String, (String, Option<String>)>, folder: &str) -> bool {
+    let mut folder = Some(folder);
+    while let Some((name, parent)) = folder.and_then(|id| folders.get(id)) {
+        if name.eq_ignore_ascii_case("DCIM") {
+            return true;
+        }
+        folder = parent.as_deref();
+    }
+    false
+}
+
+/// Every file under a DCIM folder on the device
+pub fn list_media(device: &str) -> Result<Vec<DeviceObject>, String> {
+    if device != FIRST_DEVICE {
+        return Err(format!("MTP device {} is no longer connected", device));
+    }
+    let folders = parse_folders(&run(&mut Command::new("mtp-folders"), watchdog::DEVICE_LIST_TIMEOUT)?);
+    let files = parse_files(&run(&mut Command::new("mtp-files"), watchdog::DEVICE_LIST_TIMEOUT)?);
+    Ok(files
+        .into_iter()
+        .filter(|file| !file.name.is_empty() && in_dcim(&folders, &file.parent))
+        .map(|file| DeviceObject { object: file.id, name: file.name, size: file.size, modified: file.modified })
+        .collect())
+}
+
+/// Copy the file with id `object` off the device to `target`
+pub fn download(device: &str, object: &str, target: &Path) -> Result<(), String> {
+    if device != FIRST_DEVICE {
+        return Err(format!("MTP device {} is no longer connected", device));
+    }
+    run(Command::new("mtp-getfile").arg(object).arg(target), watchdog::DEVICE_TRANSFER_TIMEOUT)?;
+    if !target.is_file() {
+        return Err("mtp-getfile wrote nothing".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+// Unicode normalization of destination file names. Cameras and macOS write
+// decomposed (NFD) names, which Linux and Windows file systems keep byte for
+// byte, so "é" from a card and "é" typed on the destination end up as two
+// different files that look the same.
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum NameForm {
+    /// Composed, as Linux and Windows tools type names
+    #[default]
+    Nfc,
+    /// Decomposed, as macOS has traditionally written them
+    Nfd,
+    /// Keep whatever the card has
+    AsIs,
+}
+
+impl NameForm {
+    pub fn apply(self, name: &str) -> String {
+        match self {
+            NameForm::Nfc => name.nfc().collect(),
+            NameForm::Nfd => name.nfd().collect(),
+            NameForm::AsIs => name.to_string(),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+// System notifications for import jobs that finish while the window is in
+// the background
+use std::time::Duration;
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::humanize;
+use crate::import::ImportReport;
+
+/// Jobs quicker than this finish before anyone has looked away
+const MIN_DURATION: Duration = Duration::from_secs(10);
+
+fn window_focused(app: &tauri::AppHandle) -> bool {
+    app.webview_windows().values().any(|w| w.is_focused().unwrap_or(false))
+}
+
+/// Notify about a finished job, unless it was short or the app is in front
+pub fn job_finished(app: &tauri::AppHandle, result: &Result<ImportReport, String>, elapsed: Duration) {
+    if elapsed < MIN_DURATION || window_focused(app) {
+        return;
+    }
+
+    let (title, body) = match result {
+        Ok(report) => {
+            let mut body = format!("{} files, {} copied", report.copied, humanize::bytes(report.bytes));
+            if report.failed > 0 {
+                body += &format!(", {} failed", report.failed);
+            }
+            let title = if report.failed > 0 { "Import finished with errors" } else { "Import complete" };
+            (title, body)
+        }
+        Err(e) => ("Import failed", e.clone()),
+    };
+
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show notification: {}", e);
+    }
+}
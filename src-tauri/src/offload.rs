@@ -0,0 +1,119 @@
+// Per-card history of offloaded files (relative path, size and mtime), so a
+// re-inserted card can be imported incrementally. Cards are keyed by volume
+// serial, so the history follows a card into any reader.
+use log::warn;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+use tauri::Manager;
+
+use crate::{disk_fingerprint, longpath};
+
+fn history_path(app: &tauri::AppHandle, fingerprint: &str) -> Result<PathBuf, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(app_data_dir.join("offloaded").join(format!("{}.json", fingerprint)))
+}
+
+/// All files recorded as offloaded from the card with this fingerprint
+pub fn load(app: &tauri::AppHandle, fingerprint: &str) -> HashSet<String> {
+    history_path(app, fingerprint)
+        .and_then(|path| fs::read_to_string(path).map_err(|e| e.to_string()))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save(app: &tauri::AppHandle, fingerprint: &str, history: &HashSet<String>) -> Result<(), String> {
+    let path = history_path(app, fingerprint)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, serde_json::to_string(history).map_err(|e| e.to_string())?).map_err(|e| e.to_string())
+}
+
+/// History key for a file on a card mounted at `mount_point`
+pub fn file_key(path: &Path, mount_point: &Path) -> Option<String> {
+    let metadata = fs::metadata(longpath::extend(path)).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let relative = path.strip_prefix(mount_point).ok()?.to_string_lossy().replace('\\', "/");
+    Some(format!("{}|{}|{}", relative, metadata.len(), modified))
+}
+
+struct Card {
+    mount_point: PathBuf,
+    fingerprint: String,
+    history: Option<HashSet<String>>,
+    changed: bool,
+}
+
+/// Checks and records offloaded files during one import, loading each card's
+/// history only when a file from it comes up
+pub struct OffloadTracker<'a> {
+    app: &'a tauri::AppHandle,
+    cards: Vec<Card>,
+}
+
+impl<'a> OffloadTracker<'a> {
+    pub fn new(app: &'a tauri::AppHandle) -> Self {
+        let disks = Disks::new_with_refreshed_list();
+        let mut cards: Vec<Card> = disks
+            .iter()
+            .map(|disk| Card {
+                mount_point: disk.mount_point().to_path_buf(),
+                fingerprint: disk_fingerprint(disk),
+                history: None,
+                changed: false,
+            })
+            .collect();
+        // Longest mount point first, so nested mounts win
+        cards.sort_by_key(|c| std::cmp::Reverse(c.mount_point.as_os_str().len()));
+        OffloadTracker { app, cards }
+    }
+
+    fn card(&mut self, path: &Path) -> Option<(&mut Card, String)> {
+        let card = self.cards.iter_mut().find(|c| path.starts_with(&c.mount_point))?;
+        let key = file_key(path, &card.mount_point)?;
+        if card.history.is_none() {
+            card.history = Some(load(self.app, &card.fingerprint));
+        }
+        Some((card, key))
+    }
+
+    /// Fingerprint of the card a file is on
+    pub fn card_id(&self, path: &Path) -> Option<&str> {
+        self.cards
+            .iter()
+            .find(|c| path.starts_with(&c.mount_point))
+            .map(|c| c.fingerprint.as_str())
+    }
+
+    pub fn is_offloaded(&mut self, path: &Path) -> bool {
+        self.card(path)
+            .is_some_and(|(card, key)| card.history.as_ref().is_some_and(|h| h.contains(&key)))
+    }
+
+    pub fn mark(&mut self, path: &Path) {
+        if let Some((card, key)) = self.card(path) {
+            if let Some(history) = &mut card.history {
+                card.changed |= history.insert(key);
+            }
+        }
+    }
+
+    /// Write back the history of every card that gained files
+    pub fn save(self) {
+        for card in self.cards.iter().filter(|c| c.changed) {
+            if let Some(history) = &card.history {
+                if let Err(e) = save(self.app, &card.fingerprint, history) {
+                    warn!("Failed to save offload history: {}", e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+// Removable partitions that nothing has mounted, as on minimal Linux setups
+// without an automounter, and mounting them so the card turns up in the drive
+// list. udisks does it on Linux and DiskArbitration on macOS. On Windows a
+// volume is always mounted once recognised, but one without a drive letter
+// can't be browsed, so a free letter is assigned to it.
+use log::info;
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::error::CamPorterError;
+
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct UnmountedPartition {
+    /// What `mount_drive` takes: "/dev/sdb1", "/dev/disk4s1", or a volume GUID
+    /// path such as `\\?\Volume{...}\` on Windows
+    pub device_id: String,
+    pub label: Option<String>,
+    pub file_system: String,
+    pub total_bytes: u64,
+}
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    use std::ffi::c_void;
+
+    pub const MAX_PATH: usize = 260;
+    pub const DRIVE_REMOVABLE: u32 = 2;
+    pub const ERROR_ACCESS_DENIED: i32 = 5;
+    pub const INVALID_HANDLE_VALUE: *mut c_void = -1isize as *mut c_void;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn FindFirstVolumeW(volume_name: *mut u16, buffer_length: u32) -> *mut c_void;
+        pub fn FindNextVolumeW(find_volume: *mut c_void, volume_name: *mut u16, buffer_length: u32) -> i32;
+        pub fn FindVolumeClose(find_volume: *mut c_void) -> i32;
+        pub fn GetVolumePathNamesForVolumeNameW(
+            volume_name: *const u16,
+            volume_path_names: *mut u16,
+            buffer_length: u32,
+            return_length: *mut u32,
+        ) -> i32;
+        pub fn SetVolumeMountPointW(volume_mount_point: *const u16, volume_name: *const u16) -> i32;
+        pub fn GetLogicalDrives() -> u32;
+        pub fn GetDriveTypeW(root_path_name: *const u16) -> u32;
+        pub fn GetDiskFreeSpaceExW(
+            directory_name: *const u16,
+            free_bytes_available: *mut u64,
+            total_number_of_bytes: *mut u64,
+            total_number_of_free_bytes: *mut u64,
+        ) -> i32;
+        pub fn GetVolumeInformationW(
+            root_path_name: *const u16,
+            volume_name_buffer: *mut u16,
+            volume_name_size: u32,
+            volume_serial_number: *mut u32,
+            maximum_component_length: *mut u32,
+            file_system_flags: *mut u32,
+            file_system_name_buffer: *mut u16,
+            file_system_name_size: u32,
+        ) -> i32;
+    }
+
+    pub fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(Some(0)).collect()
+    }
+
+    pub fn text(buffer: &[u16]) -> String {
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        String::from_utf16_lossy(&buffer[..len])
+    }
+}
+
+/// Volume GUID paths of every volume Windows knows about
+#[cfg(target_os = "windows")]
+fn volumes() -> Vec<String> {
+    let mut name = [0u16; win32::MAX_PATH + 1];
+    let find = unsafe { win32::FindFirstVolumeW(name.as_mut_ptr(), name.len() as u32) };
+    if find == win32::INVALID_HANDLE_VALUE {
+        return Vec::new();
+    }
+    let mut volumes = vec![win32::text(&name)];
+    while unsafe { win32::FindNextVolumeW(find, name.as_mut_ptr(), name.len() as u32) } != 0 {
+        volumes.push(win32::text(&name));
+    }
+    unsafe { win32::FindVolumeClose(find) };
+    volumes
+}
+
+/// Whether any drive letter or folder leads to the volume
+#[cfg(target_os = "windows")]
+fn has_path(volume: &[u16]) -> bool {
+    let mut paths = [0u16; win32::MAX_PATH + 1];
+    let mut len = 0;
+    let ok = unsafe { win32::GetVolumePathNamesForVolumeNameW(volume.as_ptr(), paths.as_mut_ptr(), paths.len() as u32, &mut len) };
+    // Too small a buffer means it has several
+    ok == 0 || paths[0] != 0
+}
+
+#[cfg(target_os = "windows")]
+pub fn unmounted() -> Result<Vec<UnmountedPartition>, CamPorterError> {
+    let mut found = Vec::new();
+    for volume in volumes() {
+        let root = win32::wide(&volume);
+        if unsafe { win32::GetDriveTypeW(root.as_ptr()) } != win32::DRIVE_REMOVABLE || has_path(&root) {
+            continue;
+        }
+        let (mut label, mut file_system) = ([0u16; win32::MAX_PATH + 1], [0u16; win32::MAX_PATH + 1]);
+        let recognised = unsafe {
+            win32::GetVolumeInformationW(
+                root.as_ptr(),
+                label.as_mut_ptr(),
+                label.len() as u32,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                file_system.as_mut_ptr(),
+                file_system.len() as u32,
+            )
+        } != 0;
+        // An empty reader slot or a partition no file system driver claimed
+        if !recognised {
+            continue;
+        }
+        let mut total_bytes = 0;
+        unsafe { win32::GetDiskFreeSpaceExW(root.as_ptr(), std::ptr::null_mut(), &mut total_bytes, std::ptr::null_mut()) };
+        let label = win32::text(&label);
+        found.push(UnmountedPartition {
+            device_id: volume,
+            label: (!label.is_empty()).then_some(label),
+            file_system: win32::text(&file_system).to_lowercase(),
+            total_bytes,
+        });
+    }
+    Ok(found)
+}
+
+/// Give the volume the first free drive letter from D: on
+#[cfg(target_os = "windows")]
+pub fn mount(device: &str) -> Result<String, CamPorterError> {
+    let volume = win32::wide(device);
+    let used = unsafe { win32::GetLogicalDrives() };
+    let letter = (3..26).find(|letter| used & (1 << letter) == 0).ok_or("Every drive letter is in use")?;
+    let mount_point = format!("{}:\\", (b'A' + letter) as char);
+    if unsafe { win32::SetVolumeMountPointW(win32::wide(&mount_point).as_ptr(), volume.as_ptr()) } == 0 {
+        let error = std::io::Error::last_os_error();
+        if error.raw_os_error() == Some(win32::ERROR_ACCESS_DENIED) {
+            return Err(CamPorterError::PermissionDenied {
+                message: "Assigning a drive letter needs administrator rights".to_string(),
+            });
+        }
+        return Err(CamPorterError::io(format!("Failed to assign {} to the volume", mount_point), error));
+    }
+    info!("Mounted {} at {}", device, mount_point);
+    Ok(mount_point)
+}
+
+#[cfg(target_os = "linux")]
+pub fn unmounted() -> Result<Vec<UnmountedPartition>, CamPorterError> {
+    crate::udisks::Udisks::connect()?.unmounted()
+}
+
+/// Mount the partition through udisks, under /run/media or /media as the
+/// desktop's automounter would have
+#[cfg(target_os = "linux")]
+pub fn mount(device: &str) -> Result<String, CamPorterError> {
+    let mount_point = crate::udisks::Udisks::connect()?.mount(device)?;
+    info!("Mounted {} at {}", device, mount_point);
+    Ok(mount_point)
+}
+
+#[cfg(target_os = "macos")]
+pub fn unmounted() -> Result<Vec<UnmountedPartition>, CamPorterError> {
+    crate::diskarb::unmounted()
+}
+
+#[cfg(target_os = "macos")]
+pub fn mount(device: &str) -> Result<String, CamPorterError> {
+    let mount_point = crate::diskarb::mount(device)?.to_string_lossy().to_string();
+    info!("Mounted {} at {}", device, mount_point);
+    Ok(mount_point)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn unmounted() -> Result<Vec<UnmountedPartition>, CamPorterError> {
+    Ok(Vec::new())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn mount(_device: &str) -> Result<String, CamPorterError> {
+    Err("Mounting drives isn't supported on this platform yet".into())
+}
+
+/// Removable partitions that could be mounted with `mount_drive`
+#[tauri::command]
+pub async fn list_unmounted_partitions() -> Result<Vec<UnmountedPartition>, CamPorterError> {
+    unmounted()
+}
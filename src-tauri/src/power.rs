@@ -0,0 +1,103 @@
+// Keeping the machine awake while an import runs, since a laptop that sleeps
+// mid-offload drops the card reader and breaks off the copy
+use log::{info, warn};
+
+/// Holds off system sleep until dropped. Acquiring it never fails the
+/// import; without it the import just runs unprotected.
+pub struct SleepInhibitor {
+    #[cfg(not(target_os = "windows"))]
+    child: Option<std::process::Child>,
+}
+
+#[cfg(target_os = "linux")]
+fn spawn_inhibitor(why: &str) -> std::io::Result<std::process::Child> {
+    use std::process::{Command, Stdio};
+
+    // The lock lasts as long as `cat`, which exits when its stdin closes,
+    // so it's released even if the app itself dies
+    Command::new("systemd-inhibit")
+        .args(["--what=sleep:idle", "--who=CamPorter", "--mode=block"])
+        .arg(format!("--why={}", why))
+        .arg("cat")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+}
+
+#[cfg(target_os = "macos")]
+fn spawn_inhibitor(_why: &str) -> std::io::Result<std::process::Child> {
+    use std::process::{Command, Stdio};
+
+    // caffeinate holds an IOPMAssertion and drops it once this process exits
+    Command::new("caffeinate")
+        .args(["-i", "-w", &std::process::id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn spawn_inhibitor(_why: &str) -> std::io::Result<std::process::Child> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "no sleep inhibitor on this platform"))
+}
+
+#[cfg(target_os = "windows")]
+mod execution_state {
+    pub const ES_CONTINUOUS: u32 = 0x8000_0000;
+    pub const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+}
+
+impl SleepInhibitor {
+    #[cfg(not(target_os = "windows"))]
+    pub fn acquire(why: &str) -> Self {
+        let child = match spawn_inhibitor(why) {
+            Ok(child) => {
+                info!("Inhibiting sleep: {}", why);
+                Some(child)
+            }
+            Err(e) => {
+                warn!("Failed to inhibit sleep: {}", e);
+                None
+            }
+        };
+        SleepInhibitor { child }
+    }
+
+    /// The execution state belongs to the calling thread, so the inhibitor
+    /// must be dropped on the thread that acquired it
+    #[cfg(target_os = "windows")]
+    pub fn acquire(why: &str) -> Self {
+        use execution_state::*;
+
+        if unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) } == 0 {
+            warn!("Failed to inhibit sleep");
+        } else {
+            info!("Inhibiting sleep: {}", why);
+        }
+        SleepInhibitor {}
+    }
+}
+
+impl Drop for SleepInhibitor {
+    #[cfg(not(target_os = "windows"))]
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            drop(child.stdin.take());
+            let _ = child.kill();
+            let _ = child.wait();
+            info!("Released sleep inhibitor");
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn drop(&mut self) {
+        unsafe { execution_state::SetThreadExecutionState(execution_state::ES_CONTINUOUS) };
+        info!("Released sleep inhibitor");
+    }
+}
@@ -0,0 +1,76 @@
+// Per-card settings, keyed by volume serial so they follow the card into any
+// reader: where its files go, how they're named, which types to pick and
+// whether to eject it afterwards. `list_removable_drives` returns the
+// matching profile with each drive for the UI to pre-fill its import.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use ts_rs::TS;
+
+use crate::config;
+use crate::dto::RemovableDrive;
+use crate::error::CamPorterError;
+
+const PROFILES_KEY: &str = "drive_profiles";
+
+/// Settings for one card; anything left unset keeps the app's default
+#[derive(Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct DriveProfile {
+    /// Name for the card shown alongside its label, such as "A-cam card 2"
+    pub name: Option<String>,
+    pub destination: Option<String>,
+    /// Template such as "{date}_{camera}_{seq}", as in `ImportOptions`
+    pub rename_template: Option<String>,
+    /// Extensions to select, lowercase and without the dot; empty for all
+    pub file_types: Vec<String>,
+    pub eject_after_import: bool,
+}
+
+fn load(app: &tauri::AppHandle) -> Result<BTreeMap<String, DriveProfile>, String> {
+    Ok(config::get(app, PROFILES_KEY)?.unwrap_or_default())
+}
+
+/// Fill in each drive's profile, for drives whose serial has one
+pub fn attach(app: &tauri::AppHandle, mut drives: Vec<RemovableDrive>) -> Vec<RemovableDrive> {
+    let profiles = load(app).unwrap_or_default();
+    for drive in &mut drives {
+        drive.profile = drive.serial.as_ref().and_then(|serial| profiles.get(serial)).cloned();
+    }
+    drives
+}
+
+#[tauri::command]
+pub fn list_drive_profiles(app: tauri::AppHandle) -> Result<BTreeMap<String, DriveProfile>, CamPorterError> {
+    Ok(load(&app)?)
+}
+
+/// Add or replace the profile for the card with `serial`
+#[tauri::command]
+pub fn save_drive_profile(serial: String, profile: DriveProfile, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    if serial.trim().is_empty() {
+        return Err(CamPorterError::invalid_input("Cards without a volume serial can't have a profile"));
+    }
+    let mut profiles = load(&app)?;
+    profiles.insert(serial, DriveProfile { file_types: normalized(profile.file_types), ..profile });
+    Ok(config::set(&app, PROFILES_KEY, &profiles)?)
+}
+
+#[tauri::command]
+pub fn delete_drive_profile(serial: String, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    let mut profiles = load(&app)?;
+    profiles.remove(&serial);
+    Ok(config::set(&app, PROFILES_KEY, &profiles)?)
+}
+
+/// Extensions as they're matched: ".CR3" and "cr3" both become "cr3"
+fn normalized(file_types: Vec<String>) -> Vec<String> {
+    let mut file_types: Vec<String> = file_types
+        .iter()
+        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect();
+    file_types.sort();
+    file_types.dedup();
+    file_types
+}
@@ -0,0 +1,172 @@
+// Folders a scan couldn't enter, typically on cards mounted root-owned (e.g.
+// under /media by fstab or a system automounter) or by another user's
+// session, and the two ways of getting at them: a udisks remount owned by the
+// user, or a pkexec-assisted unlock
+use log::{info, warn};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+use sysinfo::Disks;
+
+use crate::error::CamPorterError;
+use crate::{longpath, watchdog};
+
+/// Pass a walk entry through, noting the folder if access to it was denied
+pub fn note_denied(entry: walkdir::Result<walkdir::DirEntry>, denied: &mut Vec<String>) -> Option<walkdir::DirEntry> {
+    match entry {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            if e.io_error().is_some_and(|io| io.kind() == ErrorKind::PermissionDenied) {
+                if let Some(path) = e.path() {
+                    warn!("Permission denied scanning {}", path.display());
+                    denied.push(longpath::display(path));
+                }
+            }
+            None
+        }
+    }
+}
+
+/// Denied folders from the last scan of each drive
+#[derive(Default)]
+pub struct ProtectedFolders {
+    scans: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl ProtectedFolders {
+    pub fn store(&self, drive_path: &str, mut denied: Vec<String>) {
+        denied.sort();
+        denied.dedup();
+        if let Ok(mut scans) = self.scans.lock() {
+            scans.insert(drive_path.to_string(), denied);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_protected_folders(drive_path: String, protected: tauri::State<'_, ProtectedFolders>) -> Result<Vec<String>, CamPorterError> {
+    let scans = protected.scans.lock().map_err(|e| e.to_string())?;
+    Ok(scans.get(&drive_path).cloned().unwrap_or_default())
+}
+
+/// Name of the user running the app
+#[cfg(target_os = "linux")]
+fn current_user() -> Option<String> {
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let code = unsafe { libc::getpwuid_r(libc::getuid(), &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if code != 0 || result.is_null() {
+        return std::env::var("USER").ok();
+    }
+    Some(unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) }.to_string_lossy().to_string())
+}
+
+/// The user whose session mounted a card under /run/media/<user> or
+/// /media/<user>, when that isn't the current user
+#[cfg(target_os = "linux")]
+pub fn mounted_by_other_user(mount_point: &str) -> Option<String> {
+    let path = Path::new(mount_point);
+    let relative = ["/run/media", "/media"].iter().find_map(|base| path.strip_prefix(base).ok())?;
+    let mut components = relative.components();
+    // /media/CARD, as mounted by fstab or usbmount, has no user part
+    let user = components.next()?.as_os_str().to_string_lossy().to_string();
+    components.next()?;
+    (current_user().as_ref() != Some(&user)).then_some(user)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn mounted_by_other_user(_mount_point: &str) -> Option<String> {
+    None
+}
+
+/// Fail with a pointer to `remount_drive` when the card can't be read at all
+/// because another user's session mounted it
+pub fn ensure_readable(drive_path: &str) -> Result<(), CamPorterError> {
+    if let Err(e) = std::fs::read_dir(drive_path) {
+        if e.kind() == ErrorKind::PermissionDenied {
+            if let Some(user) = mounted_by_other_user(drive_path) {
+                warn!("{} is mounted by {} and can't be read", drive_path, user);
+                return Err(CamPorterError::PermissionDenied {
+                    message: format!("This card was mounted by {}'s session. Remount it for this user to read it.", user),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run(command: &mut Command) -> Result<String, String> {
+    let output = watchdog::output(command, watchdog::MOUNT_TIMEOUT)?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Unmount the card and mount it again through udisks, which mounts it under
+/// /run/media/<user> with the user as owner. Returns the new mount point.
+#[tauri::command]
+pub async fn remount_drive(mount_point: String) -> Result<String, CamPorterError> {
+    if !cfg!(target_os = "linux") {
+        return Err("Remounting is only supported on Linux".into());
+    }
+
+    let disks = Disks::new_with_refreshed_list();
+    let device = disks
+        .iter()
+        .find(|disk| disk.mount_point() == Path::new(&mount_point))
+        .map(|disk| disk.name().to_string_lossy().to_string())
+        .ok_or("Drive not found")?;
+
+    // Unmounting another session's mount asks for authorization through polkit
+    run(Command::new("udisksctl").args(["unmount", "-b", &device])).map_err(|e| {
+        if e.contains("NotAuthorized") {
+            match mounted_by_other_user(&mount_point) {
+                Some(user) => format!("Not authorized to unmount the card {} mounted; an administrator has to approve it", user),
+                None => "Not authorized to unmount the card".to_string(),
+            }
+        } else {
+            format!("Unmount failed: {}", e)
+        }
+    })?;
+    // "Mounted /dev/sdb1 at /run/media/user/CARD"
+    let mounted = run(Command::new("udisksctl").args(["mount", "-b", &device])).map_err(|e| format!("Mount failed: {}", e))?;
+    let new_mount_point = mounted
+        .trim()
+        .split_once(" at ")
+        .map(|(_, at)| at.trim_end_matches('.').to_string())
+        .ok_or(format!("Unexpected udisksctl output: {}", mounted.trim()))?;
+
+    info!("Remounted {} from {} at {}", device, mount_point, new_mount_point);
+    Ok(new_mount_point)
+}
+
+/// Make the denied folders from the last scan readable, asking for an
+/// administrator password through pkexec. Cards on FAT or exFAT take their
+/// permissions from mount options, so those need `remount_drive` instead.
+#[tauri::command]
+pub async fn unlock_protected_folders(drive_path: String, protected: tauri::State<'_, ProtectedFolders>) -> Result<(), CamPorterError> {
+    if !cfg!(target_os = "linux") {
+        return Err("Unlocking folders is only supported on Linux".into());
+    }
+
+    let folders = protected
+        .scans
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&drive_path)
+        .cloned()
+        .unwrap_or_default();
+    if folders.is_empty() {
+        return Ok(());
+    }
+
+    run(Command::new("pkexec").args(["chmod", "-R", "a+rX"]).args(&folders))
+        .map_err(|e| format!("Unlocking protected folders failed: {}", e))?;
+    info!("Unlocked {} protected folders on {}", folders.len(), drive_path);
+    Ok(())
+}
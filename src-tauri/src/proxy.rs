@@ -0,0 +1,76 @@
+// Lightweight editing proxies for imported footage: ffmpeg re-encodes each
+// clip to 720p H.264 next to the original (or into a chosen folder), named
+// after it with a `_proxy` suffix. Encoding is slow, so it's normally left to
+// the scheduler's quiet hours.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{ffmpeg, is_video_extension, longpath, watchdog};
+
+/// Where the proxy of `clip` goes
+fn proxy_path(clip: &Path, output_folder: Option<&Path>) -> Option<PathBuf> {
+    let stem = clip.file_stem()?.to_string_lossy();
+    let name = format!("{}_proxy.mp4", stem);
+    Some(match output_folder {
+        Some(folder) => folder.join(name),
+        None => clip.with_file_name(name),
+    })
+}
+
+/// Encode a proxy of `clip`, written under a hidden name until ffmpeg finishes
+fn encode(clip: &Path, output: &Path) -> Result<(), String> {
+    let ffmpeg_cmd = ffmpeg::ffmpeg_path().ok_or("FFmpeg not found")?;
+    let file_name = output.file_name().ok_or("Invalid proxy path")?.to_string_lossy();
+    let partial = output.with_file_name(format!(".{}", file_name));
+
+    let mut command = Command::new(ffmpeg_cmd);
+    command
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(clip)
+        .args(["-vf", "scale=-2:'min(720,ih)',format=yuv420p", "-c:v", "libx264", "-preset", "veryfast", "-crf", "23"])
+        .args(["-c:a", "aac", "-b:a", "128k", "-movflags", "+faststart"])
+        .arg(&partial);
+    let result = watchdog::output(&mut command, watchdog::ENCODE_TIMEOUT).map_err(|e| e.to_string());
+    match result {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => {
+            let _ = fs::remove_file(&partial);
+            return Err(format!("FFmpeg failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    }
+    fs::rename(&partial, output).map_err(|e| e.to_string())
+}
+
+/// Encode a proxy of every video among `file_paths`, skipping ones that
+/// already have one; errors name the clips that failed
+pub fn generate(file_paths: &[String], output_folder: Option<&str>) -> Result<usize, String> {
+    let output_folder = output_folder.filter(|f| !f.is_empty()).map(|f| longpath::extend(Path::new(f)));
+    if let Some(folder) = &output_folder {
+        fs::create_dir_all(folder).map_err(|e| format!("Failed to create {}: {}", longpath::display(folder), e))?;
+    }
+    let mut encoded = 0;
+    let mut failed = Vec::new();
+    for file_path in file_paths {
+        let clip = longpath::extend(Path::new(file_path));
+        let is_video = clip.extension().and_then(|e| e.to_str()).is_some_and(|e| is_video_extension(&e.to_lowercase()));
+        let Some(output) = proxy_path(&clip, output_folder.as_deref()).filter(|_| is_video) else {
+            continue;
+        };
+        if output.exists() {
+            continue;
+        }
+        match encode(&clip, &output) {
+            Ok(()) => encoded += 1,
+            Err(e) => failed.push(format!("{}: {}", file_path, e)),
+        }
+    }
+    if !failed.is_empty() {
+        return Err(format!("{} of {} proxies failed: {}", failed.len(), encoded + failed.len(), failed.join("; ")));
+    }
+    Ok(encoded)
+}
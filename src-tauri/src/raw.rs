@@ -0,0 +1,105 @@
+// Helpers for camera RAW files, which most image decoders can't open directly
+use image::{DynamicImage, ImageFormat};
+use std::fs;
+use std::path::Path;
+
+/// RAW formats produced by common camera vendors
+pub const RAW_EXTENSIONS: [&str; 11] = ["cr2", "cr3", "nef", "nrw", "arw", "dng", "orf", "rw2", "raf", "pef", "srw"];
+
+pub fn is_raw_file(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => RAW_EXTENSIONS.contains(&ext.to_lowercase().as_str()),
+        None => false,
+    }
+}
+
+/// Open an image with the image crate, decoding the embedded preview for RAW
+/// files since their sensor data can't be decoded directly.
+pub fn open_image(path: &Path) -> Result<DynamicImage, String> {
+    if !is_raw_file(path) {
+        return image::open(path).map_err(|e| e.to_string());
+    }
+
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let jpeg = extract_largest_embedded_jpeg(&data).ok_or("No embedded preview found")?;
+    image::load_from_memory_with_format(jpeg, ImageFormat::Jpeg).map_err(|e| e.to_string())
+}
+
+/// Find the largest JPEG stream embedded in a RAW file.
+///
+/// Every mainstream RAW container (TIFF-based CR2/NEF/ARW/DNG, Fuji RAF and
+/// ISO-BMFF CR3) stores a full-size or near full-size JPEG preview next to the
+/// sensor data, so scanning for complete JPEG streams works across vendors
+/// without parsing each container format.
+pub fn extract_largest_embedded_jpeg(data: &[u8]) -> Option<&[u8]> {
+    let mut largest: Option<&[u8]> = None;
+    let mut pos = 0;
+
+    while pos + 3 < data.len() {
+        if data[pos] == 0xFF && data[pos + 1] == 0xD8 && data[pos + 2] == 0xFF {
+            if let Some(len) = jpeg_stream_length(&data[pos..]) {
+                if largest.is_none_or(|l| len > l.len()) {
+                    largest = Some(&data[pos..pos + len]);
+                }
+                pos += len;
+                continue;
+            }
+        }
+        pos += 1;
+    }
+
+    largest
+}
+
+/// Walk the marker segments of a JPEG starting at SOI and return its total
+/// length, or None if the stream is truncated or malformed.
+fn jpeg_stream_length(data: &[u8]) -> Option<usize> {
+    let mut pos = 2; // Skip SOI
+
+    loop {
+        if pos + 1 >= data.len() || data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+
+        match marker {
+            // Fill byte before a marker
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // EOI
+            0xD9 => return Some(pos + 2),
+            // Standalone markers without a length field
+            0x01 | 0xD0..=0xD7 => {
+                pos += 2;
+                continue;
+            }
+            _ => {}
+        }
+
+        if pos + 3 >= data.len() {
+            return None;
+        }
+        let segment_len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        pos += 2 + segment_len;
+
+        // Start of scan: skip the entropy-coded data up to the next real marker
+        if marker == 0xDA {
+            loop {
+                if pos + 1 >= data.len() {
+                    return None;
+                }
+                if data[pos] == 0xFF {
+                    let next = data[pos + 1];
+                    if next != 0x00 && !(0xD0..=0xD7).contains(&next) {
+                        break;
+                    }
+                    pos += 2;
+                } else {
+                    pos += 1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+// Cards that come back under another mount point mid-session: on Windows a
+// replugged reader can hand the card F: instead of E:, and macOS mounts it at
+// "/Volumes/EOS_DIGITAL 1" while a stale mount still holds the name. Each
+// volume's mount point is tracked by serial as the drive list is built, and
+// paths the UI kept from where a card used to be are translated to where it
+// is now before they're listed, imported or previewed.
+use log::info;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::dto::RemovableDrive;
+
+#[derive(Default)]
+struct Volumes {
+    /// Where each volume is mounted now, by serial
+    current: HashMap<String, PathBuf>,
+    /// Mount points volumes have moved away from, and which volume it was
+    previous: HashMap<PathBuf, String>,
+}
+
+fn volumes() -> &'static Mutex<Volumes> {
+    static VOLUMES: OnceLock<Mutex<Volumes>> = OnceLock::new();
+    VOLUMES.get_or_init(Default::default)
+}
+
+/// Note where each drive with a serial is mounted
+pub fn observe(drives: &[RemovableDrive]) {
+    let Ok(mut volumes) = volumes().lock() else {
+        return;
+    };
+    for drive in drives {
+        let Some(serial) = &drive.serial else {
+            continue;
+        };
+        let mount_point = PathBuf::from(&drive.mount_point);
+        // Paths under a mount point belong to whichever volume is there now
+        volumes.previous.remove(&mount_point);
+        match volumes.current.insert(serial.clone(), mount_point.clone()) {
+            Some(old) if old != mount_point => {
+                info!("Volume {} moved from {} to {}", serial, old.display(), mount_point.display());
+                volumes.previous.insert(old, serial.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `path` under the mount point its volume has now, or as it is if the
+/// volume hasn't moved
+pub fn translate(path: &str) -> String {
+    let Ok(volumes) = volumes().lock() else {
+        return path.to_string();
+    };
+    let original = Path::new(path);
+    volumes
+        .previous
+        .iter()
+        .filter(|(old, _)| original.starts_with(old))
+        .max_by_key(|(old, _)| old.as_os_str().len())
+        .and_then(|(old, serial)| Some(volumes.current.get(serial)?.join(original.strip_prefix(old).ok()?)))
+        .map_or_else(|| path.to_string(), |moved| moved.to_string_lossy().to_string())
+}
+
+pub fn translate_all(paths: &[String]) -> Vec<String> {
+    paths.iter().map(|path| translate(path)).collect()
+}
@@ -0,0 +1,233 @@
+// Filename templates for renaming files on import, e.g.
+// "{date}_{camera}_{seq}" turns DSC_0001.JPG into 2024-06-01_D850_0001.jpg
+use std::path::{Path, PathBuf};
+
+use crate::metadata;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Token {
+    Date,
+    Time,
+    Camera,
+    Orig,
+    Seq,
+}
+
+enum Part {
+    Literal(String),
+    Token(Token),
+}
+
+pub struct RenameTemplate {
+    parts: Vec<Part>,
+}
+
+impl RenameTemplate {
+    /// Parse a template, rejecting unknown tokens and unbalanced braces
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("Unclosed token in template: {}", template)),
+                        }
+                    }
+                    let token = match name.as_str() {
+                        "date" => Token::Date,
+                        "time" => Token::Time,
+                        "camera" => Token::Camera,
+                        "orig" => Token::Orig,
+                        "seq" => Token::Seq,
+                        _ => return Err(format!("Unknown template token: {{{}}}", name)),
+                    };
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Token(token));
+                }
+                '}' => return Err(format!("Unexpected '}}' in template: {}", template)),
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        if parts.is_empty() {
+            return Err("Rename template is empty".to_string());
+        }
+
+        Ok(RenameTemplate { parts })
+    }
+
+    /// Whether the template numbers files, so collisions can be resolved by
+    /// bumping the sequence instead of falling back to the conflict strategy
+    pub fn has_sequence(&self) -> bool {
+        self.parts.iter().any(|p| matches!(p, Part::Token(Token::Seq)))
+    }
+
+    /// The path `path_for` gives for `seq`, bumping the sequence past names
+    /// already taken in the destination, and leaving `seq` at the next number
+    pub fn next_free(&self, seq: &mut u32, path_for: impl Fn(u32) -> PathBuf) -> PathBuf {
+        let mut candidate = path_for(*seq);
+        while self.has_sequence() && candidate.exists() {
+            *seq += 1;
+            candidate = path_for(*seq);
+        }
+        *seq += 1;
+        candidate
+    }
+
+    /// Read the capture metadata for `src`, if any token needs it
+    pub fn capture_info(&self, src: &Path) -> Option<metadata::CaptureInfo> {
+        let needs_metadata = self
+            .parts
+            .iter()
+            .any(|p| matches!(p, Part::Token(Token::Date | Token::Time | Token::Camera)));
+        needs_metadata.then(|| metadata::read_capture_info(src))
+    }
+
+    /// Render the new file name for `src`; the extension is kept (lowercased)
+    pub fn render(&self, src: &Path, info: Option<&metadata::CaptureInfo>, seq: u32) -> String {
+        let mut name = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => name.push_str(text),
+                Part::Token(Token::Date) => {
+                    if let Some(info) = info {
+                        name.push_str(&info.captured.format("%Y-%m-%d").to_string());
+                    }
+                }
+                Part::Token(Token::Time) => {
+                    if let Some(info) = info {
+                        name.push_str(&info.captured.format("%H%M%S").to_string());
+                    }
+                }
+                Part::Token(Token::Camera) => {
+                    let camera = info.and_then(|i| i.camera.as_deref()).unwrap_or("unknown");
+                    name.push_str(camera);
+                }
+                Part::Token(Token::Orig) => {
+                    name.push_str(src.file_stem().and_then(|s| s.to_str()).unwrap_or("unknown"));
+                }
+                Part::Token(Token::Seq) => name.push_str(&format!("{:04}", seq)),
+            }
+        }
+
+        let mut name = sanitize_file_name(&name);
+        if let Some(ext) = src.extension().and_then(|e| e.to_str()) {
+            name.push('.');
+            name.push_str(&ext.to_lowercase());
+        }
+        name
+    }
+}
+
+/// Replace characters that aren't valid in file names on common filesystems
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            ' ' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::fs;
+
+    fn info(camera: Option<&str>) -> metadata::CaptureInfo {
+        metadata::CaptureInfo {
+            captured: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap().and_hms_opt(14, 5, 9).unwrap(),
+            camera: camera.map(str::to_string),
+            exposure_program: None,
+        }
+    }
+
+    fn render(template: &str, src: &str, info: Option<&metadata::CaptureInfo>, seq: u32) -> String {
+        RenameTemplate::parse(template).unwrap().render(Path::new(src), info, seq)
+    }
+
+    #[test]
+    fn renders_each_token() {
+        let info = info(Some("D850"));
+        assert_eq!(render("{date}", "DSC_0001.JPG", Some(&info), 1), "2024-06-01.jpg");
+        assert_eq!(render("{time}", "DSC_0001.JPG", Some(&info), 1), "140509.jpg");
+        assert_eq!(render("{camera}", "DSC_0001.JPG", Some(&info), 1), "D850.jpg");
+        assert_eq!(render("{orig}", "DSC_0001.JPG", Some(&info), 1), "DSC_0001.jpg");
+        assert_eq!(render("{seq}", "DSC_0001.JPG", Some(&info), 7), "0007.jpg");
+        assert_eq!(render("{date}_{camera}_{seq}", "DSC_0001.JPG", Some(&info), 1), "2024-06-01_D850_0001.jpg");
+    }
+
+    #[test]
+    fn renders_without_metadata() {
+        assert_eq!(render("{date}{camera}", "DSC_0001.NEF", None, 1), "unknown.nef");
+        assert_eq!(render("{camera}", "DSC_0001.JPG", Some(&info(None)), 1), "unknown.jpg");
+    }
+
+    #[test]
+    fn keeps_missing_extension_off() {
+        assert_eq!(render("{orig}_{seq}", "README", None, 12), "README_0012");
+    }
+
+    #[test]
+    fn sanitizes_literals_and_values() {
+        assert_eq!(render("a b:c", "x.JPG", Some(&info(Some("EOS R5/II"))), 1), "a_b-c.jpg");
+        assert_eq!(render("{camera}", "x.JPG", Some(&info(Some("EOS R5/II"))), 1), "EOS_R5-II.jpg");
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        assert_eq!(RenameTemplate::parse("{lens}").err().unwrap(), "Unknown template token: {lens}");
+        assert!(RenameTemplate::parse("{date").is_err());
+        assert!(RenameTemplate::parse("date}").is_err());
+        assert!(RenameTemplate::parse("").is_err());
+    }
+
+    #[test]
+    fn only_metadata_tokens_read_metadata() {
+        assert!(RenameTemplate::parse("{orig}_{seq}").unwrap().capture_info(Path::new("missing.jpg")).is_none());
+        assert!(RenameTemplate::parse("{seq}").unwrap().has_sequence());
+        assert!(!RenameTemplate::parse("{date}_{orig}").unwrap().has_sequence());
+    }
+
+    #[test]
+    fn bumps_sequence_past_existing_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = RenameTemplate::parse("shot_{seq}").unwrap();
+        let path_for = |seq| dir.path().join(template.render(Path::new("DSC_0001.JPG"), None, seq));
+        fs::write(dir.path().join("shot_0001.jpg"), b"").unwrap();
+        fs::write(dir.path().join("shot_0002.jpg"), b"").unwrap();
+
+        let mut seq = 1;
+        assert_eq!(template.next_free(&mut seq, path_for), dir.path().join("shot_0003.jpg"));
+        assert_eq!(seq, 4);
+        assert_eq!(template.next_free(&mut seq, path_for), dir.path().join("shot_0004.jpg"));
+        assert_eq!(seq, 5);
+    }
+
+    #[test]
+    fn leaves_collisions_without_sequence_to_the_caller() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = RenameTemplate::parse("{orig}").unwrap();
+        fs::write(dir.path().join("DSC_0001.jpg"), b"").unwrap();
+
+        let mut seq = 1;
+        let path = template.next_free(&mut seq, |seq| dir.path().join(template.render(Path::new("DSC_0001.JPG"), None, seq)));
+        assert_eq!(path, dir.path().join("DSC_0001.jpg"));
+        assert_eq!(seq, 2);
+    }
+}
@@ -0,0 +1,114 @@
+// Holding area for destination files displaced by the overwrite conflict
+// policy: `_replaced/<date>/` next to the file, purged after a set number of
+// days if the user configured a retention period. Files are set aside in
+// whichever folder they were overwritten in, so every holding folder written
+// to is remembered and purged, not only the one in the import's destination.
+use chrono::{Local, NaiveDate};
+use log::{info, warn};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::error::CamPorterError;
+use crate::import::next_available_path;
+
+const REPLACED_DIR: &str = "_replaced";
+pub const RETENTION_KEY: &str = "replaced_retention_days";
+const FOLDERS_KEY: &str = "replaced_folders";
+
+/// Move a file that is about to be overwritten into today's holding folder
+/// and return where it went
+pub fn retain(path: &Path) -> Result<PathBuf, std::io::Error> {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let dir = parent.join(REPLACED_DIR).join(Local::now().format("%Y-%m-%d").to_string());
+    fs::create_dir_all(&dir)?;
+
+    let mut retained = dir.join(path.file_name().unwrap_or_default());
+    if retained.exists() {
+        retained = next_available_path(&retained);
+    }
+    move_file(path, &retained)?;
+    Ok(retained)
+}
+
+/// Rename, falling back to copy and delete across file systems
+pub fn move_file(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+/// Remember the holding folders of files that were set aside, for
+/// `purge_expired` to find
+pub fn remember(app: &tauri::AppHandle, retained: &[&Path]) {
+    if retained.is_empty() {
+        return;
+    }
+    let mut folders: BTreeSet<PathBuf> = config::get(app, FOLDERS_KEY).ok().flatten().unwrap_or_default();
+    let before = folders.len();
+    folders.extend(retained.iter().filter_map(|path| Some(path.parent()?.parent()?.to_path_buf())));
+    if folders.len() != before {
+        if let Err(e) = config::set(app, FOLDERS_KEY, &folders) {
+            warn!("Failed to remember replaced folders: {}", e);
+        }
+    }
+}
+
+/// Delete dated holding folders older than the configured retention from
+/// every holding folder written to, and from the one under `dest`
+pub fn purge_expired(app: &tauri::AppHandle, dest: &Path) {
+    let Some(days) = config::get::<u32>(app, RETENTION_KEY).ok().flatten() else {
+        return;
+    };
+    let mut folders: BTreeSet<PathBuf> = config::get(app, FOLDERS_KEY).ok().flatten().unwrap_or_default();
+    folders.insert(dest.join(REPLACED_DIR));
+    // Holding folders removed since, or on a drive that's gone, are dropped
+    let known = folders.len() - 1;
+    folders.retain(|folder| folder.is_dir());
+    for folder in &folders {
+        purge_folder(folder, days);
+    }
+    if folders.len() < known {
+        if let Err(e) = config::set(app, FOLDERS_KEY, &folders) {
+            warn!("Failed to remember replaced folders: {}", e);
+        }
+    }
+}
+
+/// Delete the dated folders in one holding folder older than `days`
+fn purge_folder(folder: &Path, days: u32) {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return;
+    };
+
+    let today = Local::now().date_naive();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let Some(date) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| NaiveDate::parse_from_str(name, "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+        if (today - date).num_days() > i64::from(days) {
+            info!("Purging replaced files from {}", entry.path().display());
+            if let Err(e) = fs::remove_dir_all(entry.path()) {
+                warn!("Failed to purge {}: {}", entry.path().display(), e);
+            }
+        }
+    }
+}
+
+/// Days to keep replaced files, or None to keep them until removed by hand
+#[tauri::command]
+pub fn get_replaced_retention_days(app: tauri::AppHandle) -> Result<Option<u32>, CamPorterError> {
+    Ok(config::get(&app, RETENTION_KEY)?)
+}
+
+#[tauri::command]
+pub fn set_replaced_retention_days(days: Option<u32>, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    Ok(config::set(&app, RETENTION_KEY, &days)?)
+}
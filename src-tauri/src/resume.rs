@@ -0,0 +1,175 @@
+// Records of imports in flight, so an import cut short by closing the app
+// can be resumed on the next launch
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use crate::error::CamPorterError;
+use crate::import::{part_path, ImportOptions};
+
+#[derive(Serialize, Deserialize)]
+struct ImportRecordData {
+    file_paths: Vec<String>,
+    target_path: String,
+    options: ImportOptions,
+}
+
+/// Where an import had got to: the file index and the destination file it
+/// was writing, which may be incomplete
+#[derive(Serialize, Deserialize, Default)]
+struct Checkpoint {
+    processed_files: usize,
+    current_target: Option<String>,
+}
+
+/// On-disk record of one running import, removed once it ends
+pub struct ImportRecord {
+    path: PathBuf,
+}
+
+impl ImportRecord {
+    /// Write the record for an import that is about to start. The file list
+    /// is written once; only the small checkpoint changes per file.
+    pub fn create(app: &tauri::AppHandle, file_paths: &[String], target_path: &str, options: &ImportOptions) -> Result<Self, String> {
+        let dir = records_dir(app)?;
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let path = dir.join(format!("{}.json", stamp));
+        let data = ImportRecordData {
+            file_paths: file_paths.to_vec(),
+            target_path: target_path.to_string(),
+            options: options.clone(),
+        };
+        fs::write(&path, serde_json::to_string(&data).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+        Ok(ImportRecord { path })
+    }
+
+    /// Note that file `index` is about to be written to `target`
+    pub fn checkpoint(&self, index: usize, target: &Path) {
+        let checkpoint = Checkpoint {
+            processed_files: index,
+            current_target: Some(target.to_string_lossy().to_string()),
+        };
+        if let Ok(json) = serde_json::to_string(&checkpoint) {
+            if let Err(e) = fs::write(self.path.with_extension("checkpoint"), json) {
+                warn!("Failed to write import checkpoint: {}", e);
+            }
+        }
+    }
+
+    pub fn remove(self) {
+        remove_record(&self.path);
+    }
+}
+
+/// An import left unfinished by a previous run
+#[derive(Serialize)]
+pub struct InterruptedImport {
+    pub id: String,
+    pub target_path: String,
+    pub remaining_files: Vec<String>,
+    pub total_files: usize,
+}
+
+/// What the UI needs to start the remaining part of an interrupted import
+#[derive(Serialize)]
+pub struct ResumePlan {
+    pub file_paths: Vec<String>,
+    pub target_path: String,
+    pub options: ImportOptions,
+}
+
+fn records_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(app_data_dir.join("interrupted_imports"))
+}
+
+fn record_path(app: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    // Ids are file stems we handed out; refuse anything that could escape the dir
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid import id: {}", id));
+    }
+    Ok(records_dir(app)?.join(format!("{}.json", id)))
+}
+
+fn remove_record(path: &Path) {
+    let _ = fs::remove_file(path.with_extension("checkpoint"));
+    if let Err(e) = fs::remove_file(path) {
+        warn!("Failed to remove import record {}: {}", path.display(), e);
+    }
+}
+
+fn read_record(path: &Path) -> Result<(ImportRecordData, Checkpoint), String> {
+    let data: ImportRecordData = serde_json::from_str(&fs::read_to_string(path).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+    // No checkpoint means the app closed before the first file started
+    let checkpoint = fs::read_to_string(path.with_extension("checkpoint"))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    Ok((data, checkpoint))
+}
+
+#[tauri::command]
+pub fn list_interrupted_imports(app: tauri::AppHandle) -> Result<Vec<InterruptedImport>, CamPorterError> {
+    let dir = records_dir(&app)?;
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut imports = Vec::new();
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()).map(String::from) else {
+            continue;
+        };
+        match read_record(&path) {
+            Ok((data, checkpoint)) => imports.push(InterruptedImport {
+                id,
+                target_path: data.target_path,
+                total_files: data.file_paths.len(),
+                remaining_files: data.file_paths.into_iter().skip(checkpoint.processed_files).collect(),
+            }),
+            Err(e) => warn!("Ignoring unreadable import record {}: {}", path.display(), e),
+        }
+    }
+    imports.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(imports)
+}
+
+/// Drop the record of an interrupted import and hand back what is left to
+/// copy. The file that was being written is deleted so it isn't mistaken for
+/// a finished copy, unless its source is gone (already moved off the card).
+#[tauri::command]
+pub fn resume_interrupted_import(id: String, app: tauri::AppHandle) -> Result<ResumePlan, CamPorterError> {
+    let path = record_path(&app, &id)?;
+    let (data, checkpoint) = read_record(&path)?;
+
+    let source_present = data.file_paths.get(checkpoint.processed_files).is_some_and(|src| Path::new(src).exists());
+    if let Some(target) = checkpoint.current_target.filter(|_| source_present) {
+        if Path::new(&target).exists() {
+            info!("Removing possibly incomplete file {}", target);
+            fs::remove_file(&target).map_err(|e| e.to_string())?;
+        }
+        let _ = fs::remove_file(part_path(Path::new(&target)));
+    }
+
+    remove_record(&path);
+    Ok(ResumePlan {
+        file_paths: data.file_paths.into_iter().skip(checkpoint.processed_files).collect(),
+        target_path: data.target_path,
+        options: data.options,
+    })
+}
+
+#[tauri::command]
+pub fn discard_interrupted_import(id: String, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    remove_record(&record_path(&app, &id)?);
+    Ok(())
+}
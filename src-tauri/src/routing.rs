@@ -0,0 +1,46 @@
+// Separate destination roots for photos and videos, so one import can send
+// stills to the picture library and footage to a larger disk
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::error::CamPorterError;
+use crate::{config, is_image_extension, is_video_extension};
+
+const ROUTES_KEY: &str = "type_destinations";
+
+/// Roots by media type; a type without one goes to the import's destination
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TypeRoutes {
+    pub photos: Option<String>,
+    pub videos: Option<String>,
+}
+
+impl TypeRoutes {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        config::get(app, ROUTES_KEY).ok().flatten().unwrap_or_default()
+    }
+
+    /// Root a file is routed to, or None to keep the import's destination
+    pub fn route(&self, path: &Path) -> Option<PathBuf> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        let root = if is_image_extension(&ext) {
+            self.photos.as_ref()
+        } else if is_video_extension(&ext) {
+            self.videos.as_ref()
+        } else {
+            None
+        };
+        root.filter(|r| !r.is_empty()).map(PathBuf::from)
+    }
+}
+
+#[tauri::command]
+pub fn get_type_destinations(app: tauri::AppHandle) -> TypeRoutes {
+    TypeRoutes::load(&app)
+}
+
+#[tauri::command]
+pub fn set_type_destinations(routes: TypeRoutes, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    Ok(config::set(&app, ROUTES_KEY, &routes)?)
+}
@@ -0,0 +1,318 @@
+// Media listing: streamed scans that send what they find in chunks while
+// the walk runs, so a card with tens of thousands of files fills the grid
+// from the first second; enriched listings, where capture dates and video
+// durations are extracted by worker threads while the directory walk is
+// still running; and cheap rescans that diff a card against its previous
+// listing
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use log::info;
+use tauri::{Emitter, Manager};
+use ts_rs::TS;
+use walkdir::WalkDir;
+
+use crate::error::CamPorterError;
+use crate::{card_fingerprint, ffmpeg, longpath, media_file, metadata, offload, protected, remap, watchdog, MediaFile};
+
+/// Files waiting for extraction; the walker blocks when workers fall behind
+const PIPELINE_DEPTH: usize = 64;
+
+/// Files sent per `scan-result` event
+const CHUNK_SIZE: usize = 200;
+
+/// Longest between `scan-progress` events while folders turn up no media
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Files found by a running scan since its last chunk
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct ScanChunk {
+    pub scan_id: u64,
+    pub files: Vec<MediaFile>,
+}
+
+/// How far a scan has got; the last one sent has `done` set
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct ScanProgress {
+    pub scan_id: u64,
+    pub files_found: usize,
+    pub folders_scanned: usize,
+    /// The folder being walked, for showing where the scan is
+    pub current_folder: Option<String>,
+    pub done: bool,
+    /// Stopped by `cancel_scan` before the walk was finished
+    pub cancelled: bool,
+}
+
+/// Streamed scans still walking, each with the flag that stops it
+#[derive(Default)]
+pub struct ScanJobs {
+    next_id: AtomicU64,
+    running: Mutex<HashMap<u64, Arc<AtomicBool>>>,
+}
+
+impl ScanJobs {
+    fn register(&self) -> (u64, Arc<AtomicBool>) {
+        let scan_id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        if let Ok(mut running) = self.running.lock() {
+            running.insert(scan_id, Arc::clone(&cancelled));
+        }
+        (scan_id, cancelled)
+    }
+
+    fn finish(&self, scan_id: u64) {
+        if let Ok(mut running) = self.running.lock() {
+            running.remove(&scan_id);
+        }
+    }
+}
+
+/// Stop a streamed scan, as when the user picks another drive, so it
+/// doesn't keep reading the card in the background. Scans that already
+/// finished are left as they are.
+#[tauri::command]
+pub fn cancel_scan(scan_id: u64, jobs: tauri::State<'_, ScanJobs>) -> Result<(), CamPorterError> {
+    let running = jobs.running.lock().map_err(|e| e.to_string())?;
+    if let Some(cancelled) = running.get(&scan_id) {
+        cancelled.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// A drive path as it's scanned: translated to where the card is mounted
+/// now, and checked to be there and readable
+pub fn scan_root(drive_path: &str) -> Result<String, CamPorterError> {
+    let drive_path = remap::translate(drive_path);
+    protected::ensure_readable(&drive_path)?;
+    if !Path::new(&drive_path).exists() {
+        return Err(CamPorterError::not_found("Drive path does not exist"));
+    }
+    Ok(drive_path)
+}
+
+/// Walk each of `roots` on a background thread and return the scan's id.
+/// Files are sent in `scan-result` chunks in the order the walk finds them,
+/// with `scan-progress` events in between; `new_only` leaves out files
+/// already offloaded from the card in an earlier import. Each root's full
+/// listing goes into the `ScanCache` once its walk is done; a cancelled
+/// walk leaves the previous listing there.
+pub fn start(app: &tauri::AppHandle, roots: Vec<String>, new_only: bool) -> u64 {
+    let (scan_id, cancelled) = app.state::<ScanJobs>().register();
+    let app = app.clone();
+    thread::spawn(move || {
+        let started = Instant::now();
+        let mut progress =
+            ScanProgress { scan_id, files_found: 0, folders_scanned: 0, current_folder: None, done: false, cancelled: false };
+        let mut pending = Vec::new();
+        let mut last_sent = Instant::now();
+        for root in roots {
+            let src = Path::new(&root);
+            let history = new_only.then(|| card_fingerprint(&root).map(|fingerprint| offload::load(&app, &fingerprint))).flatten();
+            let mut denied = Vec::new();
+            let mut listing = Vec::new();
+            for entry in WalkDir::new(longpath::extend(src)).into_iter().filter_map(|e| protected::note_denied(e, &mut denied)) {
+                if cancelled.load(Ordering::Relaxed) {
+                    progress.cancelled = true;
+                    break;
+                }
+                if entry.file_type().is_dir() {
+                    progress.folders_scanned += 1;
+                    progress.current_folder = Some(longpath::display(entry.path()));
+                }
+                if let Some(file) = media_file(&entry) {
+                    let offloaded = history.as_ref().is_some_and(|history| {
+                        offload::file_key(Path::new(&file.path), src).is_some_and(|key| history.contains(&key))
+                    });
+                    if !offloaded {
+                        progress.files_found += 1;
+                        pending.push(file.clone());
+                    }
+                    listing.push(file);
+                }
+                if pending.len() >= CHUNK_SIZE || last_sent.elapsed() >= PROGRESS_INTERVAL {
+                    send(&app, scan_id, &mut pending, &progress);
+                    last_sent = Instant::now();
+                }
+            }
+            if progress.cancelled {
+                break;
+            }
+            listing.sort_by_key(|f| std::cmp::Reverse(f.modified));
+            app.state::<ScanCache>().store(&root, &listing);
+            app.state::<protected::ProtectedFolders>().store(&root, denied);
+        }
+        app.state::<ScanJobs>().finish(scan_id);
+        progress.done = true;
+        progress.current_folder = None;
+        // Whatever was found before a cancel still goes out
+        send(&app, scan_id, &mut pending, &progress);
+        if progress.cancelled {
+            info!("Scan {} cancelled after {} files in {:?}", scan_id, progress.files_found, started.elapsed());
+        } else {
+            info!("Scan {} found {} files in {:?}", scan_id, progress.files_found, started.elapsed());
+        }
+    });
+    scan_id
+}
+
+/// Send the files found since the last chunk, if any, then the progress
+fn send(app: &tauri::AppHandle, scan_id: u64, pending: &mut Vec<MediaFile>, progress: &ScanProgress) {
+    if !pending.is_empty() {
+        let _ = app.emit("scan-result", ScanChunk { scan_id, files: std::mem::take(pending) });
+    }
+    let _ = app.emit("scan-progress", progress);
+}
+
+fn enrich(file: &mut MediaFile, ffprobe: Option<&Path>) {
+    let info = metadata::read_capture_info(Path::new(&file.path));
+    file.captured = info.captured.and_local_timezone(Local).earliest().map(|t| t.timestamp());
+
+    if file.is_video {
+        file.duration = ffprobe.and_then(|ffprobe| probe_duration(ffprobe, &file.path));
+    }
+}
+
+fn probe_duration(ffprobe: &Path, path: &str) -> Option<f64> {
+    let mut command = Command::new(ffprobe);
+    command.args(["-v", "error", "-show_entries", "format=duration", "-of", "csv=p=0", path]);
+    let output = watchdog::output(&mut command, watchdog::PROBE_TIMEOUT).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[tauri::command]
+pub async fn list_media_files_enriched(
+    drive_path: String,
+    cache: tauri::State<'_, ScanCache>,
+    protected: tauri::State<'_, protected::ProtectedFolders>,
+) -> Result<Vec<MediaFile>, CamPorterError> {
+    let drive_path = remap::translate(&drive_path);
+    protected::ensure_readable(&drive_path)?;
+    let src = Path::new(&drive_path);
+    if !src.exists() {
+        return Err(CamPorterError::not_found("Drive path does not exist"));
+    }
+
+    let ffprobe = ffmpeg::ffprobe_path();
+    let (work_tx, work_rx) = mpsc::sync_channel::<MediaFile>(PIPELINE_DEPTH);
+    let work_rx = Arc::new(Mutex::new(work_rx));
+    let (done_tx, done_rx) = mpsc::channel::<MediaFile>();
+
+    let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(4).min(8);
+    for _ in 0..workers {
+        let work_rx = Arc::clone(&work_rx);
+        let done_tx = done_tx.clone();
+        let ffprobe = ffprobe.clone();
+        thread::spawn(move || loop {
+            // Hold the lock only while taking the next file
+            let next = work_rx.lock().ok().and_then(|rx| rx.recv().ok());
+            let Some(mut file) = next else {
+                break;
+            };
+            enrich(&mut file, ffprobe.as_deref());
+            if done_tx.send(file).is_err() {
+                break;
+            }
+        });
+    }
+    drop(done_tx);
+
+    let mut denied = Vec::new();
+    for file in WalkDir::new(longpath::extend(src))
+        .into_iter()
+        .filter_map(|e| protected::note_denied(e, &mut denied))
+        .filter_map(|e| media_file(&e))
+    {
+        if work_tx.send(file).is_err() {
+            break;
+        }
+    }
+    drop(work_tx);
+
+    let mut media_files: Vec<MediaFile> = done_rx.iter().collect();
+    media_files.sort_by_key(|f| std::cmp::Reverse(f.modified));
+    cache.store(&drive_path, &media_files);
+    protected.store(&drive_path, denied);
+    Ok(media_files)
+}
+
+/// The last listing of each drive, keyed by path, for `rescan_drive`
+#[derive(Default)]
+pub struct ScanCache {
+    scans: Mutex<HashMap<String, HashMap<String, MediaFile>>>,
+}
+
+impl ScanCache {
+    pub fn store(&self, drive_path: &str, files: &[MediaFile]) {
+        if let Ok(mut scans) = self.scans.lock() {
+            let files = files.iter().map(|f| (f.path.clone(), f.clone())).collect();
+            scans.insert(drive_path.to_string(), files);
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ScanDiff {
+    pub added: Vec<MediaFile>,
+    pub removed: Vec<String>,
+    pub changed: Vec<MediaFile>,
+}
+
+/// Walk the drive again and report only what differs from the previous scan,
+/// judged by size and modification time. With no previous scan every file is
+/// reported as added.
+#[tauri::command]
+pub fn rescan_drive(
+    drive_path: String,
+    cache: tauri::State<'_, ScanCache>,
+    protected: tauri::State<'_, protected::ProtectedFolders>,
+) -> Result<ScanDiff, CamPorterError> {
+    let drive_path = remap::translate(&drive_path);
+    protected::ensure_readable(&drive_path)?;
+    let src = Path::new(&drive_path);
+    if !src.exists() {
+        return Err(CamPorterError::not_found("Drive path does not exist"));
+    }
+
+    let mut denied = Vec::new();
+    let current: HashMap<String, MediaFile> = WalkDir::new(longpath::extend(src))
+        .into_iter()
+        .filter_map(|e| protected::note_denied(e, &mut denied))
+        .filter_map(|e| media_file(&e))
+        .map(|f| (f.path.clone(), f))
+        .collect();
+
+    let mut scans = cache.scans.lock().map_err(|e| e.to_string())?;
+    let previous = scans.remove(&drive_path).unwrap_or_default();
+
+    let mut diff = ScanDiff { added: Vec::new(), removed: Vec::new(), changed: Vec::new() };
+    for (path, file) in &current {
+        match previous.get(path) {
+            None => diff.added.push(file.clone()),
+            Some(old) if old.size != file.size || old.modified != file.modified => diff.changed.push(file.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.removed = previous.into_keys().filter(|path| !current.contains_key(path)).collect();
+
+    diff.added.sort_by_key(|f| std::cmp::Reverse(f.modified));
+    diff.changed.sort_by_key(|f| std::cmp::Reverse(f.modified));
+    diff.removed.sort();
+
+    protected.store(&drive_path, denied);
+    scans.insert(drive_path, current);
+    Ok(diff)
+}
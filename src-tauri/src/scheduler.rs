@@ -0,0 +1,297 @@
+// Deferred heavy jobs that run inside configured quiet-hour windows, or
+// whenever the user triggers them by hand: imports, bit-rot scans of earlier
+// offloads, proxy encoding and cloud uploads. Jobs still waiting are kept in
+// the config so they survive a restart.
+use chrono::{Local, Timelike};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+use crate::config;
+use crate::error::CamPorterError;
+use crate::import::ImportOptions;
+use crate::jobs::JobManager;
+use crate::{proxy, upload, verify};
+
+pub const WINDOWS_KEY: &str = "schedule_windows";
+const PENDING_KEY: &str = "scheduled_jobs";
+
+/// A daily window in local time; `start_hour` > `end_hour` wraps midnight
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TimeWindow {
+    pub start_hour: u32,
+    pub end_hour: u32,
+}
+
+impl TimeWindow {
+    fn contains(&self, hour: u32) -> bool {
+        if self.start_hour <= self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduledTask {
+    Import {
+        file_paths: Vec<String>,
+        target_path: String,
+        options: ImportOptions,
+    },
+    /// Re-read sources and their copies in `destination` to catch bit rot
+    Verify {
+        source_paths: Vec<String>,
+        destination: String,
+    },
+    /// Encode editing proxies of the videos, next to them unless
+    /// `output_folder` is given
+    Proxies {
+        file_paths: Vec<String>,
+        output_folder: Option<String>,
+    },
+    /// Copy the files to an rclone remote such as "dropbox:Photos"
+    Upload {
+        file_paths: Vec<String>,
+        remote: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScheduledJob {
+    pub id: u64,
+    pub task: ScheduledTask,
+    pub status: ScheduledStatus,
+    pub created: u64, // Unix timestamp
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct Scheduler {
+    state: Mutex<SchedulerState>,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    jobs: Vec<ScheduledJob>,
+    next_id: u64,
+}
+
+impl SchedulerState {
+    /// Keep the jobs still to run; one cut off mid-run by quitting runs again
+    fn save(&self, app: &tauri::AppHandle) {
+        let pending: Vec<&ScheduledJob> =
+            self.jobs.iter().filter(|j| matches!(j.status, ScheduledStatus::Pending | ScheduledStatus::Running)).collect();
+        if let Err(e) = config::set(app, PENDING_KEY, &pending) {
+            warn!("Failed to save scheduled jobs: {}", e);
+        }
+    }
+}
+
+impl Scheduler {
+    /// Pick up the jobs left pending when the app last quit
+    fn restore(&self, app: &tauri::AppHandle) {
+        let saved: Vec<ScheduledJob> = config::get(app, PENDING_KEY).ok().flatten().unwrap_or_default();
+        if let Ok(mut state) = self.state.lock() {
+            state.next_id = saved.iter().map(|j| j.id).max().unwrap_or(0);
+            state.jobs = saved.into_iter().map(|job| ScheduledJob { status: ScheduledStatus::Pending, ..job }).collect();
+            if !state.jobs.is_empty() {
+                info!("Restored {} scheduled jobs", state.jobs.len());
+            }
+        }
+    }
+
+    fn add(&self, app: &tauri::AppHandle, task: ScheduledTask) -> Result<u64, String> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        state.next_id += 1;
+        let id = state.next_id;
+        let created = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        state.jobs.push(ScheduledJob { id, task, status: ScheduledStatus::Pending, created, error: None });
+        state.save(app);
+        Ok(id)
+    }
+
+    fn list(&self) -> Result<Vec<ScheduledJob>, String> {
+        Ok(self.state.lock().map_err(|e| e.to_string())?.jobs.clone())
+    }
+
+    fn cancel(&self, app: &tauri::AppHandle, id: u64) -> Result<(), String> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        match state.jobs.iter().position(|j| j.id == id) {
+            Some(index) if state.jobs[index].status == ScheduledStatus::Pending => {
+                state.jobs.remove(index);
+                state.save(app);
+                Ok(())
+            }
+            Some(_) => Err("Only pending jobs can be cancelled".to_string()),
+            None => Err(format!("No scheduled job with id {}", id)),
+        }
+    }
+
+    /// Mark a pending job as running and hand back its task: the given job,
+    /// or the oldest pending one
+    fn claim(&self, id: Option<u64>) -> Option<(u64, ScheduledTask)> {
+        let mut state = self.state.lock().ok()?;
+        let job = state
+            .jobs
+            .iter_mut()
+            .find(|j| j.status == ScheduledStatus::Pending && id.is_none_or(|id| j.id == id))?;
+        job.status = ScheduledStatus::Running;
+        Some((job.id, job.task.clone()))
+    }
+
+    fn finish(&self, app: &tauri::AppHandle, id: u64, result: Result<(), String>) {
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(job) = state.jobs.iter_mut().find(|j| j.id == id) {
+                match result {
+                    Ok(()) => job.status = ScheduledStatus::Completed,
+                    Err(e) => {
+                        job.status = ScheduledStatus::Failed;
+                        job.error = Some(e);
+                    }
+                }
+            }
+            state.save(app);
+        }
+    }
+}
+
+fn execute(app: &tauri::AppHandle, id: u64, task: ScheduledTask) {
+    info!("Running scheduled job {}", id);
+    let result = match task {
+        ScheduledTask::Import { file_paths, target_path, options } => {
+            // Go through the job queue so it doesn't race an import the user started
+            let jobs = app.state::<JobManager>();
+            jobs.enqueue(file_paths, target_path, options).and_then(|job_id| jobs.wait(job_id))
+        }
+        ScheduledTask::Verify { source_paths, destination } => match verify::verify(&source_paths, &destination, app) {
+            Ok(report) if report.verified == source_paths.len() => {
+                info!("Scheduled verification: {}", report.summary);
+                Ok(())
+            }
+            Ok(report) => Err(report.summary),
+            Err(e) => Err(e.to_string()),
+        },
+        ScheduledTask::Proxies { file_paths, output_folder } => proxy::generate(&file_paths, output_folder.as_deref()).map(|encoded| {
+            info!("Encoded {} proxies", encoded);
+        }),
+        ScheduledTask::Upload { file_paths, remote } => upload::upload(&file_paths, &remote).map(|uploaded| {
+            info!("Uploaded {} files to {}", uploaded, remote);
+        }),
+    };
+
+    if let Err(e) = &result {
+        error!("Scheduled job {} failed: {}", id, e);
+    }
+    app.state::<Scheduler>().finish(app, id, result);
+    let _ = app.emit("scheduled-job-finished", id);
+}
+
+/// Start the background loop that runs pending jobs inside quiet hours
+pub fn start(app: tauri::AppHandle) {
+    app.state::<Scheduler>().restore(&app);
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(60));
+
+        let windows: Vec<TimeWindow> = config::get(&app, WINDOWS_KEY).ok().flatten().unwrap_or_default();
+        let hour = Local::now().hour();
+        if !windows.iter().any(|w| w.contains(hour)) {
+            continue;
+        }
+
+        while let Some((id, task)) = app.state::<Scheduler>().claim(None) {
+            execute(&app, id, task);
+        }
+    });
+}
+
+/// Queue an import to run during the next quiet-hour window
+#[tauri::command]
+pub fn schedule_import(
+    file_paths: Vec<String>,
+    target_path: String,
+    options: Option<ImportOptions>,
+    app: tauri::AppHandle,
+) -> Result<u64, CamPorterError> {
+    let task = ScheduledTask::Import { file_paths, target_path, options: options.unwrap_or_default() };
+    Ok(app.state::<Scheduler>().add(&app, task)?)
+}
+
+/// Queue a bit-rot scan of an earlier offload for the next quiet-hour window
+#[tauri::command]
+pub fn schedule_verify(source_paths: Vec<String>, destination: String, app: tauri::AppHandle) -> Result<u64, CamPorterError> {
+    if source_paths.is_empty() {
+        return Err(CamPorterError::invalid_input("No files selected"));
+    }
+    Ok(app.state::<Scheduler>().add(&app, ScheduledTask::Verify { source_paths, destination })?)
+}
+
+/// Queue proxy encoding of the videos among `file_paths` for the next
+/// quiet-hour window
+#[tauri::command]
+pub fn schedule_proxies(file_paths: Vec<String>, output_folder: Option<String>, app: tauri::AppHandle) -> Result<u64, CamPorterError> {
+    if file_paths.is_empty() {
+        return Err(CamPorterError::invalid_input("No files selected"));
+    }
+    Ok(app.state::<Scheduler>().add(&app, ScheduledTask::Proxies { file_paths, output_folder })?)
+}
+
+/// Queue an upload to an rclone remote for the next quiet-hour window
+#[tauri::command]
+pub fn schedule_upload(file_paths: Vec<String>, remote: String, app: tauri::AppHandle) -> Result<u64, CamPorterError> {
+    if file_paths.is_empty() {
+        return Err(CamPorterError::invalid_input("No files selected"));
+    }
+    if remote.trim().is_empty() {
+        return Err(CamPorterError::invalid_input("Choose a remote to upload to"));
+    }
+    Ok(app.state::<Scheduler>().add(&app, ScheduledTask::Upload { file_paths, remote })?)
+}
+
+#[tauri::command]
+pub fn list_scheduled_jobs(scheduler: tauri::State<'_, Scheduler>) -> Result<Vec<ScheduledJob>, CamPorterError> {
+    Ok(scheduler.list()?)
+}
+
+/// Run a pending job now instead of waiting for its window
+#[tauri::command]
+pub fn run_scheduled_job(id: u64, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    let (id, task) = app
+        .state::<Scheduler>()
+        .claim(Some(id))
+        .ok_or(format!("No pending job with id {}", id))?;
+    thread::spawn(move || execute(&app, id, task));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn cancel_scheduled_job(id: u64, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    Ok(app.state::<Scheduler>().cancel(&app, id)?)
+}
+
+#[tauri::command]
+pub fn get_schedule_windows(app: tauri::AppHandle) -> Result<Vec<TimeWindow>, CamPorterError> {
+    Ok(config::get(&app, WINDOWS_KEY)?.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn set_schedule_windows(windows: Vec<TimeWindow>, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    if windows.iter().any(|w| w.start_hour > 23 || w.end_hour > 23) {
+        return Err(CamPorterError::invalid_input("Window hours must be between 0 and 23"));
+    }
+    Ok(config::set(&app, WINDOWS_KEY, &windows)?)
+}
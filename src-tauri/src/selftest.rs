@@ -0,0 +1,169 @@
+// End-to-end check of the import pipeline against a destination, using
+// synthetic files, for validating a new NAS or card reader before a shoot
+use image::{ImageFormat, Rgb, RgbImage};
+use log::info;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::CamPorterError;
+use crate::import::{self, ImportControl, ImportOptions, ImportReport, ProgressReporter};
+use crate::workspace;
+
+/// Size of the synthetic video, large enough to span many copy buffers
+const VIDEO_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Serialize)]
+pub struct StageResult {
+    pub stage: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Serialize)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub stages: Vec<StageResult>,
+}
+
+/// Write a small JPEG and a video-sized file of patterned bytes
+fn create_test_files(dir: &Path) -> Result<Vec<String>, String> {
+    let photo = dir.join("SELFTEST_0001.JPG");
+    RgbImage::from_fn(320, 240, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, 128]))
+        .save_with_format(&photo, ImageFormat::Jpeg)
+        .map_err(|e| e.to_string())?;
+
+    let video = dir.join("SELFTEST_0002.MP4");
+    // An ftyp box up front, so type sniffing takes it for an MP4
+    let mut data = b"\0\0\0\x18ftypmp42\0\0\0\0mp42isom".to_vec();
+    data.extend((data.len()..VIDEO_SIZE).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8));
+    fs::write(&video, data).map_err(|e| e.to_string())?;
+
+    Ok([photo, video].iter().map(|p| p.to_string_lossy().to_string()).collect())
+}
+
+fn options(json: serde_json::Value) -> ImportOptions {
+    serde_json::from_value(json).unwrap_or_default()
+}
+
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_file())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+struct SelfTest<'a> {
+    sources: Vec<String>,
+    progress: ProgressReporter<'a>,
+    control: ImportControl,
+    stages: Vec<StageResult>,
+}
+
+impl SelfTest<'_> {
+    fn import(&self, target: &Path, options: ImportOptions) -> Result<ImportReport, String> {
+        import::run_untracked_import(&self.sources, &target.to_string_lossy(), options, &self.progress, &self.control)
+            .map_err(|e| serde_json::to_string(&e).unwrap_or_else(|_| "Import failed".to_string()))
+    }
+
+    fn stage(&mut self, stage: &str, result: Result<String, String>) -> bool {
+        let passed = result.is_ok();
+        let detail = result.unwrap_or_else(|e| e);
+        info!("Self-test {}: {} ({})", stage, if passed { "passed" } else { "failed" }, detail);
+        self.stages.push(StageResult { stage: stage.to_string(), passed, detail });
+        passed
+    }
+
+    /// Expect every source to have been copied, with no failures
+    fn expect_copied(&self, target: &Path, options: ImportOptions) -> Result<String, String> {
+        let report = self.import(target, options)?;
+        if report.copied != self.sources.len() || report.failed > 0 {
+            return Err(format!("Expected {} files copied: {}", self.sources.len(), report.summary));
+        }
+        Ok(report.summary)
+    }
+
+    fn verify(&self, target: &Path) -> Result<String, String> {
+        for source in &self.sources {
+            let source = Path::new(source);
+            let copy = target.join(source.file_name().unwrap_or_default());
+            let expected = import::hash_file(source).map_err(|e| e.to_string())?;
+            let actual = import::hash_file(&copy).map_err(|e| format!("{}: {}", copy.display(), e))?;
+            if expected != actual {
+                return Err(format!("{} differs from its source", copy.display()));
+            }
+        }
+        Ok(format!("{} files read back identical", self.sources.len()))
+    }
+
+    fn conflict_skip(&self, target: &Path) -> Result<String, String> {
+        let report = self.import(target, options(serde_json::json!({ "conflict_strategy": "skip" })))?;
+        if report.skipped != self.sources.len() || report.copied > 0 {
+            return Err(format!("Expected every file skipped: {}", report.summary));
+        }
+        Ok(report.summary)
+    }
+
+    fn conflict_rename(&self, target: &Path) -> Result<String, String> {
+        self.expect_copied(target, options(serde_json::json!({ "conflict_strategy": "rename" })))?;
+        let names = file_names(target);
+        if names.len() != self.sources.len() * 2 {
+            return Err(format!("Expected renamed copies next to the originals, found {}", names.join(", ")));
+        }
+        Ok(names.join(", "))
+    }
+
+    fn template(&self, target: &Path) -> Result<String, String> {
+        self.expect_copied(target, options(serde_json::json!({ "rename_template": "selftest_{seq}_{orig}" })))?;
+        let names = file_names(target);
+        if names.len() != self.sources.len() || !names.iter().all(|n| n.starts_with("selftest_")) {
+            return Err(format!("Unexpected names after renaming: {}", names.join(", ")));
+        }
+        Ok(names.join(", "))
+    }
+}
+
+/// Copy synthetic files into a scratch folder under `destination`, checking
+/// copying, verification, conflict handling and rename templates in turn.
+/// Nothing is recorded in the import history and the folder is removed after.
+#[tauri::command]
+pub async fn run_self_test(destination: String, app: tauri::AppHandle) -> Result<SelfTestReport, CamPorterError> {
+    let source_dir = workspace::scratch()?;
+    let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let scratch = PathBuf::from(&destination).join(format!("CamPorter self-test {}", started));
+
+    let mut test = SelfTest {
+        sources: Vec::new(),
+        progress: ProgressReporter::new(&app, None),
+        control: ImportControl::default(),
+        stages: Vec::new(),
+    };
+
+    let created = create_test_files(source_dir.path()).map(|sources| {
+        let detail = format!("{} synthetic files", sources.len());
+        test.sources = sources;
+        detail
+    });
+    let mut passed = test.stage("prepare", created)
+        && test.stage("create_destination", fs::create_dir_all(&scratch).map(|_| scratch.display().to_string()).map_err(|e| e.to_string()));
+
+    if passed {
+        let copy = scratch.join("copy");
+        passed &= test.stage("copy", test.expect_copied(&copy, ImportOptions::default()));
+        passed &= test.stage("verify", test.verify(&copy));
+        passed &= test.stage("conflict_skip", test.conflict_skip(&copy));
+        passed &= test.stage("conflict_rename", test.conflict_rename(&copy));
+        passed &= test.stage("template", test.template(&scratch.join("template")));
+        passed &= test.stage("cleanup", fs::remove_dir_all(&scratch).map(|_| "Removed test files".to_string()).map_err(|e| e.to_string()));
+    }
+
+    Ok(SelfTestReport { passed, stages: test.stages })
+}
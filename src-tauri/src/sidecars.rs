@@ -0,0 +1,95 @@
+// Sidecar files cameras and editors write next to media (XMP metadata, THM
+// thumbnails, GoPro LRV proxies, Apple AAE edits and Live Photo clips, DJI
+// SRT telemetry)
+use std::path::{Path, PathBuf};
+
+const SIDECAR_EXTENSIONS: [&str; 5] = ["xmp", "thm", "lrv", "aae", "srt"];
+
+/// Find the sidecars belonging to a media file by probing the names cameras
+/// and editors use, rather than listing the (often huge) DCIM folder
+pub fn find_sidecars(media: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(stem), Some(name)) = (
+        media.parent(),
+        media.file_stem().and_then(|s| s.to_str()),
+        media.file_name().and_then(|n| n.to_str()),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut candidates = Vec::new();
+    for ext in SIDECAR_EXTENSIONS {
+        for ext in [ext.to_string(), ext.to_uppercase()] {
+            candidates.push(format!("{}.{}", stem, ext));
+        }
+    }
+    // darktable and some DAMs append to the full name: DSC_0001.NEF.xmp
+    candidates.push(format!("{}.xmp", name));
+    candidates.push(format!("{}.XMP", name));
+    // GoPro pairs GX010001.MP4 with a GL010001.LRV low-res proxy
+    if let Some(rest) = stem.strip_prefix("GX").or_else(|| stem.strip_prefix("GH")) {
+        candidates.push(format!("GL{}.LRV", rest));
+        candidates.push(format!("GL{}.lrv", rest));
+    }
+    if is_live_photo(media) {
+        candidates.push(format!("{}.MOV", stem));
+        candidates.push(format!("{}.mov", stem));
+    }
+
+    let mut found: Vec<PathBuf> = Vec::new();
+    for candidate in candidates {
+        let path = dir.join(candidate);
+        // Case-insensitive file systems report both spellings of a name
+        let lower = path.to_string_lossy().to_lowercase();
+        if path.is_file() && !found.iter().any(|f| f.to_string_lossy().to_lowercase() == lower) {
+            found.push(path);
+        }
+    }
+    found
+}
+
+/// Whether `media` is named like an iPhone photo, whose Live Photo motion clip
+/// is a MOV with the same stem: IMG_0001.HEIC, or IMG_0001.JPG when the phone
+/// is set to "Most Compatible"
+fn is_live_photo(media: &Path) -> bool {
+    let extension = media.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+    let stem = media.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    match extension.as_deref() {
+        Some("heic") => true,
+        Some("jpg") => stem.starts_with("IMG_"),
+        _ => false,
+    }
+}
+
+/// The photo a Live Photo clip such as IMG_0001.MOV belongs to, if it's there
+pub fn live_photo_of(clip: &Path) -> Option<PathBuf> {
+    if !clip.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("mov")) {
+        return None;
+    }
+    let stem = clip.file_stem()?.to_str()?;
+    ["HEIC", "heic", "JPG", "jpg"]
+        .iter()
+        .map(|ext| clip.with_file_name(format!("{}.{}", stem, ext)))
+        .find(|photo| is_live_photo(photo) && photo.is_file())
+}
+
+/// Name for a sidecar next to the imported media, following any rename
+pub fn target_name(media_src: &Path, sidecar: &Path, media_target: &Path) -> String {
+    let sidecar_name = sidecar.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
+    let src_name = media_src.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let target_name = media_target.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+    if src_name == target_name {
+        return sidecar_name.to_string();
+    }
+
+    // DSC_0001.NEF.xmp follows the full new name
+    if let Some(suffix) = sidecar_name.strip_prefix(src_name).filter(|s| s.starts_with('.')) {
+        return format!("{}{}", target_name, suffix);
+    }
+
+    let target_stem = media_target.file_stem().and_then(|s| s.to_str()).unwrap_or("unknown");
+    match sidecar.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}.{}", target_stem, ext),
+        None => sidecar_name.to_string(),
+    }
+}
@@ -0,0 +1,94 @@
+// Magic-byte sniffing, to catch files whose contents don't match their
+// extension, such as a .JPG that is really a truncated fragment or zeros left
+// by a failing card
+use serde::Serialize;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use ts_rs::TS;
+
+/// A file whose contents disagree with its extension
+#[derive(Serialize, Clone, TS)]
+#[ts(export)]
+pub struct Anomaly {
+    pub path: String,
+    pub extension: String,
+    /// What the contents look like, if recognised
+    pub detected: Option<String>,
+}
+
+/// Recognise a format from the start of a file
+fn detect(header: &[u8]) -> Option<&'static str> {
+    let at = |offset: usize, magic: &[u8]| header.get(offset..offset + magic.len()) == Some(magic);
+
+    if at(0, &[0xFF, 0xD8, 0xFF]) {
+        Some("jpeg")
+    } else if at(0, b"\x89PNG") {
+        Some("png")
+    } else if at(0, b"GIF8") {
+        Some("gif")
+    } else if at(4, b"ftyp") {
+        let brand = header.get(8..12)?;
+        match brand {
+            b"heic" | b"heix" | b"hevc" | b"hevx" | b"mif1" | b"msf1" | b"avif" => Some("heif"),
+            _ => Some("isobmff"),
+        }
+    } else if at(4, b"moov") || at(4, b"mdat") || at(4, b"wide") || at(4, b"free") || at(4, b"skip") {
+        // QuickTime files from older cameras without an ftyp box
+        Some("isobmff")
+    } else if at(0, b"RIFF") && at(8, b"AVI ") {
+        Some("avi")
+    } else if at(0, b"RIFF") && at(8, b"WAVE") {
+        Some("wav")
+    } else if at(0, &[0x1A, 0x45, 0xDF, 0xA3]) {
+        Some("matroska")
+    } else if at(0, b"ID3") || (header.len() >= 2 && header[0] == 0xFF && header[1] & 0xE0 == 0xE0) {
+        Some("mp3")
+    } else {
+        None
+    }
+}
+
+/// Formats acceptable for an extension; MP4 and MOV share a container and
+/// cameras mix them up, so either is accepted for both
+fn expected(extension: &str) -> Option<&'static [&'static str]> {
+    match extension {
+        "jpg" | "jpeg" => Some(&["jpeg"]),
+        "png" => Some(&["png"]),
+        "gif" => Some(&["gif"]),
+        "heic" => Some(&["heif"]),
+        "mp4" | "mov" => Some(&["isobmff"]),
+        "avi" => Some(&["avi"]),
+        "wav" => Some(&["wav"]),
+        "mkv" => Some(&["matroska"]),
+        "mp3" => Some(&["mp3"]),
+        _ => None,
+    }
+}
+
+/// Check a file against its extension. Unknown extensions and unreadable
+/// files aren't reported here; the copy itself surfaces read errors.
+pub fn check(path: &Path) -> Option<Anomaly> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    let expected = expected(&extension)?;
+
+    let mut header = [0u8; 16];
+    let mut file = File::open(path).ok()?;
+    let read = file.read(&mut header).ok()?;
+    let detected = detect(&header[..read]);
+
+    if detected.is_some_and(|d| expected.contains(&d)) {
+        return None;
+    }
+    Some(Anomaly {
+        path: path.to_string_lossy().to_string(),
+        extension,
+        detected: detected.map(str::to_string),
+    })
+}
+
+/// Files among `file_paths` whose contents disagree with their extension
+#[tauri::command]
+pub fn check_file_types(file_paths: Vec<String>) -> Vec<Anomaly> {
+    file_paths.iter().filter_map(|p| check(Path::new(p))).collect()
+}
@@ -0,0 +1,100 @@
+// Deep-zoom style tiling so the preview can pan/zoom large images at full
+// resolution while only the visible tiles are sent to the webview
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::raw;
+
+pub const TILE_SIZE: u32 = 256;
+
+#[derive(Serialize)]
+pub struct TileInfo {
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: u32,
+    pub max_level: u32,
+}
+
+/// Keeps the decoded pyramid of the image currently being viewed, so panning
+/// around doesn't decode the source file again for every tile.
+#[derive(Default)]
+pub struct TileCache {
+    current: Mutex<Option<CachedImage>>,
+}
+
+struct CachedImage {
+    path: String,
+    max_level: u32,
+    levels: HashMap<u32, DynamicImage>,
+}
+
+/// Read the image size and derive its pyramid depth. Other images are read
+/// from their headers alone, but a RAW file has its embedded preview decoded,
+/// since that is what the tiles are cut from
+pub fn tile_info(path: &Path) -> Result<TileInfo, String> {
+    let (width, height) = if raw::is_raw_file(path) {
+        raw::open_image(path)?.dimensions()
+    } else {
+        image::image_dimensions(path).map_err(|e| e.to_string())?
+    };
+
+    Ok(TileInfo {
+        width,
+        height,
+        tile_size: TILE_SIZE,
+        max_level: max_level(width, height),
+    })
+}
+
+/// Deep Zoom level count: level 0 is 1x1, the max level is full resolution
+fn max_level(width: u32, height: u32) -> u32 {
+    let largest = width.max(height).max(1);
+    32 - (largest - 1).leading_zeros()
+}
+
+fn level_dimensions(width: u32, height: u32, level: u32, max_level: u32) -> (u32, u32) {
+    let scale = 1u32 << (max_level - level);
+    (width.div_ceil(scale).max(1), height.div_ceil(scale).max(1))
+}
+
+impl TileCache {
+    pub fn tile(&self, path: &str, level: u32, col: u32, row: u32) -> Result<DynamicImage, String> {
+        let mut current = self.current.lock().map_err(|e| e.to_string())?;
+
+        if current.as_ref().is_none_or(|cached| cached.path != path) {
+            let full = raw::open_image(Path::new(path))?;
+            let (width, height) = full.dimensions();
+            let max_level = max_level(width, height);
+            let mut levels = HashMap::new();
+            levels.insert(max_level, full);
+            *current = Some(CachedImage { path: path.to_string(), max_level, levels });
+        }
+        let cached = current.as_mut().ok_or("Image cache is empty")?;
+
+        if level > cached.max_level {
+            return Err(format!("Level {} exceeds max level {}", level, cached.max_level));
+        }
+
+        if !cached.levels.contains_key(&level) {
+            let full = &cached.levels[&cached.max_level];
+            let (width, height) = full.dimensions();
+            let (level_width, level_height) = level_dimensions(width, height, level, cached.max_level);
+            let scaled = full.resize_exact(level_width, level_height, FilterType::Triangle);
+            cached.levels.insert(level, scaled);
+        }
+        let level_image = &cached.levels[&level];
+        let (level_width, level_height) = level_image.dimensions();
+
+        let x = col * TILE_SIZE;
+        let y = row * TILE_SIZE;
+        if x >= level_width || y >= level_height {
+            return Err(format!("Tile {},{} is out of range for level {}", col, row, level));
+        }
+
+        Ok(level_image.crop_imm(x, y, TILE_SIZE.min(level_width - x), TILE_SIZE.min(level_height - y)))
+    }
+}
@@ -0,0 +1,207 @@
+// Spotting interval-shot stills on a card (a long run at constant spacing with
+// the same exposure program) and assembling them into an MP4 preview with
+// ffmpeg, so a time-lapse can be checked without opening an editor
+use chrono::NaiveDateTime;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use tauri::Emitter;
+
+use crate::error::CamPorterError;
+use crate::{ffmpeg, is_image_extension, metadata, watchdog, workspace};
+
+/// Fewer frames than this are more likely a bracket or a burst
+const MIN_FRAMES: usize = 20;
+/// Exposures closer together are a burst, which EXIF's whole-second
+/// timestamps can't space out anyway
+const MIN_INTERVAL_SECONDS: i64 = 1;
+/// Frame rate of assembled previews
+const PREVIEW_FPS: u32 = 24;
+/// Formats ffmpeg can decode as frames; RAW-only sequences get no preview
+const PREVIEW_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "tif", "tiff"];
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Serialize, Clone)]
+pub struct TimelapseSequence {
+    /// Every file in the sequence, RAW+JPEG pairs included
+    pub files: Vec<String>,
+    /// One decodable file per exposure, in capture order, for the preview
+    pub preview_frames: Vec<String>,
+    pub frame_count: usize,
+    pub interval_seconds: i64,
+    /// Capture times of the first and last frames, in camera-local time
+    /// (ISO 8601 without an offset)
+    pub start: String,
+    pub end: String,
+    pub camera: Option<String>,
+}
+
+/// One exposure, which may have been written as several files
+struct Frame {
+    files: Vec<String>,
+    captured: NaiveDateTime,
+    exposure_program: Option<u16>,
+    camera: Option<String>,
+}
+
+impl Frame {
+    fn preview_file(&self) -> Option<&String> {
+        self.files.iter().find(|f| {
+            let ext = Path::new(f).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+            PREVIEW_EXTENSIONS.contains(&ext.as_str())
+        })
+    }
+}
+
+/// Read every still's capture info, merging files that share a folder and
+/// name (IMG_0001.CR3 and IMG_0001.JPG) into one frame
+fn read_frames(file_paths: &[String]) -> Vec<Frame> {
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut by_stem: HashMap<PathBuf, usize> = HashMap::new();
+    for file_path in file_paths {
+        let path = Path::new(file_path);
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        if !is_image_extension(&ext) {
+            continue;
+        }
+        let stem = path.with_extension("");
+        if let Some(&index) = by_stem.get(&stem) {
+            frames[index].files.push(file_path.clone());
+            continue;
+        }
+        let info = metadata::read_capture_info(path);
+        by_stem.insert(stem, frames.len());
+        frames.push(Frame {
+            files: vec![file_path.clone()],
+            captured: info.captured,
+            exposure_program: info.exposure_program,
+            camera: info.camera,
+        });
+    }
+    frames.sort_by_key(|f| f.captured);
+    frames
+}
+
+fn sequence_of(run: &[Frame], interval_seconds: i64) -> TimelapseSequence {
+    TimelapseSequence {
+        files: run.iter().flat_map(|f| f.files.iter().cloned()).collect(),
+        preview_frames: run.iter().filter_map(|f| f.preview_file().cloned()).collect(),
+        frame_count: run.len(),
+        interval_seconds,
+        start: run[0].captured.format(TIME_FORMAT).to_string(),
+        end: run[run.len() - 1].captured.format(TIME_FORMAT).to_string(),
+        camera: run[0].camera.clone(),
+    }
+}
+
+/// Group the stills among `file_paths` into time-lapse sequences
+pub fn detect(file_paths: &[String]) -> Vec<TimelapseSequence> {
+    let frames = read_frames(file_paths);
+    let mut sequences = Vec::new();
+    let mut start = 0;
+    let mut interval = 0;
+    for i in 1..=frames.len() {
+        let continues = frames.get(i).is_some_and(|frame| {
+            let previous = &frames[i - 1];
+            let gap = (frame.captured - previous.captured).num_seconds();
+            let same_setup = frame.exposure_program == previous.exposure_program && frame.camera == previous.camera;
+            // Timestamps only have whole seconds, so allow a second of drift
+            // (or a tenth of the interval on long ones)
+            let fits = if i - start == 1 {
+                gap >= MIN_INTERVAL_SECONDS
+            } else {
+                (gap - interval).abs() <= (interval / 10).max(1)
+            };
+            same_setup && fits
+        });
+        if continues {
+            if i - start == 1 {
+                interval = (frames[i].captured - frames[start].captured).num_seconds();
+            }
+            continue;
+        }
+        if i - start >= MIN_FRAMES {
+            sequences.push(sequence_of(&frames[start..i], interval));
+        }
+        start = i;
+    }
+    sequences
+}
+
+/// Encode `frames` into an MP4 at `output`, scaled down to at most 1920 wide
+pub fn assemble(frames: &[String], output: &Path) -> Result<(), String> {
+    if frames.len() < 2 {
+        return Err("Not enough JPEG, PNG or TIFF frames for a preview; RAW-only sequences can't be assembled".to_string());
+    }
+    let ffmpeg_cmd = ffmpeg::ffmpeg_path().ok_or("FFmpeg not found")?;
+
+    // The concat demuxer takes the frame list from a file, which avoids both
+    // command-line length limits and renumbering the frames
+    let scratch = workspace::scratch()?;
+    let list_path = scratch.path().join("frames.ffconcat");
+    let duration = 1.0 / PREVIEW_FPS as f64;
+    let mut list = String::from("ffconcat version 1.0\n");
+    for frame in frames {
+        list += &format!("file '{}'\nduration {:.6}\n", frame.replace('\'', "'\\''"), duration);
+    }
+    fs::write(&list_path, list).map_err(|e| format!("Failed to write frame list: {}", e))?;
+
+    let mut command = Command::new(ffmpeg_cmd);
+    command
+        .args(["-y", "-loglevel", "error", "-f", "concat", "-safe", "0", "-i"])
+        .arg(&list_path)
+        .args(["-vf", "scale='min(1920,iw)':-2,format=yuv420p", "-r", &PREVIEW_FPS.to_string(), "-c:v", "libx264"])
+        .arg(output);
+    let result = watchdog::output(&mut command, watchdog::ENCODE_TIMEOUT).map_err(|e| e.to_string())?;
+    if !result.status.success() {
+        return Err(format!("FFmpeg failed: {}", String::from_utf8_lossy(&result.stderr).trim()));
+    }
+    Ok(())
+}
+
+/// Where a sequence's preview goes: next to its first frame, named after it
+fn preview_path(sequence: &TimelapseSequence) -> Option<PathBuf> {
+    let first = Path::new(sequence.preview_frames.first()?);
+    let stem = first.file_stem()?.to_string_lossy();
+    Some(first.with_file_name(format!("{}_timelapse.mp4", stem)))
+}
+
+/// Look for sequences among freshly imported files and assemble a preview of
+/// each in the background, announcing each one as `timelapse-preview-ready`
+pub fn assemble_imported(app: &tauri::AppHandle, imported: Vec<String>) {
+    if imported.len() < MIN_FRAMES {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        for sequence in detect(&imported) {
+            let Some(output) = preview_path(&sequence) else {
+                info!("Skipping preview of {}-frame sequence without JPEG frames", sequence.frame_count);
+                continue;
+            };
+            match assemble(&sequence.preview_frames, &output) {
+                Ok(()) => {
+                    info!("Assembled time-lapse preview {}", output.display());
+                    let _ = app.emit("timelapse-preview-ready", output.to_string_lossy().to_string());
+                }
+                Err(e) => warn!("Failed to assemble time-lapse preview {}: {}", output.display(), e),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn detect_timelapses(file_paths: Vec<String>) -> Vec<TimelapseSequence> {
+    detect(&file_paths)
+}
+
+/// Assemble a detected sequence's preview frames into an MP4 at `output_path`
+#[tauri::command]
+pub async fn assemble_timelapse_preview(frames: Vec<String>, output_path: String) -> Result<String, CamPorterError> {
+    assemble(&frames, Path::new(&output_path))?;
+    Ok(output_path)
+}
@@ -0,0 +1,57 @@
+// Importing only part of a long clip: ffmpeg copies the streams between the
+// in and out points without re-encoding. Stream copy can only start on a
+// keyframe, so the cut begins at the keyframe at or before the in point and
+// nothing that was marked is lost.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use ts_rs::TS;
+
+use crate::{ffmpeg, watchdog};
+
+/// In and out points, in seconds from the start of the clip
+#[derive(Serialize, Deserialize, Clone, Copy, TS)]
+#[ts(export)]
+pub struct ClipTrim {
+    pub start_seconds: f64,
+    pub end_seconds: f64,
+}
+
+/// Write the trimmed range of `src` to `target`, returning the new file's size.
+/// Video and audio are kept; timecode and telemetry tracks are dropped, as
+/// most containers won't take them in a stream copy.
+pub fn extract(src: &Path, target: &Path, trim: ClipTrim) -> Result<u64, String> {
+    if !(trim.start_seconds >= 0.0 && trim.end_seconds > trim.start_seconds) {
+        return Err(format!("Invalid trim {:.3}-{:.3} s", trim.start_seconds, trim.end_seconds));
+    }
+    let ffmpeg_cmd = ffmpeg::ffmpeg_path().ok_or("FFmpeg not found")?;
+
+    // Written under a hidden name until ffmpeg finishes, keeping the extension
+    // so ffmpeg picks the same container
+    let file_name = target.file_name().ok_or("Invalid target path")?.to_string_lossy();
+    let partial = target.with_file_name(format!(".{}", file_name));
+
+    let mut command = Command::new(ffmpeg_cmd);
+    command
+        .args(["-y", "-loglevel", "error", "-ss", &format!("{:.3}", trim.start_seconds), "-i"])
+        .arg(src)
+        .args(["-t", &format!("{:.3}", trim.end_seconds - trim.start_seconds)])
+        .args(["-map", "0:v", "-map", "0:a?", "-c", "copy", "-map_metadata", "0", "-avoid_negative_ts", "make_zero"])
+        .arg(&partial);
+    let result = watchdog::output(&mut command, watchdog::ENCODE_TIMEOUT).map_err(|e| e.to_string());
+    match result {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => {
+            let _ = fs::remove_file(&partial);
+            return Err(format!("FFmpeg failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    }
+
+    fs::rename(&partial, target).map_err(|e| e.to_string())?;
+    fs::metadata(target).map(|m| m.len()).map_err(|e| e.to_string())
+}
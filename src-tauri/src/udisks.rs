@@ -0,0 +1,203 @@
+// Mounting, unmounting and powering off drives through udisks2 over the
+// system D-Bus, the service `udisksctl` itself talks to. Calling it directly
+// gives back udisks' error names, so a busy card is told apart from one the
+// user may not unmount, and lets partitions that were never mounted be
+// found and mounted as the desktop would.
+use std::collections::HashMap;
+use zbus::blocking::{Connection, Proxy};
+use zbus::fdo::ManagedObjects;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+use crate::error::CamPorterError;
+use crate::partitions::UnmountedPartition;
+
+const SERVICE: &str = "org.freedesktop.UDisks2";
+const ROOT: &str = "/org/freedesktop/UDisks2";
+const BLOCK: &str = "org.freedesktop.UDisks2.Block";
+const FILESYSTEM: &str = "org.freedesktop.UDisks2.Filesystem";
+const DRIVE: &str = "org.freedesktop.UDisks2.Drive";
+
+/// Options for udisks' methods; none are needed, but the argument isn't optional
+type Options<'a> = HashMap<&'a str, Value<'a>>;
+
+/// Turn a udisks error into the matching error kind, with what was being done
+fn error(action: &str, error: zbus::Error) -> CamPorterError {
+    let zbus::Error::MethodError(name, detail, _) = &error else {
+        return CamPorterError::from(format!("{} failed: udisks is not available ({})", action, error));
+    };
+    let message = format!("{} failed: {}", action, detail.as_deref().unwrap_or(name.as_str()));
+    match name.as_str().trim_start_matches("org.freedesktop.UDisks2.Error.") {
+        "DeviceBusy" => CamPorterError::busy(message),
+        "NotAuthorized" | "NotAuthorizedCanObtain" | "NotAuthorizedDismissed" => CamPorterError::PermissionDenied { message },
+        "NotMounted" => CamPorterError::not_found(message),
+        _ => CamPorterError::from(message),
+    }
+}
+
+/// A byte string property such as Block.Device, without its trailing NUL
+fn bytestring(value: &OwnedValue) -> Option<String> {
+    let bytes: Vec<u8> = value.try_clone().ok()?.try_into().ok()?;
+    Some(String::from_utf8_lossy(bytes.strip_suffix(&[0]).unwrap_or(&bytes)).to_string())
+}
+
+/// A block device udisks knows about, with the properties of each interface
+struct BlockDevice {
+    path: OwnedObjectPath,
+    interfaces: HashMap<String, HashMap<String, OwnedValue>>,
+}
+
+impl BlockDevice {
+    fn property(&self, interface: &str, name: &str) -> Option<&OwnedValue> {
+        self.interfaces.get(interface)?.get(name)
+    }
+
+    /// Its device node, such as "/dev/sdb1"
+    fn device(&self) -> Option<String> {
+        bytestring(self.property(BLOCK, "Device")?)
+    }
+
+    fn has_filesystem(&self) -> bool {
+        self.interfaces.contains_key(FILESYSTEM)
+    }
+
+    fn flag(&self, interface: &str, name: &str) -> bool {
+        self.property(interface, name).and_then(|v| bool::try_from(v).ok()).unwrap_or(false)
+    }
+
+    fn text(&self, interface: &str, name: &str) -> Option<String> {
+        let text = self.property(interface, name).and_then(|v| <&str>::try_from(v).ok())?;
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    /// The drive the block device is on; "/" for loop devices and the like
+    fn drive(&self) -> Option<OwnedObjectPath> {
+        self.property(BLOCK, "Drive").and_then(|v| v.try_clone().ok()).and_then(|v| v.try_into().ok())
+    }
+
+    /// Where its file system is mounted, if anywhere
+    fn mount_points(&self) -> Vec<String> {
+        let Some(value) = self.property(FILESYSTEM, "MountPoints").and_then(|v| v.try_clone().ok()) else {
+            return Vec::new();
+        };
+        let points: Vec<Vec<u8>> = value.try_into().unwrap_or_default();
+        points.iter().map(|point| String::from_utf8_lossy(point.strip_suffix(&[0]).unwrap_or(point)).to_string()).collect()
+    }
+}
+
+pub struct Udisks {
+    connection: Connection,
+}
+
+impl Udisks {
+    pub fn connect() -> Result<Self, CamPorterError> {
+        let connection = Connection::system().map_err(|e| error("Connecting to the system bus", e))?;
+        Ok(Udisks { connection })
+    }
+
+    fn proxy<'a>(&self, path: &'a OwnedObjectPath, interface: &'a str) -> Result<Proxy<'a>, CamPorterError> {
+        Proxy::new(&self.connection, SERVICE, path.as_ref(), interface).map_err(|e| error("Reaching udisks", e))
+    }
+
+    /// Every object udisks exports: block devices, drives, jobs and so on
+    fn objects(&self) -> Result<Vec<BlockDevice>, CamPorterError> {
+        let manager = Proxy::new(&self.connection, SERVICE, ROOT, "org.freedesktop.DBus.ObjectManager")
+            .map_err(|e| error("Reaching udisks", e))?;
+        let objects: ManagedObjects = manager.call("GetManagedObjects", &()).map_err(|e| error("Listing drives", e))?;
+        Ok(objects
+            .into_iter()
+            .map(|(path, interfaces)| BlockDevice {
+                path,
+                interfaces: interfaces.into_iter().map(|(name, properties)| (name.to_string(), properties)).collect(),
+            })
+            .collect())
+    }
+
+    /// Every block device with a file system on it
+    fn filesystems(&self) -> Result<Vec<BlockDevice>, CamPorterError> {
+        let mut objects = self.objects()?;
+        objects.retain(|block| block.interfaces.contains_key(BLOCK) && block.has_filesystem());
+        Ok(objects)
+    }
+
+    /// File systems on removable drives that nothing has mounted, leaving out
+    /// those udisks is told to hide, such as recovery partitions
+    pub fn unmounted(&self) -> Result<Vec<UnmountedPartition>, CamPorterError> {
+        let objects = self.objects()?;
+        let removable = |drive: &OwnedObjectPath| {
+            objects
+                .iter()
+                .find(|object| &object.path == drive)
+                .is_some_and(|object| object.flag(DRIVE, "Removable") || object.flag(DRIVE, "MediaRemovable"))
+        };
+        Ok(objects
+            .iter()
+            .filter(|block| block.interfaces.contains_key(BLOCK) && block.has_filesystem() && block.mount_points().is_empty())
+            .filter(|block| !block.flag(BLOCK, "HintIgnore") && !block.flag(BLOCK, "HintSystem"))
+            .filter(|block| block.drive().is_some_and(|drive| removable(&drive)))
+            .filter_map(|block| {
+                Some(UnmountedPartition {
+                    device_id: block.device()?,
+                    label: block.text(BLOCK, "IdLabel"),
+                    file_system: block.text(BLOCK, "IdType").unwrap_or_default(),
+                    total_bytes: block.property(BLOCK, "Size").and_then(|v| u64::try_from(v).ok()).unwrap_or(0),
+                })
+            })
+            .collect())
+    }
+
+    fn block(&self, device: &str) -> Result<BlockDevice, CamPorterError> {
+        self.filesystems()?
+            .into_iter()
+            .find(|block| block.device().as_deref() == Some(device))
+            .ok_or_else(|| CamPorterError::not_found(format!("{} is no longer attached", device)))
+    }
+
+    /// Mount `device` where udisks mounts removable media for the user,
+    /// under /run/media or /media, and return the mount point
+    pub fn mount(&self, device: &str) -> Result<String, CamPorterError> {
+        let block = self.block(device)?;
+        if let Some(mount_point) = block.mount_points().into_iter().next() {
+            return Ok(mount_point);
+        }
+        let mount_point = self.proxy(&block.path, FILESYSTEM)?.call("Mount", &(Options::new(),)).map_err(|e| error("Mount", e))?;
+        Ok(mount_point)
+    }
+
+    /// Unmount `device`; forcing unmounts it lazily even while files on it
+    /// are open
+    pub fn unmount(&self, device: &str, force: bool) -> Result<(), CamPorterError> {
+        let block = self.block(device)?;
+        let mut options = Options::new();
+        if force {
+            options.insert("force", Value::from(true));
+        }
+        self.proxy(&block.path, FILESYSTEM)?.call_method("Unmount", &(options,)).map_err(|e| error("Unmount", e))?;
+        Ok(())
+    }
+
+    /// Make a new `kind` file system ("exfat", "vfat") on `device`,
+    /// unmounting it first, and set the partition type to match
+    pub fn format(&self, device: &str, kind: &str, label: &str) -> Result<(), CamPorterError> {
+        let block = self.block(device)?;
+        if !block.mount_points().is_empty() {
+            self.proxy(&block.path, FILESYSTEM)?.call_method("Unmount", &(Options::new(),)).map_err(|e| error("Unmount", e))?;
+        }
+        let mut options = Options::new();
+        options.insert("label", Value::from(label));
+        options.insert("update-partition-type", Value::from(true));
+        self.proxy(&block.path, BLOCK)?.call_method("Format", &(kind, options)).map_err(|e| error("Format", e))?;
+        Ok(())
+    }
+
+    /// Power down the drive holding `device`. udisks refuses while another
+    /// partition on it is still mounted.
+    pub fn power_off(&self, device: &str) -> Result<(), CamPorterError> {
+        let block = self.block(device)?;
+        let drive = block
+            .drive()
+            .filter(|drive| drive.as_str() != "/")
+            .ok_or_else(|| CamPorterError::not_found(format!("No drive found for {}", device)))?;
+        self.proxy(&drive, DRIVE)?.call_method("PowerOff", &(Options::new(),)).map_err(|e| error("Power-off", e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+// Per-card UI state (selection, sort, filters), so reinserting a card picks
+// up where the user left off culling, and which card was picked last, so the
+// UI can go straight to it when it's back
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::dto::RemovableDrive;
+use crate::error::CamPorterError;
+use crate::{card_fingerprint, config};
+
+const UI_STATE_KEY: &str = "drive_ui_state";
+const LAST_USED_KEY: &str = "last_used_drive";
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct DriveUiState {
+    /// Selected files; absolute in the API, stored relative to the mount point
+    pub selection: Vec<String>,
+    pub sort: String,
+    /// Filter settings are owned by the frontend and stored as-is
+    pub filters: serde_json::Value,
+}
+
+#[tauri::command]
+pub fn save_drive_ui_state(mount_point: String, state: DriveUiState, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    let fingerprint = card_fingerprint(&mount_point).ok_or("Drive not found")?;
+    let root = Path::new(&mount_point);
+
+    let selection = state
+        .selection
+        .iter()
+        .filter_map(|p| Path::new(p).strip_prefix(root).ok())
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+
+    let mut states: HashMap<String, DriveUiState> = config::get(&app, UI_STATE_KEY)?.unwrap_or_default();
+    states.insert(fingerprint, DriveUiState { selection, ..state });
+    Ok(config::set(&app, UI_STATE_KEY, &states)?)
+}
+
+#[tauri::command]
+pub fn load_drive_ui_state(mount_point: String, app: tauri::AppHandle) -> Result<Option<DriveUiState>, CamPorterError> {
+    let fingerprint = card_fingerprint(&mount_point).ok_or("Drive not found")?;
+    let root = Path::new(&mount_point);
+
+    let mut states: HashMap<String, DriveUiState> = config::get(&app, UI_STATE_KEY)?.unwrap_or_default();
+    Ok(states.remove(&fingerprint).map(|state| DriveUiState {
+        selection: state
+            .selection
+            .iter()
+            .map(|p| root.join(p).to_string_lossy().to_string())
+            .collect(),
+        ..state
+    }))
+}
+
+/// Remember the card with `serial` as the one picked last
+#[tauri::command]
+pub fn set_last_used_drive(serial: String, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    Ok(config::set(&app, LAST_USED_KEY, &serial)?)
+}
+
+/// Flag the drive picked last, if it's among `drives`
+pub fn mark_last_used(app: &tauri::AppHandle, mut drives: Vec<RemovableDrive>) -> Vec<RemovableDrive> {
+    let Ok(Some(serial)) = config::get::<String>(app, LAST_USED_KEY) else {
+        return drives;
+    };
+    if let Some(drive) = drives.iter_mut().find(|d| d.serial.as_ref() == Some(&serial)) {
+        drive.last_used = true;
+    }
+    drives
+}
@@ -0,0 +1,88 @@
+// Journal of the destructive steps an import takes (source deletes in move
+// mode, renames, overwrites) so the last operation can be reverted
+use log::{info, warn};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::CamPorterError;
+use crate::replaced::move_file;
+
+pub enum UndoAction {
+    /// A source removed after its copy verified; restored from the copy
+    Deleted { path: PathBuf, copy: PathBuf },
+    /// A file written under a new name; renamed back
+    Renamed { original: PathBuf, renamed: PathBuf },
+    /// A destination file replaced by an import; moved back from `_replaced/`
+    Overwritten { path: PathBuf, retained: PathBuf },
+}
+
+impl UndoAction {
+    fn revert(&self) -> Result<(), String> {
+        match self {
+            UndoAction::Deleted { path, copy } => {
+                if path.exists() {
+                    return Err(format!("{} already exists", path.display()));
+                }
+                fs::copy(copy, path).map(|_| ()).map_err(|e| format!("Failed to restore {}: {}", path.display(), e))
+            }
+            UndoAction::Renamed { original, renamed } => {
+                if original.exists() {
+                    return Err(format!("{} already exists", original.display()));
+                }
+                fs::rename(renamed, original).map_err(|e| format!("Failed to rename {} back: {}", renamed.display(), e))
+            }
+            UndoAction::Overwritten { path, retained } => {
+                move_file(retained, path).map_err(|e| format!("Failed to restore {}: {}", path.display(), e))
+            }
+        }
+    }
+}
+
+struct Operation {
+    description: String,
+    actions: Vec<UndoAction>,
+}
+
+#[derive(Default)]
+pub struct UndoJournal {
+    operations: Mutex<Vec<Operation>>,
+}
+
+impl UndoJournal {
+    /// Record a finished operation; operations with nothing to undo are dropped
+    pub fn record(&self, description: String, actions: Vec<UndoAction>) {
+        if actions.is_empty() {
+            return;
+        }
+        if let Ok(mut operations) = self.operations.lock() {
+            operations.push(Operation { description, actions });
+        }
+    }
+}
+
+/// Revert every step of the most recent operation, newest first
+#[tauri::command]
+pub fn undo_last_operation(journal: tauri::State<'_, UndoJournal>) -> Result<String, CamPorterError> {
+    let operation = journal
+        .operations
+        .lock()
+        .map_err(|e| e.to_string())?
+        .pop()
+        .ok_or("Nothing to undo")?;
+
+    info!("Undoing: {}", operation.description);
+    let errors: Vec<String> = operation
+        .actions
+        .iter()
+        .rev()
+        .filter_map(|action| action.revert().err())
+        .collect();
+
+    if errors.is_empty() {
+        Ok(operation.description)
+    } else {
+        warn!("Undo of '{}' was incomplete: {}", operation.description, errors.join("; "));
+        Err(format!("Some changes could not be undone: {}", errors.join("; ")).into())
+    }
+}
@@ -0,0 +1,35 @@
+// Uploads of imported files to cloud storage through rclone, which already
+// knows how to talk to most providers and keeps its own credentials. The
+// remote is anything rclone accepts as a destination, such as
+// "dropbox:Photos/2024"; each file goes there under its own name.
+use std::path::Path;
+use std::process::Command;
+use which::which;
+
+use crate::{longpath, watchdog};
+
+/// Copy each of `file_paths` to `remote`; errors name the files that failed
+pub fn upload(file_paths: &[String], remote: &str) -> Result<usize, String> {
+    let rclone = which("rclone").map_err(|_| "rclone is not installed".to_string())?;
+    let remote = remote.trim_end_matches('/');
+    let mut uploaded = 0;
+    let mut failed = Vec::new();
+    for file_path in file_paths {
+        let path = longpath::extend(Path::new(file_path));
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        let target = format!("{}/{}", remote, name.to_string_lossy());
+        let mut command = Command::new(&rclone);
+        command.arg("copyto").arg(&path).arg(&target);
+        match watchdog::output(&mut command, watchdog::DEVICE_TRANSFER_TIMEOUT) {
+            Ok(output) if output.status.success() => uploaded += 1,
+            Ok(output) => failed.push(format!("{}: {}", file_path, String::from_utf8_lossy(&output.stderr).trim())),
+            Err(e) => failed.push(format!("{}: {}", file_path, e)),
+        }
+    }
+    if !failed.is_empty() {
+        return Err(format!("{} of {} uploads failed: {}", failed.len(), uploaded + failed.len(), failed.join("; ")));
+    }
+    Ok(uploaded)
+}
@@ -0,0 +1,168 @@
+// Double-checking an earlier offload before the card is formatted: each
+// source is re-read and hashed along with its copy in the destination, and
+// nothing is copied. Copies are found by the name they'd have been imported
+// under anywhere in the destination, so ones in recreated card folders count,
+// and through the catalog for ones a rename template gave a new name.
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager};
+use ts_rs::TS;
+use walkdir::WalkDir;
+
+use crate::catalog::Catalog;
+use crate::error::CamPorterError;
+use crate::import::hash_file;
+use crate::normalize::NameForm;
+use crate::{humanize, longpath};
+
+#[derive(Serialize, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+    /// A copy with the same contents is in the destination
+    Verified,
+    /// Copies were found, but none matches the source
+    Mismatch,
+    /// No copy was found in the destination
+    Missing,
+    /// The source couldn't be read
+    Unreadable,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct VerifiedFile {
+    pub source: String,
+    /// The matching copy, or the closest candidate on a mismatch
+    pub copy: Option<String>,
+    pub status: VerifyStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Default, TS)]
+#[ts(export)]
+pub struct VerifyReport {
+    pub verified: usize,
+    pub mismatched: usize,
+    pub missing: usize,
+    pub unreadable: usize,
+    /// Source bytes confirmed to be safely copied
+    pub bytes: u64,
+    pub summary: String,
+    pub files: Vec<VerifiedFile>,
+}
+
+#[derive(Serialize, Clone)]
+struct VerifyProgress {
+    processed: usize,
+    total: usize,
+    current_file: String,
+}
+
+/// Files in the destination by name
+fn index_destination(destination: &Path) -> HashMap<String, Vec<PathBuf>> {
+    let mut index: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for entry in WalkDir::new(destination).into_iter().filter_map(|e| e.ok()) {
+        if entry.file_type().is_file() {
+            index.entry(entry.file_name().to_string_lossy().to_string()).or_default().push(entry.into_path());
+        }
+    }
+    index
+}
+
+/// Hash `source` and look for a copy with the same contents
+fn verify_file(
+    source: &Path,
+    destination: &Path,
+    index: &HashMap<String, Vec<PathBuf>>,
+    catalog: &Catalog,
+    app: &tauri::AppHandle,
+) -> (VerifiedFile, u64) {
+    let file = |copy: Option<&Path>, status, error: Option<String>| VerifiedFile {
+        source: source.to_string_lossy().to_string(),
+        copy: copy.map(longpath::display),
+        status,
+        error,
+    };
+    let (size, hash) = match source.metadata().and_then(|m| Ok((m.len(), hash_file(source)?))) {
+        Ok(read) => read,
+        Err(e) => return (file(None, VerifyStatus::Unreadable, Some(e.to_string())), 0),
+    };
+
+    // Imports write names in NFC by default, but may have kept the card's
+    let name = source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let mut names = vec![NameForm::Nfc.apply(&name), NameForm::Nfd.apply(&name), name];
+    names.dedup();
+    let mut candidates: Vec<PathBuf> = names.iter().filter_map(|name| index.get(name)).flatten().cloned().collect();
+    if let Some(entry) = catalog.find(app, size, || Some(hash)) {
+        let cataloged = longpath::extend(Path::new(&entry.destination));
+        if cataloged.starts_with(destination) {
+            candidates.push(cataloged);
+        }
+    }
+    candidates.sort();
+    candidates.dedup();
+
+    let mut mismatch = None;
+    for candidate in &candidates {
+        if candidate.metadata().map(|m| m.len()).ok() != Some(size) {
+            mismatch.get_or_insert((candidate, "size differs".to_string()));
+            continue;
+        }
+        match hash_file(candidate) {
+            Ok(copy_hash) if copy_hash == hash => return (file(Some(candidate), VerifyStatus::Verified, None), size),
+            Ok(_) => mismatch = Some((candidate, "contents differ".to_string())),
+            Err(e) => mismatch = Some((candidate, e.to_string())),
+        }
+    }
+    match mismatch {
+        Some((copy, reason)) => (file(Some(copy), VerifyStatus::Mismatch, Some(reason)), 0),
+        None => (file(None, VerifyStatus::Missing, None), 0),
+    }
+}
+
+/// Re-read every source and its copy in `destination` and compare their
+/// hashes, announcing each file as `verify-progress`
+#[tauri::command]
+pub async fn verify_import(source_paths: Vec<String>, destination: String, app: tauri::AppHandle) -> Result<VerifyReport, CamPorterError> {
+    verify(&source_paths, &destination, &app)
+}
+
+/// What `verify_import` does, for scheduled bit-rot scans too
+pub fn verify(source_paths: &[String], destination: &str, app: &tauri::AppHandle) -> Result<VerifyReport, CamPorterError> {
+    let destination = longpath::extend(Path::new(destination));
+    if !destination.is_dir() {
+        return Err(CamPorterError::not_found("Destination folder does not exist"));
+    }
+    let index = index_destination(&destination);
+    let catalog = app.state::<Catalog>();
+
+    let mut report = VerifyReport::default();
+    for (i, source) in source_paths.iter().enumerate() {
+        let _ = app.emit(
+            "verify-progress",
+            VerifyProgress { processed: i, total: source_paths.len(), current_file: source.clone() },
+        );
+        let (file, bytes) = verify_file(Path::new(source), &destination, &index, &catalog, app);
+        match file.status {
+            VerifyStatus::Verified => report.verified += 1,
+            VerifyStatus::Mismatch => report.mismatched += 1,
+            VerifyStatus::Missing => report.missing += 1,
+            VerifyStatus::Unreadable => report.unreadable += 1,
+        }
+        report.bytes += bytes;
+        report.files.push(file);
+    }
+
+    report.summary = format!(
+        "{} of {} files verified ({}), {} mismatched, {} missing, {} unreadable",
+        report.verified,
+        source_paths.len(),
+        humanize::bytes(report.bytes),
+        report.mismatched,
+        report.missing,
+        report.unreadable
+    );
+    Ok(report)
+}
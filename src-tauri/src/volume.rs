@@ -0,0 +1,634 @@
+// What the OS says about a mounted volume beyond sysinfo's disk list, which
+// has sizes and the file system but not how the volume is mounted or what
+// it's called: the label and serial number that identify a card whichever
+// reader or mount point it turns up in, whether the card's lock switch is
+// set, the disk it's on and how that's attached, and on Windows whether a
+// drive letter is on removable media and a lock that keeps a card from being
+// ejected while it's read
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use ts_rs::TS;
+
+/// A volume's own name and serial, as written when it was formatted, and the
+/// disk it's on
+#[derive(Clone, Default)]
+pub struct VolumeId {
+    pub label: Option<String>,
+    /// The FAT/exFAT serial, such as "1A2B-3C4D", or the file system UUID
+    pub serial: Option<String>,
+    /// None when the OS doesn't say
+    pub bus: Option<Bus>,
+    /// The whole disk the volume is a partition of, which every partition on
+    /// the same card shares
+    pub disk: Option<String>,
+}
+
+/// How a volume's disk is attached, as the OS reports it
+#[derive(Serialize, Clone, Copy, PartialEq, Debug, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Bus {
+    Usb,
+    /// An SD or MMC slot, built into the computer or not
+    Sd,
+    Firewire,
+    Thunderbolt,
+    /// SATA, NVMe and the like inside the computer
+    Internal,
+    /// Disk images, loop devices and RAM disks
+    Virtual,
+}
+
+impl Bus {
+    /// Whether disks on it can be unplugged: cards and external drives
+    pub fn is_external(self) -> bool {
+        matches!(self, Bus::Usb | Bus::Sd | Bus::Firewire | Bus::Thunderbolt)
+    }
+}
+
+/// Whether the volume at `mount_point` is mounted read-only
+#[cfg(unix)]
+pub fn is_read_only(mount_point: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = CString::new(mount_point.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+        return false;
+    }
+    stats.f_flag & libc::ST_RDONLY != 0
+}
+
+/// Whether the card's lock switch is set, which the SD reader reports to the
+/// kernel as write protection on the whole disk and, in turn, its partitions
+#[cfg(target_os = "linux")]
+pub fn is_write_protected(device: &str, _mount_point: &Path) -> bool {
+    let Some(name) = Path::new(device).file_name() else {
+        return false;
+    };
+    let Ok(partition) = std::fs::canonicalize(Path::new("/sys/class/block").join(name)) else {
+        return false;
+    };
+    let read_only = |dir: &Path| std::fs::read_to_string(dir.join("ro")).is_ok_and(|ro| ro.trim() == "1");
+    // A partition's directory sits inside its disk's
+    read_only(&partition) || partition.parent().is_some_and(read_only)
+}
+
+/// The bus of the disk holding `device`: udev's ID_BUS where it set one, else
+/// the controller the kernel's device path runs through, such as
+/// .../usb2/2-1/... or .../mmc_host/mmc0/...
+#[cfg(target_os = "linux")]
+fn bus(device: &str) -> Option<Bus> {
+    let name = Path::new(device).file_name()?;
+    let sys = std::fs::canonicalize(Path::new("/sys/class/block").join(name)).ok()?;
+    // udev keeps what it learned about each device by major:minor number
+    let number = std::fs::read_to_string(sys.join("dev")).ok()?;
+    let udev = std::fs::read_to_string(format!("/run/udev/data/b{}", number.trim())).unwrap_or_default();
+    let property = |key: &str| udev.lines().find_map(|line| line.strip_prefix("E:")?.strip_prefix(key)?.strip_prefix('='));
+    match property("ID_BUS") {
+        Some("usb") => return Some(Bus::Usb),
+        Some("ieee1394") => return Some(Bus::Firewire),
+        _ => {}
+    }
+    let path = sys.to_string_lossy();
+    if path.contains("/virtual/block/dm-") {
+        // Device-mapper, as for an encrypted volume, hides the disk underneath
+        None
+    } else if path.contains("/virtual/") {
+        Some(Bus::Virtual)
+    } else if path.contains("/usb") {
+        Some(Bus::Usb)
+    } else if path.contains("/mmc_host/") || path.contains("/memstick") {
+        Some(Bus::Sd)
+    } else if path.contains("/firewire") {
+        Some(Bus::Firewire)
+    } else if path.contains("/nvme/") || path.contains("/ata") || path.contains("/virtio") {
+        Some(Bus::Internal)
+    } else {
+        None
+    }
+}
+
+/// The whole disk `device` is a partition of, such as "/dev/sdb" for
+/// "/dev/sdb1", or `device` itself when it isn't a partition
+#[cfg(target_os = "linux")]
+fn whole_disk(device: &str) -> Option<String> {
+    let name = Path::new(device).file_name()?;
+    let sys = std::fs::canonicalize(Path::new("/sys/class/block").join(name)).ok()?;
+    let disk = if sys.join("partition").exists() { sys.parent()? } else { &sys };
+    Some(format!("/dev/{}", disk.file_name()?.to_string_lossy()))
+}
+
+/// macOS mounts a locked card read-only, which `is_read_only` already sees
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn is_write_protected(_device: &str, _mount_point: &Path) -> bool {
+    false
+}
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    use std::ffi::c_void;
+
+    pub const FILE_READ_ONLY_VOLUME: u32 = 0x0008_0000;
+    pub const MAX_PATH: usize = 260;
+    pub const DRIVE_REMOVABLE: u32 = 2;
+    pub const FILE_SHARE_READ: u32 = 0x0000_0001;
+    pub const FILE_SHARE_WRITE: u32 = 0x0000_0002;
+    pub const GENERIC_READ: u32 = 0x8000_0000;
+    pub const OPEN_EXISTING: u32 = 3;
+    pub const IOCTL_DISK_IS_WRITABLE: u32 = 0x0007_0024;
+    pub const ERROR_WRITE_PROTECT: i32 = 19;
+    pub const IOCTL_STORAGE_QUERY_PROPERTY: u32 = 0x002D_1400;
+    pub const IOCTL_STORAGE_GET_DEVICE_NUMBER: u32 = 0x002D_1080;
+    pub const IOCTL_STORAGE_MEDIA_REMOVAL: u32 = 0x002D_4804;
+    pub const STORAGE_DEVICE_PROPERTY: u32 = 0;
+    pub const PROPERTY_STANDARD_QUERY: u32 = 0;
+    pub const BUS_TYPE_SCSI: u32 = 0x1;
+    pub const BUS_TYPE_ATAPI: u32 = 0x2;
+    pub const BUS_TYPE_ATA: u32 = 0x3;
+    pub const BUS_TYPE_1394: u32 = 0x4;
+    pub const BUS_TYPE_USB: u32 = 0x7;
+    pub const BUS_TYPE_RAID: u32 = 0x8;
+    pub const BUS_TYPE_SAS: u32 = 0xA;
+    pub const BUS_TYPE_SATA: u32 = 0xB;
+    pub const BUS_TYPE_SD: u32 = 0xC;
+    pub const BUS_TYPE_MMC: u32 = 0xD;
+    pub const BUS_TYPE_VIRTUAL: u32 = 0xE;
+    pub const BUS_TYPE_FILE_BACKED_VIRTUAL: u32 = 0xF;
+    pub const BUS_TYPE_SPACES: u32 = 0x10;
+    pub const BUS_TYPE_NVME: u32 = 0x11;
+    pub const INVALID_HANDLE_VALUE: *mut c_void = -1isize as *mut c_void;
+
+    #[repr(C)]
+    pub struct StoragePropertyQuery {
+        pub property_id: u32,
+        pub query_type: u32,
+        pub additional_parameters: [u8; 1],
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    pub struct StorageDeviceNumber {
+        pub device_type: u32,
+        pub device_number: u32,
+        pub partition_number: u32,
+    }
+
+    /// The fixed part of STORAGE_DEVICE_DESCRIPTOR; vendor and product
+    /// strings follow it
+    #[repr(C)]
+    #[derive(Default)]
+    pub struct StorageDeviceDescriptor {
+        pub version: u32,
+        pub size: u32,
+        pub device_type: u8,
+        pub device_type_modifier: u8,
+        pub removable_media: u8,
+        pub command_queueing: u8,
+        pub vendor_id_offset: u32,
+        pub product_id_offset: u32,
+        pub product_revision_offset: u32,
+        pub serial_number_offset: u32,
+        pub bus_type: u32,
+        pub raw_properties_length: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn CreateFileW(
+            file_name: *const u16,
+            desired_access: u32,
+            share_mode: u32,
+            security_attributes: *mut c_void,
+            creation_disposition: u32,
+            flags_and_attributes: u32,
+            template_file: *mut c_void,
+        ) -> *mut c_void;
+        pub fn DeviceIoControl(
+            device: *mut c_void,
+            io_control_code: u32,
+            in_buffer: *mut c_void,
+            in_buffer_size: u32,
+            out_buffer: *mut c_void,
+            out_buffer_size: u32,
+            bytes_returned: *mut u32,
+            overlapped: *mut c_void,
+        ) -> i32;
+        pub fn CloseHandle(handle: *mut c_void) -> i32;
+        pub fn GetLogicalDrives() -> u32;
+        pub fn GetDriveTypeW(root_path_name: *const u16) -> u32;
+        pub fn GetVolumeInformationW(
+            root_path_name: *const u16,
+            volume_name_buffer: *mut u16,
+            volume_name_size: u32,
+            volume_serial_number: *mut u32,
+            maximum_component_length: *mut u32,
+            file_system_flags: *mut u32,
+            file_system_name_buffer: *mut u16,
+            file_system_name_size: u32,
+        ) -> i32;
+    }
+
+    /// An open volume such as `\\.\E:`, closed when dropped
+    pub struct Volume(pub *mut c_void);
+
+    impl Volume {
+        /// Opened without read or write access, which is enough to query it
+        pub fn open(mount_point: &std::path::Path) -> Option<Volume> {
+            Self::open_with(mount_point, 0)
+        }
+
+        pub fn open_with(mount_point: &std::path::Path, access: u32) -> Option<Volume> {
+            let letter = mount_point.to_string_lossy().trim_end_matches('\\').to_string();
+            let path: Vec<u16> = format!("\\\\.\\{}", letter).encode_utf16().chain(Some(0)).collect();
+            let handle = unsafe {
+                CreateFileW(
+                    path.as_ptr(),
+                    access,
+                    FILE_SHARE_READ | FILE_SHARE_WRITE,
+                    std::ptr::null_mut(),
+                    OPEN_EXISTING,
+                    0,
+                    std::ptr::null_mut(),
+                )
+            };
+            (handle != INVALID_HANDLE_VALUE).then_some(Volume(handle))
+        }
+    }
+
+    impl Drop for Volume {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    /// Label, serial number and file system flags of the volume at a root
+    /// such as `E:\`
+    pub fn volume_information(mount_point: &std::path::Path) -> Option<(String, u32, u32)> {
+        use std::os::windows::ffi::OsStrExt;
+
+        let root: Vec<u16> = mount_point.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut name = [0u16; MAX_PATH + 1];
+        let (mut serial, mut flags) = (0, 0);
+        let ok = unsafe {
+            GetVolumeInformationW(
+                root.as_ptr(),
+                name.as_mut_ptr(),
+                name.len() as u32,
+                &mut serial,
+                std::ptr::null_mut(),
+                &mut flags,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        if ok == 0 {
+            return None;
+        }
+        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        Some((String::from_utf16_lossy(&name[..len]), serial, flags))
+    }
+}
+
+/// Whether the volume at a root such as `E:\` is on removable media: a card
+/// reader or USB stick, but not a USB hard disk or SSD, which Windows treats
+/// as fixed
+#[cfg(target_os = "windows")]
+pub fn is_removable_media(mount_point: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+
+    let root: Vec<u16> = mount_point.as_os_str().encode_wide().chain(Some(0)).collect();
+    unsafe { win32::GetDriveTypeW(root.as_ptr()) == win32::DRIVE_REMOVABLE }
+}
+
+/// Bit 0 for A:, bit 1 for B: and so on, set for each drive letter with a
+/// volume on it. An empty card reader slot keeps its letter but has no
+/// volume, so inserting a card changes this where the letters don't.
+#[cfg(target_os = "windows")]
+pub fn mounted_drives() -> u32 {
+    let drives = unsafe { win32::GetLogicalDrives() };
+    (0..26u8)
+        .filter(|letter| drives & (1 << letter) != 0)
+        .filter(|letter| {
+            let root = PathBuf::from(format!("{}:\\", (b'A' + letter) as char));
+            !is_removable_media(&root) || win32::volume_information(&root).is_some()
+        })
+        .fold(0, |mask, letter| mask | 1 << letter)
+}
+
+/// Keeps the cards at the given mount points from being ejected or pulled
+/// while an import reads them, released when dropped. On Windows the open
+/// volume handle makes Explorer's Eject report the card as in use, and
+/// readers that can lock their media are told to. Elsewhere it does nothing.
+pub struct RemovalLock {
+    #[cfg(target_os = "windows")]
+    volumes: Vec<win32::Volume>,
+}
+
+impl RemovalLock {
+    #[cfg(target_os = "windows")]
+    pub fn acquire(mount_points: &[String]) -> Self {
+        let volumes = mount_points
+            .iter()
+            .filter_map(|mount_point| {
+                let mount_point = Path::new(mount_point);
+                // Locking the media needs read access; the handle alone still vetoes Eject
+                let Some(volume) = win32::Volume::open_with(mount_point, win32::GENERIC_READ).or_else(|| win32::Volume::open(mount_point)) else {
+                    log::warn!("Failed to lock {} against removal", mount_point.display());
+                    return None;
+                };
+                prevent_media_removal(&volume, true);
+                Some(volume)
+            })
+            .collect();
+        RemovalLock { volumes }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn acquire(_mount_points: &[String]) -> Self {
+        RemovalLock {}
+    }
+
+    /// Let the cards go before the lock would otherwise be dropped
+    pub fn release(self) {}
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for RemovalLock {
+    fn drop(&mut self) {
+        // Windows also drops a handle's media locks when it's closed
+        for volume in &self.volumes {
+            prevent_media_removal(volume, false);
+        }
+    }
+}
+
+/// Lock or unlock the media in the reader; many card readers don't support it
+#[cfg(target_os = "windows")]
+fn prevent_media_removal(volume: &win32::Volume, prevent: bool) {
+    let mut prevent = u8::from(prevent);
+    let mut returned = 0;
+    unsafe {
+        win32::DeviceIoControl(
+            volume.0,
+            win32::IOCTL_STORAGE_MEDIA_REMOVAL,
+            (&mut prevent as *mut u8).cast(),
+            1,
+            std::ptr::null_mut(),
+            0,
+            &mut returned,
+            std::ptr::null_mut(),
+        );
+    }
+}
+
+/// Whether the card's lock switch is set: the disk under a root such as
+/// `E:\` answers that it isn't writable because it's write-protected
+#[cfg(target_os = "windows")]
+pub fn is_write_protected(_device: &str, mount_point: &Path) -> bool {
+    let Some(volume) = win32::Volume::open(mount_point) else {
+        return false;
+    };
+    let mut returned = 0;
+    let writable = unsafe {
+        win32::DeviceIoControl(
+            volume.0,
+            win32::IOCTL_DISK_IS_WRITABLE,
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+            0,
+            &mut returned,
+            std::ptr::null_mut(),
+        )
+    } != 0;
+    !writable && std::io::Error::last_os_error().raw_os_error() == Some(win32::ERROR_WRITE_PROTECT)
+}
+
+/// The physical disk under a root such as `E:\`, as `\\.\PhysicalDrive2`;
+/// None for a volume spanning several
+#[cfg(target_os = "windows")]
+fn whole_disk(mount_point: &Path) -> Option<String> {
+    let volume = win32::Volume::open(mount_point)?;
+    let mut number = win32::StorageDeviceNumber::default();
+    let mut returned = 0;
+    let ok = unsafe {
+        win32::DeviceIoControl(
+            volume.0,
+            win32::IOCTL_STORAGE_GET_DEVICE_NUMBER,
+            std::ptr::null_mut(),
+            0,
+            (&mut number as *mut win32::StorageDeviceNumber).cast(),
+            std::mem::size_of::<win32::StorageDeviceNumber>() as u32,
+            &mut returned,
+            std::ptr::null_mut(),
+        )
+    };
+    (ok != 0).then(|| format!("\\\\.\\PhysicalDrive{}", number.device_number))
+}
+
+/// The bus of the disk under a root such as `E:\`, from the storage
+/// driver's device descriptor
+#[cfg(target_os = "windows")]
+fn bus(mount_point: &Path) -> Option<Bus> {
+    let volume = win32::Volume::open(mount_point)?;
+    let mut query = win32::StoragePropertyQuery {
+        property_id: win32::STORAGE_DEVICE_PROPERTY,
+        query_type: win32::PROPERTY_STANDARD_QUERY,
+        additional_parameters: [0],
+    };
+    let mut descriptor = win32::StorageDeviceDescriptor::default();
+    let mut returned = 0;
+    let ok = unsafe {
+        win32::DeviceIoControl(
+            volume.0,
+            win32::IOCTL_STORAGE_QUERY_PROPERTY,
+            (&mut query as *mut win32::StoragePropertyQuery).cast(),
+            std::mem::size_of::<win32::StoragePropertyQuery>() as u32,
+            (&mut descriptor as *mut win32::StorageDeviceDescriptor).cast(),
+            std::mem::size_of::<win32::StorageDeviceDescriptor>() as u32,
+            &mut returned,
+            std::ptr::null_mut(),
+        )
+    };
+    // A short answer still covers the bus type when the buffer holds it
+    if ok == 0 && (returned as usize) < std::mem::size_of::<win32::StorageDeviceDescriptor>() {
+        return None;
+    }
+    match descriptor.bus_type {
+        win32::BUS_TYPE_USB => Some(Bus::Usb),
+        win32::BUS_TYPE_SD | win32::BUS_TYPE_MMC => Some(Bus::Sd),
+        win32::BUS_TYPE_1394 => Some(Bus::Firewire),
+        win32::BUS_TYPE_SCSI
+        | win32::BUS_TYPE_ATAPI
+        | win32::BUS_TYPE_ATA
+        | win32::BUS_TYPE_RAID
+        | win32::BUS_TYPE_SAS
+        | win32::BUS_TYPE_SATA
+        | win32::BUS_TYPE_SPACES
+        | win32::BUS_TYPE_NVME => Some(Bus::Internal),
+        win32::BUS_TYPE_VIRTUAL | win32::BUS_TYPE_FILE_BACKED_VIRTUAL => Some(Bus::Virtual),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn is_read_only(mount_point: &Path) -> bool {
+    win32::volume_information(mount_point).is_some_and(|(_, _, flags)| flags & win32::FILE_READ_ONLY_VOLUME != 0)
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+pub fn is_read_only(_mount_point: &Path) -> bool {
+    false
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// `blkid` reads the label and UUID from the partition's superblock, or its
+/// cache of them when the device can't be opened without root
+#[cfg(target_os = "linux")]
+fn probe(device: &str, _mount_point: &Path) -> VolumeId {
+    use std::process::Command;
+
+    let Ok(output) = crate::watchdog::output(Command::new("blkid").args(["-o", "export", device]), crate::watchdog::PROBE_TIMEOUT) else {
+        return VolumeId::default();
+    };
+    let mut id = VolumeId::default();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        match line.split_once('=') {
+            Some(("LABEL", label)) => id.label = non_empty(label),
+            Some(("UUID", uuid)) => id.serial = non_empty(uuid),
+            _ => {}
+        }
+    }
+    id
+}
+
+/// `diskutil info` gives the DiskArbitration volume name and UUID; FAT and
+/// exFAT cards get a UUID derived from their serial
+#[cfg(target_os = "macos")]
+fn probe(_device: &str, mount_point: &Path) -> VolumeId {
+    use std::process::Command;
+
+    let Ok(output) = crate::watchdog::output(Command::new("diskutil").arg("info").arg(mount_point), crate::watchdog::PROBE_TIMEOUT) else {
+        return VolumeId::default();
+    };
+    let mut id = VolumeId::default();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        match line.split_once(':').map(|(key, value)| (key.trim(), value)) {
+            Some(("Volume Name", name)) => id.label = non_empty(name),
+            Some(("Volume UUID", uuid)) => id.serial = non_empty(uuid),
+            _ => {}
+        }
+    }
+    id
+}
+
+#[cfg(target_os = "windows")]
+fn probe(_device: &str, mount_point: &Path) -> VolumeId {
+    match win32::volume_information(mount_point) {
+        Some((label, serial, _)) => VolumeId {
+            label: non_empty(&label),
+            serial: Some(format!("{:04X}-{:04X}", serial >> 16, serial & 0xFFFF)),
+            ..Default::default()
+        },
+        None => VolumeId::default(),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn probe(_device: &str, _mount_point: &Path) -> VolumeId {
+    VolumeId::default()
+}
+
+/// The bus and whole disk of a volume
+#[cfg(target_os = "linux")]
+fn attachment(device: &str, _mount_point: &Path) -> (Option<Bus>, Option<String>) {
+    (bus(device), whole_disk(device))
+}
+
+#[cfg(target_os = "macos")]
+fn attachment(_device: &str, mount_point: &Path) -> (Option<Bus>, Option<String>) {
+    (crate::diskarb::bus(mount_point), crate::diskarb::whole_disk(mount_point))
+}
+
+#[cfg(target_os = "windows")]
+fn attachment(_device: &str, mount_point: &Path) -> (Option<Bus>, Option<String>) {
+    (bus(mount_point), whole_disk(mount_point))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn attachment(_device: &str, _mount_point: &Path) -> (Option<Bus>, Option<String>) {
+    (None, None)
+}
+
+/// The volume's serial as the OS hands it out without reading the disk,
+/// such as the name of its /dev/disk/by-uuid link; None where it can't
+#[cfg(target_os = "linux")]
+fn quick_serial(device: &str, _mount_point: &Path) -> Option<String> {
+    let device = std::fs::canonicalize(device).ok()?;
+    std::fs::read_dir("/dev/disk/by-uuid")
+        .ok()?
+        .flatten()
+        .find(|link| std::fs::canonicalize(link.path()).ok().as_ref() == Some(&device))
+        .map(|link| link.file_name().to_string_lossy().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn quick_serial(_device: &str, mount_point: &Path) -> Option<String> {
+    crate::diskarb::volume_uuid(mount_point)
+}
+
+#[cfg(target_os = "windows")]
+fn quick_serial(device: &str, mount_point: &Path) -> Option<String> {
+    // GetVolumeInformationW is already cheap
+    probe(device, mount_point).serial
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn quick_serial(_device: &str, _mount_point: &Path) -> Option<String> {
+    None
+}
+
+/// Device, mount point, capacity and quick serial of a volume that has been
+/// identified; identical cards put in the same reader one after the other
+/// differ only in the serial
+type VolumeKey = (String, PathBuf, u64, Option<String>);
+
+fn known() -> &'static Mutex<HashMap<VolumeKey, VolumeId>> {
+    static KNOWN: OnceLock<Mutex<HashMap<VolumeKey, VolumeId>>> = OnceLock::new();
+    KNOWN.get_or_init(Default::default)
+}
+
+/// Label, serial, bus and disk of the volume `device` mounted at `mount_point`. The drive
+/// list is built often, so answers are kept until another volume takes the
+/// mount point or the volume is removed.
+pub fn identify(device: &str, mount_point: &Path, capacity: u64) -> VolumeId {
+    let key = (device.to_string(), mount_point.to_path_buf(), capacity, quick_serial(device, mount_point));
+    if let Some(id) = known().lock().ok().and_then(|known| known.get(&key).cloned()) {
+        return id;
+    }
+    let (bus, disk) = attachment(device, mount_point);
+    let id = VolumeId { bus, disk, ..probe(device, mount_point) };
+    if let Ok(mut known) = known().lock() {
+        known.retain(|(_, known_mount, _, _), _| known_mount != mount_point);
+        known.insert(key, id.clone());
+    }
+    id
+}
+
+/// Drop what's known of the volume that was at `mount_point`, once it's
+/// removed, so the next card there is identified afresh
+pub fn forget(mount_point: &Path) {
+    if let Ok(mut known) = known().lock() {
+        known.retain(|(_, known_mount, _, _), _| known_mount != mount_point);
+    }
+}
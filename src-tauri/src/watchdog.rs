@@ -0,0 +1,101 @@
+// Running external tools (ffmpeg, ffprobe, udisksctl, shell hooks) with a
+// time limit, killing any that hang; a corrupt MP4 can keep ffmpeg busy forever
+use log::warn;
+use serde::Serialize;
+use std::io::Read;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Frame grabs for thumbnails
+pub const THUMBNAIL_TIMEOUT: Duration = Duration::from_secs(20);
+/// Duration and metadata probes
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Mounting and unmounting, which may wait on an authentication prompt
+pub const MOUNT_TIMEOUT: Duration = Duration::from_secs(120);
+/// Quick-formatting a card with `diskutil`
+#[cfg(target_os = "macos")]
+pub const FORMAT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Opening a folder in the file manager
+pub const OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+/// Post-import hooks, which may run whole backups
+pub const HOOK_TIMEOUT: Duration = Duration::from_secs(2 * 60 * 60);
+/// Encoding time-lapse previews from a few thousand stills, and trimming clips
+pub const ENCODE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// Listing every object on a phone or camera, which MTP does one at a time
+pub const DEVICE_LIST_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// Downloading one file from a device, up to a long 4K clip over USB 2
+pub const DEVICE_TRANSFER_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// How often a running process is checked on
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProcessError {
+    /// The program couldn't be started
+    Spawn { program: String, message: String },
+    /// The program ran past its time limit and was killed
+    TimedOut { program: String, seconds: u64 },
+}
+
+impl std::fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessError::Spawn { program, message } => write!(f, "Failed to run {}: {}", program, message),
+            ProcessError::TimedOut { program, seconds } => write!(f, "{} timed out after {} s", program, seconds),
+        }
+    }
+}
+
+impl From<ProcessError> for String {
+    fn from(error: ProcessError) -> Self {
+        error.to_string()
+    }
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut data = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut data);
+        }
+        data
+    })
+}
+
+/// Like `Command::output`, but kills the process once `timeout` passes
+pub fn output(command: &mut Command, timeout: Duration) -> Result<Output, ProcessError> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ProcessError::Spawn { program: program.clone(), message: e.to_string() })?;
+
+    // Read both pipes while waiting, so a chatty process can't block on a full pipe
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < timeout => thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                warn!("Killing {} after {} s", program, timeout.as_secs());
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(ProcessError::TimedOut { program, seconds: timeout.as_secs() });
+            }
+            Err(e) => return Err(ProcessError::Spawn { program, message: e.to_string() }),
+        }
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
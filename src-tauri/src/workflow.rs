@@ -0,0 +1,272 @@
+// Named import presets (destination plus options such as rename templates and
+// conflict rules) and "workflow packs" that bundle them with shared settings
+// into a signed file, so a team can hand a standard ingest setup to
+// freelancers. Importing a pack merges it key by key, reporting conflicts.
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use crate::error::CamPorterError;
+use crate::import::ImportOptions;
+use crate::{config, replaced, scheduler};
+
+const PRESETS_KEY: &str = "import_presets";
+const TRUSTED_SIGNERS_KEY: &str = "trusted_pack_signers";
+const PACK_FORMAT: u32 = 1;
+
+/// Settings that travel in a pack alongside the presets
+const SHARED_SETTINGS: [&str; 3] = ["destination_path", replaced::RETENTION_KEY, scheduler::WINDOWS_KEY];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ImportPreset {
+    pub target_path: String,
+    pub options: ImportOptions,
+}
+
+fn load_presets(app: &tauri::AppHandle) -> Result<BTreeMap<String, ImportPreset>, String> {
+    Ok(config::get(app, PRESETS_KEY)?.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn list_import_presets(app: tauri::AppHandle) -> Result<BTreeMap<String, ImportPreset>, CamPorterError> {
+    Ok(load_presets(&app)?)
+}
+
+/// Add or replace a preset
+#[tauri::command]
+pub fn save_import_preset(name: String, preset: ImportPreset, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    let mut presets = load_presets(&app)?;
+    presets.insert(name, preset);
+    Ok(config::set(&app, PRESETS_KEY, &presets)?)
+}
+
+#[tauri::command]
+pub fn delete_import_preset(name: String, app: tauri::AppHandle) -> Result<(), CamPorterError> {
+    let mut presets = load_presets(&app)?;
+    presets.remove(&name);
+    Ok(config::set(&app, PRESETS_KEY, &presets)?)
+}
+
+/// What a pack carries; this is the part that is signed
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PackContents {
+    pub name: String,
+    pub created: u64, // Unix timestamp
+    pub presets: BTreeMap<String, ImportPreset>,
+    pub settings: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WorkflowPack {
+    format: u32,
+    contents: PackContents,
+    /// Hex-encoded ed25519 public key of whoever exported the pack
+    signer: String,
+    signature: String,
+}
+
+/// Stable bytes to sign: serde_json sorts object keys, so going through a
+/// Value gives the same text on every machine
+fn signed_bytes(contents: &PackContents) -> Result<Vec<u8>, String> {
+    let value = serde_json::to_value(contents).map_err(|e| e.to_string())?;
+    Ok(value.to_string().into_bytes())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect()
+}
+
+/// This installation's signing key, created on first export
+fn signing_key(app: &tauri::AppHandle) -> Result<SigningKey, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let path = app_data_dir.join("workflow_signing_key");
+
+    if let Ok(bytes) = fs::read(&path) {
+        let bytes: [u8; 32] = bytes.try_into().map_err(|_| "Signing key file is corrupt".to_string())?;
+        return Ok(SigningKey::from_bytes(&bytes));
+    }
+
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
+    fs::write(&path, bytes).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+    }
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// Write the presets and shared settings to `path` as a signed pack.
+/// Returns the signer's public key, for recipients to check against.
+#[tauri::command]
+pub fn export_workflow_pack(path: String, name: String, app: tauri::AppHandle) -> Result<String, CamPorterError> {
+    let mut settings = BTreeMap::new();
+    for key in SHARED_SETTINGS {
+        if let Some(value) = config::get::<Value>(&app, key)? {
+            settings.insert(key.to_string(), value);
+        }
+    }
+    let contents = PackContents {
+        name,
+        created: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        presets: load_presets(&app)?,
+        settings,
+    };
+
+    let key = signing_key(&app)?;
+    let signature = key.sign(&signed_bytes(&contents)?);
+    let signer = to_hex(key.verifying_key().as_bytes());
+    let pack = WorkflowPack {
+        format: PACK_FORMAT,
+        contents,
+        signer: signer.clone(),
+        signature: to_hex(&signature.to_bytes()),
+    };
+
+    fs::write(&path, serde_json::to_string_pretty(&pack).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+    info!("Exported workflow pack {} to {}", pack.contents.name, path);
+    Ok(signer)
+}
+
+/// What to do with a preset or setting that exists locally with a different value
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    #[default]
+    KeepLocal,
+    TakePack,
+    /// Import conflicting presets under a new name; settings keep the local value
+    KeepBoth,
+}
+
+#[derive(Serialize, Default)]
+pub struct PackImportReport {
+    pub name: String,
+    pub signer: String,
+    /// The signer was trusted before this import (or is this installation)
+    pub trusted: bool,
+    pub added: Vec<String>,
+    pub replaced: Vec<String>,
+    pub unchanged: Vec<String>,
+    /// Entries that differ locally, whatever the strategy did with them
+    pub conflicts: Vec<String>,
+}
+
+fn read_pack(path: &str) -> Result<WorkflowPack, String> {
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let pack: WorkflowPack = serde_json::from_str(&json).map_err(|e| format!("Not a workflow pack: {}", e))?;
+    if pack.format != PACK_FORMAT {
+        return Err(format!("Unsupported workflow pack format {}", pack.format));
+    }
+
+    let invalid = || "Workflow pack signature is invalid; it may have been altered".to_string();
+    let signer: [u8; 32] = from_hex(&pack.signer).and_then(|k| k.try_into().ok()).ok_or_else(invalid)?;
+    let signature: [u8; 64] = from_hex(&pack.signature).and_then(|s| s.try_into().ok()).ok_or_else(invalid)?;
+    VerifyingKey::from_bytes(&signer)
+        .map_err(|_| invalid())?
+        .verify(&signed_bytes(&pack.contents)?, &Signature::from_bytes(&signature))
+        .map_err(|_| invalid())?;
+    Ok(pack)
+}
+
+/// Verify and merge a pack. With `dry_run` nothing is saved, so the report
+/// can be shown for confirmation first. Applying a pack trusts its signer.
+#[tauri::command]
+pub fn import_workflow_pack(
+    path: String,
+    strategy: Option<MergeStrategy>,
+    dry_run: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<PackImportReport, CamPorterError> {
+    let strategy = strategy.unwrap_or_default();
+    let pack = read_pack(&path)?;
+
+    let mut trusted_signers: Vec<String> = config::get(&app, TRUSTED_SIGNERS_KEY)?.unwrap_or_default();
+    let own_key = signing_key(&app).map(|k| to_hex(k.verifying_key().as_bytes())).ok();
+    let mut report = PackImportReport {
+        name: pack.contents.name.clone(),
+        trusted: trusted_signers.contains(&pack.signer) || own_key.as_ref() == Some(&pack.signer),
+        signer: pack.signer.clone(),
+        ..Default::default()
+    };
+
+    let mut presets = load_presets(&app)?;
+    for (name, preset) in pack.contents.presets {
+        let Some(local) = presets.get(&name) else {
+            report.added.push(name.clone());
+            presets.insert(name, preset);
+            continue;
+        };
+        if serde_json::to_value(local).ok() == serde_json::to_value(&preset).ok() {
+            report.unchanged.push(name);
+            continue;
+        }
+        report.conflicts.push(name.clone());
+        match strategy {
+            MergeStrategy::KeepLocal => {}
+            MergeStrategy::TakePack => {
+                report.replaced.push(name.clone());
+                presets.insert(name, preset);
+            }
+            MergeStrategy::KeepBoth => {
+                let mut renamed = format!("{} ({})", name, pack.contents.name);
+                let mut n = 2;
+                while presets.contains_key(&renamed) {
+                    renamed = format!("{} ({} {})", name, pack.contents.name, n);
+                    n += 1;
+                }
+                report.added.push(renamed.clone());
+                presets.insert(renamed, preset);
+            }
+        }
+    }
+
+    let mut settings = Vec::new();
+    for (key, value) in pack.contents.settings {
+        // Only settings packs are meant to carry, whatever the file contains
+        if !SHARED_SETTINGS.contains(&key.as_str()) {
+            continue;
+        }
+        match config::get::<Value>(&app, &key)? {
+            None => {
+                report.added.push(key.clone());
+                settings.push((key, value));
+            }
+            Some(local) if local == value => report.unchanged.push(key),
+            Some(_) => {
+                report.conflicts.push(key.clone());
+                if strategy == MergeStrategy::TakePack {
+                    report.replaced.push(key.clone());
+                    settings.push((key, value));
+                }
+            }
+        }
+    }
+
+    if !dry_run.unwrap_or(false) {
+        config::set(&app, PRESETS_KEY, &presets)?;
+        for (key, value) in settings {
+            config::set(&app, &key, &value)?;
+        }
+        if !report.trusted {
+            trusted_signers.push(pack.signer);
+            config::set(&app, TRUSTED_SIGNERS_KEY, &trusted_signers)?;
+        }
+        info!("Imported workflow pack {}", report.name);
+    }
+    Ok(report)
+}
@@ -0,0 +1,113 @@
+// One temp folder per app session for everything short-lived: preview
+// copies, RAW previews, thumbnail frames and ffmpeg scratch files. Scratch
+// folders go away when the work using them ends, the whole session folder on
+// exit, and the folders of sessions that were killed are swept at startup.
+use log::{info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+use sysinfo::{Pid, System};
+
+/// Most the cached preview files may take up; the oldest are dropped first
+const FILES_QUOTA: u64 = 4 * 1024 * 1024 * 1024;
+
+static SESSION_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Parent of every session's folder
+fn root() -> PathBuf {
+    std::env::temp_dir().join("camporter")
+}
+
+fn session_dir() -> Result<&'static Path, String> {
+    let dir = SESSION_DIR.get_or_init(|| root().join(std::process::id().to_string()));
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create temp workspace: {}", e))?;
+    Ok(dir)
+}
+
+/// Remove the folders of sessions whose process is gone; called once from setup
+pub fn init() {
+    let Ok(entries) = fs::read_dir(root()) else {
+        return;
+    };
+    let mut system = System::new();
+    system.refresh_processes();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let pid = entry.file_name().to_string_lossy().parse::<usize>().ok();
+        if pid.is_some_and(|pid| system.process(Pid::from(pid)).is_some()) {
+            continue;
+        }
+        match fs::remove_dir_all(entry.path()) {
+            Ok(()) => info!("Removed stale temp workspace {}", entry.path().display()),
+            Err(e) => warn!("Failed to remove stale temp workspace {}: {}", entry.path().display(), e),
+        }
+    }
+}
+
+/// Remove this session's folder; called when the app exits
+pub fn cleanup() {
+    if let Some(dir) = SESSION_DIR.get() {
+        if let Err(e) = fs::remove_dir_all(dir) {
+            warn!("Failed to remove temp workspace {}: {}", dir.display(), e);
+        }
+    }
+}
+
+/// A scratch folder removed when dropped, whether the work succeeded or not
+pub struct Scratch(PathBuf);
+
+impl Scratch {
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn scratch() -> Result<Scratch, String> {
+    let dir = session_dir()?.join("scratch");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create temp dir: {}", e))?;
+    tempfile::Builder::new()
+        .tempdir_in(&dir)
+        .map(|d| Scratch(d.keep()))
+        .map_err(|e| format!("Failed to create temp dir: {}", e))
+}
+
+/// Path for a file kept for the rest of the session, such as a preview copy
+/// the webview reads back, after making room for `size` more bytes
+pub fn file(name: &str, size: u64) -> Result<PathBuf, String> {
+    let dir = session_dir()?.join("files");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create temp dir: {}", e))?;
+    let path = dir.join(name);
+    let _ = fs::remove_file(&path);
+    make_room(&dir, size);
+    Ok(path)
+}
+
+/// Drop the oldest files until `incoming` more bytes fit in the quota
+fn make_room(dir: &Path, incoming: u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), e.path()))
+        })
+        .collect();
+    files.sort();
+    let mut used: u64 = files.iter().map(|(_, size, _)| size).sum();
+    for (_, size, path) in files {
+        if used + incoming <= FILES_QUOTA {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            used -= size;
+        }
+    }
+}
@@ -0,0 +1,25 @@
+import type { CamPorterError } from '../types/bindings/CamPorterError';
+
+function isCamPorterError(err: unknown): err is CamPorterError {
+  return typeof err === 'object' && err !== null && 'kind' in err;
+}
+
+/**
+ * Readable text for an error thrown by `invoke`. Commands reject with a
+ * `CamPorterError` tagged by `kind`; switch on that for targeted recovery.
+ */
+export function errorMessage(err: unknown): string {
+  if (!isCamPorterError(err)) {
+    return String(err);
+  }
+  switch (err.kind) {
+    case 'insufficient_space':
+      return `Insufficient space in destination (${err.missing} more bytes needed)`;
+    case 'timed_out':
+      return `${err.program} timed out after ${err.seconds} s`;
+    case 'tool_failed':
+      return `Failed to run ${err.program}: ${err.message}`;
+    default:
+      return err.message;
+  }
+}